@@ -1,6 +1,6 @@
 use edigeo::*;
 
-use std::{str::FromStr, time::Instant};
+use std::time::Instant;
 
 fn main() {
     let now = Instant::now();
@@ -15,11 +15,11 @@ fn main() {
 
     for line in thf.lines() {
         if !line.is_empty() {
-            let d = Line::parse_line(line);
-            // println!("{:?}", d);
+            let _d = Line::parse_line(line);
+            // println!("{:?}", _d);
         }
     }
-    let blocks = THFFile::parse(thf);
+    let blocks = THFFile::parse(thf).unwrap();
     println!("{:?}", blocks);
 
     let elapsed = now.elapsed();
@@ -0,0 +1,329 @@
+//! Contains the unpacking of the archives EDIGéO lots are delivered in:
+//! `.tar.bz2`, `.tar.gz`, `.tar` and `.zip`, possibly nested in one another
+//! as in department dumps holding one `.tar.bz2` per sheet.
+//!
+//! Formats are detected from their magic bytes, not from file names.
+use crate::bundle::EdigeoBundle;
+use crate::diagnostics::{Diagnostic, DiagnosticKind, DiagnosticSink, Severity};
+use crate::error::{EdigeoError, EdigeoResult};
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use tar::Archive;
+use zip::ZipArchive;
+
+/// Deepest nesting of archives that is unpacked.
+const MAX_DEPTH: usize = 8;
+
+/// Default limit of the bytes unpacked from an archive, 4 GiB, against
+/// archives crafted to expand without bound.
+///
+/// The functions returning every file or bundle of an archive hold them all
+/// in memory and apply it; [`for_each_bundle`] takes its own limit for the
+/// dumps of large departments.
+pub const MAX_UNPACKED_SIZE: u64 = 4 << 30;
+
+/// Container or compression format of some bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    /// `bzip2` compressed stream, starting with `BZh`
+    Bzip2,
+    /// `gzip` compressed stream, starting with `1F 8B`
+    Gzip,
+    /// `zip` archive, starting with `PK`
+    Zip,
+    /// POSIX `tar` archive, with `ustar` at offset 257
+    Tar,
+}
+
+impl ArchiveKind {
+    /// Detects the format of some bytes from their magic bytes, `None` for a
+    /// plain file.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        match data {
+            [b'B', b'Z', b'h', ..] => Some(Self::Bzip2),
+            [0x1F, 0x8B, ..] => Some(Self::Gzip),
+            [b'P', b'K', 3, 4, ..] | [b'P', b'K', 5, 6, ..] => Some(Self::Zip),
+            _ if data.get(257..262) == Some(b"ustar") => Some(Self::Tar),
+            _ => None,
+        }
+    }
+}
+
+/// Something met while walking through an archive.
+enum Event {
+    /// A plain file with its path
+    File(String, Vec<u8>),
+    /// The end of the tar or zip archive with the given path
+    End(String),
+}
+
+/// The bytes that may still be unpacked from an archive.
+struct Budget<'a> {
+    name: &'a str,
+    max_size: u64,
+    remaining: u64,
+}
+
+impl Budget<'_> {
+    /// Reads a stream to its end, failing once the budget is spent.
+    fn read(&mut self, reader: &mut dyn Read) -> EdigeoResult<Vec<u8>> {
+        let mut data = Vec::new();
+        reader.take(self.remaining + 1).read_to_end(&mut data)?;
+        self.remaining = self
+            .remaining
+            .checked_sub(data.len() as u64)
+            .ok_or_else(|| {
+                EdigeoError::InvalidArchive(format!(
+                    "{} unpacks to more than {} bytes",
+                    self.name, self.max_size
+                ))
+            })?;
+        Ok(data)
+    }
+}
+
+/// Unpacks an archive into its plain files, recursing into nested archives.
+///
+/// Each file is returned with its path, made of the names of the archives
+/// holding it and its own path in the innermost one, separated by `/`.
+/// Fails with [`EdigeoError::InvalidArchive`] beyond [`MAX_UNPACKED_SIZE`].
+pub fn unpack(name: &str, data: Vec<u8>) -> EdigeoResult<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    walk_archive(name, &mut &data[..], MAX_UNPACKED_SIZE, &mut |event| {
+        if let Event::File(path, content) = event {
+            files.push((path, content));
+        }
+        Ok(())
+    })?;
+    Ok(files)
+}
+
+/// Walks through an archive stream, reading one entry at a time.
+///
+/// Plain files and `zip` archives, which are read whole to find their
+/// central directory, count towards `max_size`.
+fn walk_archive(
+    name: &str,
+    reader: &mut dyn Read,
+    max_size: u64,
+    visit: &mut dyn FnMut(Event) -> EdigeoResult<()>,
+) -> EdigeoResult<()> {
+    let mut budget = Budget {
+        name,
+        max_size,
+        remaining: max_size,
+    };
+    walk(name, reader, 0, &mut budget, visit)
+}
+
+fn walk(
+    name: &str,
+    reader: &mut dyn Read,
+    depth: usize,
+    budget: &mut Budget,
+    visit: &mut dyn FnMut(Event) -> EdigeoResult<()>,
+) -> EdigeoResult<()> {
+    // The magic bytes of a tar archive end at offset 262.
+    let mut head = Vec::with_capacity(512);
+    reader.take(512).read_to_end(&mut head)?;
+    let kind = ArchiveKind::detect(&head);
+    let mut reader = Cursor::new(head).chain(reader);
+    let Some(kind) = kind else {
+        return visit(Event::File(name.to_string(), budget.read(&mut reader)?));
+    };
+    if depth == MAX_DEPTH {
+        return Err(EdigeoError::InvalidArchive(format!(
+            "{name} is nested more than {MAX_DEPTH} archives deep"
+        )));
+    }
+
+    match kind {
+        ArchiveKind::Bzip2 | ArchiveKind::Gzip => {
+            let mut unpacked: Box<dyn Read> = match kind {
+                ArchiveKind::Bzip2 => Box::new(BzDecoder::new(reader)),
+                _ => Box::new(GzDecoder::new(reader)),
+            };
+            let stem = name
+                .rsplit_once('.')
+                .map_or(name, |(stem, _extension)| stem);
+            walk(stem, &mut unpacked, depth + 1, budget, visit)?;
+        }
+        ArchiveKind::Tar => {
+            let mut archive = Archive::new(reader);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let path = format!("{name}/{}", entry.path()?.to_string_lossy());
+                walk(&path, &mut entry, depth + 1, budget, visit)?;
+            }
+            visit(Event::End(name.to_string()))?;
+        }
+        ArchiveKind::Zip => {
+            let mut archive = ZipArchive::new(Cursor::new(budget.read(&mut reader)?))
+                .map_err(|error| EdigeoError::InvalidArchive(error.to_string()))?;
+            for index in 0..archive.len() {
+                let mut entry = archive
+                    .by_index(index)
+                    .map_err(|error| EdigeoError::InvalidArchive(error.to_string()))?;
+                if !entry.is_file() {
+                    continue;
+                }
+                let path = format!("{name}/{}", entry.name());
+                walk(&path, &mut entry, depth + 1, budget, visit)?;
+            }
+            visit(Event::End(name.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpacks an archive and groups its files into one bundle per directory,
+/// keeping only complete bundles, in path order.
+pub fn read_bundles(name: &str, data: Vec<u8>) -> EdigeoResult<Vec<EdigeoBundle>> {
+    read_bundles_with(name, data, &mut ())
+}
+
+/// Reads the bundles of an archive like [`read_bundles`], reporting every
+/// directory holding EDIGéO files but missing a mandatory one, which is
+/// skipped, as an [`EdigeoError::IncompleteBundle`] error to the sink.
+pub fn read_bundles_with(
+    name: &str,
+    data: Vec<u8>,
+    diagnostics: &mut dyn DiagnosticSink,
+) -> EdigeoResult<Vec<EdigeoBundle>> {
+    collect_bundles(name, &data[..], diagnostics)
+}
+
+/// Reads every bundle of an archive stream with [`for_each_bundle`] and
+/// [`MAX_UNPACKED_SIZE`], in path order.
+pub(crate) fn collect_bundles<R: Read>(
+    name: &str,
+    reader: R,
+    diagnostics: &mut dyn DiagnosticSink,
+) -> EdigeoResult<Vec<EdigeoBundle>> {
+    let mut bundles = Vec::new();
+    for_each_bundle(name, reader, MAX_UNPACKED_SIZE, diagnostics, |bundle| {
+        bundles.push(bundle);
+        Ok(())
+    })?;
+    bundles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(bundles)
+}
+
+/// Streams the bundles of an archive to `f`, reading one entry at a time.
+///
+/// The bundles of a nested archive, such as a sheet of a department dump,
+/// are passed as soon as it has been read, so that only one sheet is held
+/// in memory; those of the outermost archive once it has been read whole.
+/// Incomplete bundles are reported like [`read_bundles_with`] does. The
+/// walk stops at the first error of `f`, and fails with
+/// [`EdigeoError::InvalidArchive`] when more than `max_size` bytes are
+/// unpacked.
+pub fn for_each_bundle<R: Read>(
+    name: &str,
+    mut reader: R,
+    max_size: u64,
+    diagnostics: &mut dyn DiagnosticSink,
+    mut f: impl FnMut(EdigeoBundle) -> EdigeoResult<()>,
+) -> EdigeoResult<()> {
+    // Bundles by directory, with whether they hold any file of a lot.
+    let mut bundles: BTreeMap<String, (EdigeoBundle, bool)> = BTreeMap::new();
+    walk_archive(name, &mut reader, max_size, &mut |event| match event {
+        Event::File(path, content) => {
+            let (directory, file) = path.rsplit_once('/').unwrap_or(("", &path));
+            let (bundle, found) = bundles.entry(directory.to_string()).or_default();
+            *found |= bundle.insert(file, content);
+            if bundle.name.is_none() && !directory.is_empty() {
+                bundle.name = Some(directory.to_string());
+            }
+            Ok(())
+        }
+        Event::End(archive) => {
+            let read: BTreeMap<_, _>;
+            (read, bundles) =
+                std::mem::take(&mut bundles)
+                    .into_iter()
+                    .partition(|(directory, _)| {
+                        directory
+                            .strip_prefix(archive.as_str())
+                            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+                    });
+            pass_bundles(name, read, diagnostics, &mut f)
+        }
+    })?;
+    pass_bundles(name, bundles, diagnostics, &mut f)
+}
+
+/// Passes the complete bundles of some directories to `f` and reports the
+/// incomplete ones holding EDIGéO files.
+fn pass_bundles(
+    name: &str,
+    bundles: BTreeMap<String, (EdigeoBundle, bool)>,
+    diagnostics: &mut dyn DiagnosticSink,
+    f: &mut dyn FnMut(EdigeoBundle) -> EdigeoResult<()>,
+) -> EdigeoResult<()> {
+    for (directory, (bundle, found)) in bundles {
+        if bundle.is_completed() {
+            f(bundle)?;
+        } else if found {
+            diagnostics.report(Diagnostic::new(
+                Severity::Error,
+                if directory.is_empty() {
+                    name
+                } else {
+                    &directory
+                },
+                None,
+                DiagnosticKind::Recovered {
+                    error: EdigeoError::IncompleteBundle.to_string(),
+                },
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(ArchiveKind::detect(b"BZh91AY&SY"), Some(ArchiveKind::Bzip2));
+        assert_eq!(
+            ArchiveKind::detect(&[0x1F, 0x8B, 8]),
+            Some(ArchiveKind::Gzip)
+        );
+        assert_eq!(ArchiveKind::detect(b"PK\x03\x04"), Some(ArchiveKind::Zip));
+        assert_eq!(ArchiveKind::detect(b"BOMT 12:E0000A01.THF"), None);
+
+        let mut tar = vec![0; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(ArchiveKind::detect(&tar), Some(ArchiveKind::Tar));
+    }
+
+    #[test]
+    fn test_max_size() {
+        use std::io::Write;
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gz.write_all(&[0; 1 << 20]).unwrap();
+        let gz = gz.finish().unwrap();
+
+        let mut files = 0;
+        let mut count = |_| {
+            files += 1;
+            Ok(())
+        };
+        assert!(matches!(
+            walk_archive("zeros.gz", &mut &gz[..], 1000, &mut count),
+            Err(EdigeoError::InvalidArchive(_))
+        ));
+        assert!(walk_archive("zeros.gz", &mut &gz[..], 1 << 20, &mut count).is_ok());
+        assert_eq!(files, 1);
+    }
+}
@@ -0,0 +1,449 @@
+//! Contains the conversion of lots into Apache Arrow record batches and the
+//! GeoParquet writer, enabled by the `arrow` feature.
+//!
+//! Each object type becomes a table with the record identifier, the lot and
+//! sheet names, the attributes listed by the SCD typed after their dictionary
+//! definition, and a `geometry` column of OGC WKB tagged as `geoarrow.wkb`
+//! with the PROJJSON of the lot's projection.
+//!
+//! The [`GeoParquetWriter`] streams any number of lots into one Parquet file
+//! per object type, e.g. a whole department queried from DuckDB:
+//!
+//! ```no_run
+//! use edigeo::{arrow::GeoParquetWriter, *};
+//!
+//! let mut writer = GeoParquetWriter::new("dep74/").unwrap();
+//! for path in ["edigeo-740240000A01.tar.bz2", "edigeo-740240000A02.tar.bz2"] {
+//!     let lot = Lot::from_bundle(&EdigeoReader::new(path).read_bundle()).unwrap();
+//!     writer.write_lot(&lot).unwrap();
+//! }
+//! let written = writer.finish().unwrap();
+//! ```
+use crate::error::{EdigeoError, EdigeoResult};
+use crate::geometry::{BoundingBox, Geometry};
+use crate::lot::{float, layer_name, ColumnNames, Feature, Lot};
+use crate::FormatResult;
+use arrow_array::builder::{
+    BinaryBuilder, Date32Builder, Float64Builder, Int64Builder, StringBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use chrono::NaiveDate;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use parquet::format::KeyValue;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Name of the geometry column.
+pub const GEOMETRY_COLUMN: &str = "geometry";
+
+/// An attribute column of the table of an object type.
+#[derive(Debug, Clone, PartialEq)]
+struct Column {
+    /// SCD identifier of the attribute, e.g. `SUPF_id`
+    attribute: String,
+    data_type: DataType,
+}
+
+/// Columns of an object type and the schema built from them, fixed by the
+/// first lot holding the type.
+#[derive(Debug, Clone)]
+struct Table {
+    schema: SchemaRef,
+    columns: Vec<Column>,
+}
+
+impl Table {
+    fn new(lot: &Lot, object_type: &str, features: &[Feature]) -> Self {
+        let mut names = ColumnNames::default();
+        let mut fields = vec![
+            Field::new(names.unique("ID"), DataType::Utf8, false),
+            Field::new(names.unique("LOT"), DataType::Utf8, false),
+            Field::new(names.unique("SHEET"), DataType::Utf8, true),
+        ];
+        let mut columns = Vec::new();
+        for column in lot.attribute_columns(object_type, features, &mut names) {
+            let data_type = match column.value_type.as_deref() {
+                Some("R" | "E") => DataType::Float64,
+                Some("N" | "I") => DataType::Int64,
+                Some("D") => DataType::Date32,
+                _ => DataType::Utf8,
+            };
+            fields.push(Field::new(column.name, data_type.clone(), true));
+            columns.push(Column {
+                attribute: column.id,
+                data_type,
+            });
+        }
+
+        let crs = lot
+            .projection()
+            .map(|projection| format!(r#"{{"crs":{}}}"#, projection.to_projjson()));
+        let geometry = Field::new(names.unique(GEOMETRY_COLUMN), DataType::Binary, true)
+            .with_metadata(HashMap::from([
+                (
+                    "ARROW:extension:name".to_string(),
+                    "geoarrow.wkb".to_string(),
+                ),
+                (
+                    "ARROW:extension:metadata".to_string(),
+                    crs.unwrap_or_else(|| "{}".to_string()),
+                ),
+            ]));
+        fields.push(geometry);
+        Self {
+            schema: Arc::new(Schema::new(fields)),
+            columns,
+        }
+    }
+
+    /// Builds the batch of the features of a lot. Attributes missing from the
+    /// columns of the table are left out, and missing or unparsable values
+    /// are null.
+    fn batch(&self, lot: &Lot, features: &[Feature]) -> EdigeoResult<RecordBatch> {
+        let mut arrays: Vec<ArrayRef> = Vec::with_capacity(self.columns.len() + 4);
+
+        let mut ids = StringBuilder::new();
+        let mut lots = StringBuilder::new();
+        let mut sheets = StringBuilder::new();
+        let sheet = lot.sheet.as_ref().map(|sheet| sheet.to_string());
+        for feature in features {
+            ids.append_value(&feature.id);
+            lots.append_value(&lot.name);
+            sheets.append_option(sheet.as_deref());
+        }
+        arrays.push(Arc::new(ids.finish()));
+        arrays.push(Arc::new(lots.finish()));
+        arrays.push(Arc::new(sheets.finish()));
+
+        for column in &self.columns {
+            let values = features.iter().map(|f| f.attribute(&column.attribute));
+            let array: ArrayRef = match column.data_type {
+                DataType::Float64 => {
+                    let mut builder = Float64Builder::with_capacity(features.len());
+                    values.for_each(|value| builder.append_option(value.and_then(float)));
+                    Arc::new(builder.finish())
+                }
+                DataType::Int64 => {
+                    let mut builder = Int64Builder::with_capacity(features.len());
+                    values.for_each(|value| builder.append_option(value.and_then(integer)));
+                    Arc::new(builder.finish())
+                }
+                DataType::Date32 => {
+                    let mut builder = Date32Builder::with_capacity(features.len());
+                    values.for_each(|value| builder.append_option(value.and_then(days)));
+                    Arc::new(builder.finish())
+                }
+                _ => {
+                    let mut builder = StringBuilder::new();
+                    values.for_each(|value| {
+                        builder.append_option(value.map(|v| v.to_string().trim().to_string()))
+                    });
+                    Arc::new(builder.finish())
+                }
+            };
+            arrays.push(array);
+        }
+
+        let mut geometries = BinaryBuilder::new();
+        for feature in features {
+            geometries.append_option(feature.geometry.as_ref().map(Geometry::to_wkb));
+        }
+        arrays.push(Arc::new(geometries.finish()));
+
+        Ok(RecordBatch::try_new(self.schema.clone(), arrays)?)
+    }
+}
+
+fn integer(value: &FormatResult) -> Option<i64> {
+    match value {
+        FormatResult::Int(value) => Some(*value as i64),
+        FormatResult::Float(value) if value.fract() == 0.0 => Some(*value as i64),
+        value => value.to_string().trim().parse().ok(),
+    }
+}
+
+/// Days since the UNIX epoch of a date.
+fn days(value: &FormatResult) -> Option<i32> {
+    let FormatResult::Date(date) = value else {
+        return None;
+    };
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+    Some(date.signed_duration_since(epoch).num_days() as i32)
+}
+
+/// Converts lots into one Arrow [`RecordBatch`] per object type.
+///
+/// The schema of an object type is fixed by the first lot holding it, so
+/// that the batches of a stream of lots can be concatenated or written to
+/// the same file.
+/// ```ignore
+///     let mut converter = ArrowConverter::new();
+///     for lot in lots {
+///         for (object_type, batch) in converter.convert(&lot)? { ... }
+///     }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ArrowConverter {
+    tables: BTreeMap<String, Table>,
+}
+
+impl ArrowConverter {
+    /// Constructor method for creating a new [`ArrowConverter`] without any
+    /// known object type.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the schema of an object type, once a lot holding it was
+    /// converted.
+    pub fn schema(&self, object_type: &str) -> Option<SchemaRef> {
+        self.tables
+            .get(object_type)
+            .map(|table| table.schema.clone())
+    }
+
+    /// Converts the features of a lot, keyed by object type.
+    pub fn convert(&mut self, lot: &Lot) -> EdigeoResult<BTreeMap<String, RecordBatch>> {
+        lot.layers()
+            .into_iter()
+            .map(|(object_type, features)| {
+                let batch = self.batch(lot, &object_type, &features)?;
+                Ok((object_type, batch))
+            })
+            .collect()
+    }
+
+    /// Converts the features of one object type of a lot.
+    pub fn batch(
+        &mut self,
+        lot: &Lot,
+        object_type: &str,
+        features: &[Feature],
+    ) -> EdigeoResult<RecordBatch> {
+        self.tables
+            .entry(object_type.to_string())
+            .or_insert_with(|| Table::new(lot, object_type, features))
+            .batch(lot, features)
+    }
+}
+
+impl Lot {
+    /// Converts the features of the lot into one Arrow [`RecordBatch`] per
+    /// object type, see [`ArrowConverter`] for a stream of lots.
+    pub fn to_record_batches(&self) -> EdigeoResult<BTreeMap<String, RecordBatch>> {
+        ArrowConverter::new().convert(self)
+    }
+}
+
+/// GeoParquet name of the type of a geometry.
+fn geometry_type(geometry: &Geometry) -> &'static str {
+    match geometry {
+        Geometry::Point(_) => "Point",
+        Geometry::MultiPoint(_) => "MultiPoint",
+        Geometry::LineString(_) => "LineString",
+        Geometry::MultiLineString(_) => "MultiLineString",
+        Geometry::Polygon(_) => "Polygon",
+        Geometry::MultiPolygon(_) => "MultiPolygon",
+    }
+}
+
+/// The Parquet file of an object type being written, with the statistics of
+/// its geometries.
+struct ParquetTable {
+    path: PathBuf,
+    writer: ArrowWriter<File>,
+    crs: Option<String>,
+    geometry_types: BTreeSet<&'static str>,
+    bbox: Option<BoundingBox>,
+}
+
+impl ParquetTable {
+    /// The GeoParquet `geo` metadata of the file.
+    fn metadata(&self) -> String {
+        let types: Vec<_> = self
+            .geometry_types
+            .iter()
+            .map(|kind| format!("\"{kind}\""))
+            .collect();
+        let bbox = match &self.bbox {
+            Some(b) => format!(
+                r#","bbox":[{:?},{:?},{:?},{:?}]"#,
+                b.min.x, b.min.y, b.max.x, b.max.y
+            ),
+            None => String::new(),
+        };
+        format!(
+            r#"{{"version":"1.1.0","primary_column":"{GEOMETRY_COLUMN}","columns":{{"{GEOMETRY_COLUMN}":{{"encoding":"WKB","geometry_types":[{}],"crs":{}{bbox}}}}}}}"#,
+            types.join(","),
+            self.crs.as_deref().unwrap_or("null"),
+        )
+    }
+}
+
+/// Writes lots as one GeoParquet file per object type (`PARCELLE.parquet`,
+/// `BATIMENT.parquet`...), compressed with Snappy.
+///
+/// The files are complete once [`GeoParquetWriter::finish`] has written
+/// their footers. They hold one CRS, the projection of the first lot.
+pub struct GeoParquetWriter {
+    dir: PathBuf,
+    converter: ArrowConverter,
+    tables: BTreeMap<String, ParquetTable>,
+    /// Name and SRID of the first lot written
+    first: Option<(String, Option<u32>)>,
+}
+
+impl GeoParquetWriter {
+    /// Constructor method for creating a new [`GeoParquetWriter`] writing
+    /// into `dir`, created if needed.
+    pub fn new<P: AsRef<Path>>(dir: P) -> EdigeoResult<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            converter: ArrowConverter::new(),
+            tables: BTreeMap::new(),
+            first: None,
+        })
+    }
+
+    /// Appends the features of a lot to the files of their object types.
+    ///
+    /// Fails with [`EdigeoError::IncompatibleLots`] when the projection of
+    /// the lot differs from that of the first lot written.
+    pub fn write_lot(&mut self, lot: &Lot) -> EdigeoResult<()> {
+        match &self.first {
+            Some((name, srid)) if *srid != lot.srid() => {
+                return Err(EdigeoError::IncompatibleLots(format!(
+                    "{name} and {} have different projections",
+                    lot.name
+                )));
+            }
+            Some(_) => {}
+            None => self.first = Some((lot.name.clone(), lot.srid())),
+        }
+        for (object_type, features) in lot.layers() {
+            let batch = self.converter.batch(lot, &object_type, &features)?;
+            let table = match self.tables.get_mut(&object_type) {
+                Some(table) => table,
+                None => {
+                    let path = self
+                        .dir
+                        .join(layer_name(&object_type))
+                        .with_extension("parquet");
+                    let properties = WriterProperties::builder()
+                        .set_compression(Compression::SNAPPY)
+                        .build();
+                    let writer = ArrowWriter::try_new(
+                        File::create(&path)?,
+                        batch.schema(),
+                        Some(properties),
+                    )?;
+                    let table = ParquetTable {
+                        path,
+                        writer,
+                        crs: lot.projection().map(|projection| projection.to_projjson()),
+                        geometry_types: BTreeSet::new(),
+                        bbox: None,
+                    };
+                    self.tables.entry(object_type.clone()).or_insert(table)
+                }
+            };
+            for geometry in features.iter().filter_map(|f| f.geometry.as_ref()) {
+                table.geometry_types.insert(geometry_type(geometry));
+                if let Some(bbox) = geometry.bbox() {
+                    table.bbox = Some(table.bbox.map_or(bbox, |b| b.expand(&bbox)));
+                }
+            }
+            table.writer.write(&batch)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the GeoParquet metadata and footer of every file, and returns
+    /// their paths.
+    pub fn finish(self) -> EdigeoResult<Vec<PathBuf>> {
+        self.tables
+            .into_values()
+            .map(|mut table| {
+                let metadata = table.metadata();
+                table
+                    .writer
+                    .append_key_value_metadata(KeyValue::new("geo".to_string(), metadata));
+                table.writer.close().map_err(EdigeoError::from)?;
+                Ok(table.path)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{coord, feature, text};
+    use arrow_array::{Array, StringArray};
+
+    #[test]
+    fn test_batch() {
+        let point = Some(Geometry::Point(coord(1.0, 2.0)));
+        let mut features = [
+            feature(
+                "Objet_1",
+                "BORNE_id",
+                &[("TEX_id", text("12 "))],
+                point.clone(),
+            ),
+            feature("Objet_2", "BORNE_id", &[("TEX_id", text(""))], point),
+        ];
+        features[1].attributes[0].value = None;
+        let lot = Lot {
+            name: "ED0A01".to_string(),
+            ..Lot::default()
+        };
+
+        let mut converter = ArrowConverter::new();
+        let batch = converter.batch(&lot, "BORNE_id", &features).unwrap();
+        let names: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect();
+        assert_eq!(names, ["ID", "LOT", "SHEET", "TEX", "geometry"]);
+        assert_eq!(batch.num_rows(), 2);
+        let text = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(text.value(0), "12");
+        assert!(text.is_null(1));
+        assert!(batch.column(2).is_null(0));
+        let geometry = batch.schema().field(4).clone();
+        assert_eq!(geometry.metadata()["ARROW:extension:name"], "geoarrow.wkb");
+
+        // The next lots keep the columns of the first one.
+        let other = [feature(
+            "Objet_1",
+            "BORNE_id",
+            &[("SUPF_id", FormatResult::Int(3))],
+            features[0].geometry.clone(),
+        )];
+        let batch = converter.batch(&lot, "BORNE_id", &other).unwrap();
+        assert_eq!(batch.schema(), converter.schema("BORNE_id").unwrap());
+        assert!(batch.column(3).is_null(0));
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(float(&FormatResult::Text(" 1.5".to_string())), Some(1.5));
+        assert_eq!(integer(&FormatResult::Float(3.0)), Some(3));
+        assert_eq!(integer(&FormatResult::Float(3.5)), None);
+        let date = NaiveDate::from_ymd_opt(1970, 1, 11).unwrap();
+        assert_eq!(days(&FormatResult::Date(date)), Some(10));
+    }
+}
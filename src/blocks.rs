@@ -1,33 +1,64 @@
 //! Contains all logic for processing **Blocks**.
-use crate::{decode_file, Code, FormatResult, KeyWordCode, Line};
-use crate::{Code, FormatResult, KeyWordCode, Line};
-use std::path::PathBuf;
+use crate::diagnostics::{Diagnostic, DiagnosticKind, DiagnosticSink, Severity};
+use crate::error::*;
+use crate::reader::check_encoding;
+use crate::{Code, FormatResult, KeyWordCode, Line, ValueFormat, ValueSize, ZoneName};
+use std::fmt;
+
+/// A single EDIGéO descriptor, starting at an `RTY` line and holding every
+/// zone line that follows it until the next `RTY` or `EOM`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Block {
+    id: String,
+    line: usize,
+    entries: Vec<Line>,
+}
 
-/// Block Identifiers
-#[derive(Debug)]
-enum BlockIdent {
-    /// Indentifier value for THF Support Descriptor Block
-    GTS,
-    /// Indentifier value for THF Batch Descriptor Block
-    GTL,
-    // /// Indentifier value for GEN Geographical Descriptor Block
-    // DEG,
-    // /// Indentifier value for GEN Geographical Data Block
-    // GSE,
-    // /// Indentifier value for GEO Geographical Data Block
-    // GEO,
+/// Count zones paired with the zone they count.
+const COUNTS: [(ZoneName, ZoneName); 7] = [
+    (ZoneName::ATC, ZoneName::ATP),
+    (ZoneName::PTC, ZoneName::COR),
+    (ZoneName::FTC, ZoneName::FTP),
+    (ZoneName::AAC, ZoneName::AAP),
+    (ZoneName::SCC, ZoneName::SCP),
+    (ZoneName::GDC, ZoneName::GDN),
+    (ZoneName::QAC, ZoneName::QAP),
+];
+
+/// A count zone of a block that differs from the number of values it counts.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CountMismatch {
+    /// Line number of the `RTY` line opening the block, starting at 1
+    pub line: usize,
+    /// Record identifier of the block
+    pub rid: String,
+    /// The count zone, e.g. `PTC`
+    pub count: ZoneName,
+    /// The counted zone, e.g. `COR`
+    pub zone: ZoneName,
+    /// Count declared by the count zone
+    pub declared: i32,
+    /// Number of counted zones found in the block
+    pub found: usize,
 }
 
-#[derive(Debug)]
-struct Block {
-    _id: String,
-    entries: Vec<Line>,
+impl fmt::Display for CountMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} ({}): {:?} declares {} {:?}, {} found",
+            self.line, self.rid, self.count, self.declared, self.zone, self.found
+        )
+    }
 }
 
 impl Block {
-    fn new(id: &str) -> Self {
+    fn new(id: &str, line: usize) -> Self {
         Self {
-            _id: id.to_owned(),
+            id: id.to_owned(),
+            line,
             entries: Vec::new(),
         }
     }
@@ -35,428 +66,347 @@ impl Block {
     fn add_line(&mut self, line: Line) {
         self.entries.push(line);
     }
-}
 
-pub trait BlockParse {
-    fn parse<S: AsRef<str>>(lines: S) -> Self;
-}
+    /// Appends a `NEX` continuation value to the previous line of the block.
+    fn continue_line(&mut self, line: Line) {
+        if let (Some(last), Some(FormatResult::Text(rest))) =
+            (self.entries.last_mut(), line.parsed_value)
+        {
+            last.raw_value.push_str(&line.raw_value);
+            if let Some(FormatResult::Text(value)) = &mut last.parsed_value {
+                value.push_str(&rest);
+            }
+        }
+    }
 
-#[derive(Debug)]
-pub struct THFFile {
-    support_block: Block,
-    batch_block: Block,
+    /// Returns the descriptor type of the block (the `RTY` value, e.g. `PAR`).
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the line number of the `RTY` line opening the block, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns all zone lines of the block in file order.
+    pub fn entries(&self) -> &[Line] {
+        &self.entries
+    }
+
+    /// Returns the first line with the given zone name.
+    pub fn get(&self, zone: ZoneName) -> Option<&Line> {
+        self.all(zone).next()
+    }
+
+    /// Returns every line with the given zone name.
+    pub fn all(&self, zone: ZoneName) -> impl Iterator<Item = &Line> {
+        self.entries
+            .iter()
+            .filter(move |line| line.header.code == Code::ZoneCode(zone.clone()))
+    }
+
+    /// Returns the parsed value of the first line with the given zone name.
+    pub fn value(&self, zone: ZoneName) -> Option<&FormatResult> {
+        self.get(zone).and_then(|line| line.parsed_value.as_ref())
+    }
+
+    /// Returns the record identifier (`RID`) of the block.
+    pub fn rid(&self) -> String {
+        self.text(ZoneName::RID).unwrap_or_default()
+    }
+
+    /// Returns the first value of the given zone as text, if it is not empty.
+    pub fn text(&self, zone: ZoneName) -> Option<String> {
+        match self.value(zone)? {
+            FormatResult::Text(value) if !value.is_empty() => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the first value of the given zone as an integer.
+    pub fn int(&self, zone: ZoneName) -> Option<i32> {
+        match self.value(zone)? {
+            FormatResult::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Checks the `ATC`, `PTC`, `FTC`, `AAC`, `SCC`, `GDC` and `QAC` counts of
+    /// the block against the number of `ATP`, `COR`, `FTP`, `AAP`, `SCP`,
+    /// `GDN` and `QAP` zones that follow them.
+    ///
+    /// A truncated transfer usually shows up as a mismatch in its last block.
+    pub fn check_counts(&self) -> Vec<CountMismatch> {
+        COUNTS
+            .iter()
+            .filter_map(|(count, zone)| {
+                let declared = self.int(count.clone())?;
+                let found = self.all(zone.clone()).count();
+                (declared < 0 || declared as usize != found).then(|| CountMismatch {
+                    line: self.line,
+                    rid: self.rid(),
+                    count: count.clone(),
+                    zone: zone.clone(),
+                    declared,
+                    found,
+                })
+            })
+            .collect()
+    }
 }
 
-impl THFFile {
-    pub fn new(data: &[u8]) -> Self {
-        let data = decode_file(data);
-        let thf = THFFile::parse(data);
-        thf
+/// Trait implemented by every EDIGéO file model that can be built from the
+/// blocks of a file.
+pub trait BlockParse: Sized {
+    /// Builds `Self` from the blocks of a file.
+    fn from_blocks(blocks: Vec<Block>) -> Self;
+
+    /// Parses the decoded lines of a file into `Self`.
+    fn parse<S: AsRef<str>>(lines: S) -> EdigeoResult<Self> {
+        parse_blocks(lines).map(Self::from_blocks)
     }
 
-    pub fn is_empty(&self) -> bool {
-        todo!()
+    /// Parses the raw bytes of a file into `Self`, checking the size of every
+    /// value as [`ValueSize`] requires before decoding it.
+    fn parse_bytes(data: &[u8], value_size: ValueSize) -> EdigeoResult<Self> {
+        parse_blocks_bytes(data, value_size).map(Self::from_blocks)
+    }
+
+    /// Parses the raw bytes of a file into `Self` like
+    /// [`BlockParse::parse_bytes`], failing with
+    /// [`EdigeoError::CountMismatch`] on the first block whose counts do not
+    /// match its zones.
+    fn parse_bytes_checked(data: &[u8], value_size: ValueSize) -> EdigeoResult<Self> {
+        checked(parse_blocks_bytes(data, value_size)?).map(Self::from_blocks)
+    }
+
+    /// Parses the raw bytes of a file into `Self` without failing, see
+    /// [`parse_blocks_lenient`].
+    fn parse_lenient(
+        data: &[u8],
+        value_size: ValueSize,
+        file: &str,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Self {
+        Self::from_blocks(parse_blocks_lenient(data, value_size, file, diagnostics))
     }
 }
 
-impl BlockParse for THFFile {
-    fn parse<S: AsRef<str>>(lines: S) -> Self {
-        let mut support_block = Block::new("GTS");
-        let mut batch_block = Block::new("GTL");
-        let mut current_block: Option<&mut Block> = None;
-
-        for line in lines.as_ref().lines() {
-            if line.is_empty() {
-                continue;
+/// Groups parsed lines, numbered from 0, into blocks.
+fn collect_blocks<I>(lines: I) -> EdigeoResult<Vec<Block>>
+where
+    I: Iterator<Item = EdigeoResult<(usize, Line)>>,
+{
+    let mut blocks = Vec::new();
+    let mut current_block: Option<Block> = None;
+
+    for data in lines {
+        let (number, data) = data?;
+
+        match &data.header.code {
+            Code::KWCode(KeyWordCode::EOM) => blocks.extend(current_block.take()),
+            Code::KWCode(_) => {}
+            Code::TypeCode(_) => {
+                blocks.extend(current_block.take());
+                if let Some(FormatResult::Text(value)) = &data.parsed_value {
+                    current_block = Some(Block::new(value, number + 1));
+                }
             }
-            let data = Line::parse_line(&line);
-
-            match &data.header.code {
-                Code::KWCode(kwc) => match kwc {
-                    KeyWordCode::BOM => {}
-                    KeyWordCode::CSE => {}
-                    KeyWordCode::EOM => current_block = None,
-                },
-                Code::TypeCode(type_code) => match data.parsed_value {
-                    Some(FormatResult::Text(ref value)) if value == "GTS" => {
-                        current_block = Some(&mut support_block);
-                    }
-                    Some(FormatResult::Text(ref value)) if value == "GTL" => {
-                        current_block = Some(&mut batch_block);
-                    }
-                    _ => {}
-                },
-                Code::ZoneCode(zone_name) => {
-                    if let Some(block) = &mut current_block {
-                        block.add_line(data);
-                    }
+            Code::ZoneCode(ZoneName::NEX) => {
+                if let Some(block) = &mut current_block {
+                    block.continue_line(data);
+                }
+            }
+            Code::ZoneCode(_) => {
+                if let Some(block) = &mut current_block {
+                    block.add_line(data);
                 }
             }
-        }
-
-        Self {
-            support_block,
-            batch_block,
         }
     }
+    blocks.extend(current_block);
+    Ok(blocks)
 }
 
-// #[derive(Debug)]
-// pub struct GENFile {
-//     geographical_descriptor_block: Block,
-//     geographical_data_block: Block,
-// }
-
-// impl BlockParse for GENFile {
-//     fn parse<S: AsRef<str>>(lines: S) -> Self {
-//         let mut geographical_descriptor_block = Block::new("DEG");
-//         let mut geographical_data_block = Block::new("GSE");
-//         let mut current_block: Option<&mut Block> = None;
-
-//         for line in lines.as_ref().lines() {
-//             if line.is_empty() {
-//                 continue;
-//             }
-//             let data = Line::parse_line(&line);
-
-//             match data.header.code.as_str() {
-//                 "BOM" | "CSE" => {}
-//                 "RTY" => match data.parsed_value {
-//                     Some(FormatResult::Text(ref value)) if value == "DEG" => {
-//                         current_block = Some(&mut geographical_descriptor_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "GSE" => {
-//                         current_block = Some(&mut geographical_data_block);
-//                     }
-//                     _ => {}
-//                 },
-//                 "EOM" => current_block = None,
-//                 _ => {
-//                     if let Some(block) = &mut current_block {
-//                         block.add_line(data);
-//                     }
-//                 }
-//             }
-//         }
-
-//         Self {
-//             geographical_descriptor_block,
-//             geographical_data_block,
-//         }
-//     }
-// }
-
-// #[derive(Debug)]
-// pub struct GEOFile {
-//     geo_reference_block: Block,
-// }
-
-// impl BlockParse for GEOFile {
-//     fn parse<S: AsRef<str>>(lines: S) -> Self {
-//         let mut geo_reference_block = Block::new("GEO");
-//         let mut current_block: Option<&mut Block> = None;
-
-//         for line in lines.as_ref().lines() {
-//             if line.is_empty() {
-//                 continue;
-//             }
-//             let data = Line::parse_line(&line);
-
-//             match data.header.code.as_str() {
-//                 "BOM" => {}
-//                 "RTY" => match data.parsed_value {
-//                     Some(FormatResult::Text(ref value)) if value == "GEO" => {
-//                         current_block = Some(&mut geo_reference_block);
-//                     }
-//                     _ => {}
-//                 },
-//                 "EOM" => current_block = None,
-//                 _ => {
-//                     if let Some(block) = &mut current_block {
-//                         block.add_line(data);
-//                     }
-//                 }
-//             }
-//         }
-
-//         Self {
-//             geo_reference_block,
-//         }
-//     }
-// }
-
-// #[derive(Debug)]
-// pub struct DICFile {
-//     object_definition_block: Block,
-//     attribute_definition_block: Block,
-//     semantic_relationship_block: Block,
-// }
-
-// impl BlockParse for DICFile {
-//     fn parse<S: AsRef<str>>(lines: S) -> Self {
-//         let mut object_definition_block = Block::new("DID");
-//         let mut attribute_definition_block = Block::new("DIA");
-//         let mut semantic_relationship_block = Block::new("DIR");
-//         let mut current_block: Option<&mut Block> = None;
-
-//         for line in lines.as_ref().lines() {
-//             if line.is_empty() {
-//                 continue;
-//             }
-//             let data = Line::parse_line(&line);
-
-//             match data.header.code.as_str() {
-//                 "BOM" => {}
-//                 "RTY" => match data.parsed_value {
-//                     Some(FormatResult::Text(ref value)) if value == "DID" => {
-//                         current_block = Some(&mut object_definition_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "DIA" => {
-//                         current_block = Some(&mut attribute_definition_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "DIR" => {
-//                         current_block = Some(&mut semantic_relationship_block);
-//                     }
-//                     _ => {}
-//                 },
-//                 "EOM" => current_block = None,
-//                 _ => {
-//                     if let Some(block) = &mut current_block {
-//                         block.add_line(data);
-//                     }
-//                 }
-//             }
-//         }
-
-//         Self {
-//             object_definition_block,
-//             attribute_definition_block,
-//             semantic_relationship_block,
-//         }
-//     }
-// }
-
-// #[derive(Debug)]
-// pub struct SCDFile {
-//     object_type_block: Block,
-//     attribute_type_block: Block,
-//     primitive_type_block: Block,
-//     semantic_relationship_block: Block,
-//     construction_relationship_type_block: Block,
-// }
-
-// impl BlockParse for SCDFile {
-//     fn parse<S: AsRef<str>>(lines: S) -> Self {
-//         let mut object_type_block = Block::new("OBJ");
-//         let mut attribute_type_block = Block::new("ATT");
-//         let mut primitive_type_block = Block::new("PGE");
-//         let mut semantic_relationship_block = Block::new("ASS");
-//         let mut construction_relationship_type_block = Block::new("REL");
-//         let mut current_block: Option<&mut Block> = None;
-
-//         for line in lines.as_ref().lines() {
-//             if line.is_empty() {
-//                 continue;
-//             }
-//             let data = Line::parse_line(&line);
-
-//             match data.header.code.as_str() {
-//                 "BOM" => {}
-//                 "RTY" => match data.parsed_value {
-//                     Some(FormatResult::Text(ref value)) if value == "OBJ" => {
-//                         current_block = Some(&mut object_type_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "ATT" => {
-//                         current_block = Some(&mut attribute_type_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "PGE" => {
-//                         current_block = Some(&mut primitive_type_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "ASS" => {
-//                         current_block = Some(&mut semantic_relationship_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "REL" => {
-//                         current_block = Some(&mut construction_relationship_type_block);
-//                     }
-//                     _ => {}
-//                 },
-//                 "EOM" => current_block = None,
-//                 _ => {
-//                     if let Some(block) = &mut current_block {
-//                         block.add_line(data);
-//                     }
-//                 }
-//             }
-//         }
-
-//         Self {
-//             object_type_block,
-//             attribute_type_block,
-//             primitive_type_block,
-//             semantic_relationship_block,
-//             construction_relationship_type_block,
-//         }
-//     }
-// }
-
-// #[derive(Debug)]
-// pub struct QALFile {
-//     descriptor_block: Block,
-// }
-
-// impl BlockParse for QALFile {
-//     fn parse<S: AsRef<str>>(lines: S) -> Self {
-//         let mut descriptor_block = Block::new("QUP");
-//         let mut current_block: Option<&mut Block> = None;
-
-//         for line in lines.as_ref().lines() {
-//             if line.is_empty() {
-//                 continue;
-//             }
-//             let data = Line::parse_line(&line);
-
-//             match data.header.code.as_str() {
-//                 "BOM" => {}
-//                 "RTY" => match data.parsed_value {
-//                     Some(FormatResult::Text(ref value)) if value == "QUP" => {
-//                         current_block = Some(&mut descriptor_block);
-//                     }
-//                     _ => {}
-//                 },
-//                 "EOM" => current_block = None,
-//                 _ => {
-//                     if let Some(block) = &mut current_block {
-//                         block.add_line(data);
-//                     }
-//                 }
-//             }
-//         }
-
-//         Self { descriptor_block }
-//     }
-// }
-
-// #[derive(Debug)]
-// pub struct VECFile {
-//     geographic_objects_blocks: Block,
-//     node_descriptor_block: Block,
-//     arc_descriptor_block: Block,
-//     face_descriptor_block: Block,
-//     relationship_descriptor_block: Block,
-// }
-
-// impl BlockParse for VECFile {
-//     fn parse<S: AsRef<str>>(lines: S) -> Self {
-//         let mut geographic_objects_blocks = Block::new("PNO");
-//         let mut node_descriptor_block = Block::new("PAR");
-//         let mut arc_descriptor_block = Block::new("PFE");
-//         let mut face_descriptor_block = Block::new("FEA");
-//         let mut relationship_descriptor_block = Block::new("LNK");
-//         let mut current_block: Option<&mut Block> = None;
-
-//         for line in lines.as_ref().lines() {
-//             if line.is_empty() {
-//                 continue;
-//             }
-//             let data = Line::parse_line(&line);
-
-//             match data.header.code.as_str() {
-//                 "BOM" => {}
-//                 "RTY" => match data.parsed_value {
-//                     Some(FormatResult::Text(ref value)) if value == "PNO" => {
-//                         current_block = Some(&mut geographic_objects_blocks);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "PAR" => {
-//                         current_block = Some(&mut node_descriptor_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "PFE" => {
-//                         current_block = Some(&mut arc_descriptor_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "FEA" => {
-//                         current_block = Some(&mut face_descriptor_block);
-//                     }
-//                     Some(FormatResult::Text(ref value)) if value == "LNK" => {
-//                         current_block = Some(&mut relationship_descriptor_block);
-//                     }
-//                     _ => {}
-//                 },
-//                 "EOM" => current_block = None,
-//                 _ => {
-//                     if let Some(block) = &mut current_block {
-//                         block.add_line(data);
-//                     }
-//                 }
-//             }
-//         }
-
-//         Self {
-//             geographic_objects_blocks,
-//             node_descriptor_block,
-//             arc_descriptor_block,
-//             face_descriptor_block,
-//             relationship_descriptor_block,
-//         }
-//     }
-// }
+/// Splits the decoded lines of an EDIGéO file into its [`Block`]s.
+///
+/// `BOM` and `CSE` lines are skipped, `EOM` closes the current block and
+/// `NEX` lines are appended to the value they continue. Errors are located
+/// with [`EdigeoError::AtLine`].
+pub fn parse_blocks<S: AsRef<str>>(lines: S) -> EdigeoResult<Vec<Block>> {
+    collect_blocks(
+        lines
+            .as_ref()
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(number, line)| at_line(number, Line::parse_line(line))),
+    )
+}
 
-#[derive(Debug)]
-struct Block {
-    _id: BlockIdent,
-    entries: Vec<Line>,
+/// Locates the error of a line numbered from 0 with [`EdigeoError::AtLine`].
+fn at_line(number: usize, line: EdigeoResult<Line>) -> EdigeoResult<(usize, Line)> {
+    line.map(|line| (number, line))
+        .map_err(|source| EdigeoError::AtLine {
+            line: number + 1,
+            source: Box::new(source),
+        })
 }
 
-impl Block {
-    fn new(id: BlockIdent) -> Self {
-        Self {
-            _id: id,
-            entries: Vec::new(),
+/// Splits raw bytes into their non empty lines, numbered from 0.
+fn split_lines(data: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    data.split(|byte| *byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+}
+
+/// Splits the raw bytes of an EDIGéO file into its [`Block`]s like
+/// [`parse_blocks`], checking the size of every value as [`ValueSize`]
+/// requires before decoding it from `WINDOWS_1252`.
+pub fn parse_blocks_bytes(data: &[u8], value_size: ValueSize) -> EdigeoResult<Vec<Block>> {
+    collect_blocks(
+        split_lines(data)
+            .map(|(number, line)| at_line(number, Line::parse_bytes(line, value_size))),
+    )
+}
+
+/// Splits the raw bytes of a file into its [`Block`]s like
+/// [`parse_blocks_bytes`], reporting problems to the sink instead of failing.
+///
+/// Lines with an unknown code or that cannot be parsed are skipped. A value
+/// whose size differs from its declared size is read whole with a warning,
+/// and a value that cannot be parsed in its declared format, e.g. an invalid
+/// date, is dropped with a warning.
+/// Count mismatches and a file that is not plain `WINDOWS_1252`, see
+/// [`is_misencoded`](crate::is_misencoded), are reported as warnings.
+/// Diagnostics are located in `file`, e.g. `T1`.
+pub fn parse_blocks_lenient(
+    data: &[u8],
+    value_size: ValueSize,
+    file: &str,
+    diagnostics: &mut dyn DiagnosticSink,
+) -> Vec<Block> {
+    check_encoding(data, file, diagnostics);
+    let lines = split_lines(data).filter_map(|(number, line)| {
+        let report = |diagnostics: &mut dyn DiagnosticSink, severity, error| {
+            diagnostics.report(Diagnostic::from_error(severity, file, number + 1, error))
+        };
+        let parsed = match Line::parse_bytes(line, value_size) {
+            Err(error @ EdigeoError::ValueSizeMismatch { .. }) => {
+                report(diagnostics, Severity::Warning, error);
+                Line::parse_bytes(line, ValueSize::Ignore)
+            }
+            parsed => parsed,
+        };
+        match parsed {
+            Ok(parsed) => {
+                if parsed.parsed_value.is_none()
+                    && !parsed.raw_value.is_empty()
+                    && parsed.header.value_format != ValueFormat::WhiteSpace
+                {
+                    let error = EdigeoError::InvalidValue {
+                        code: parsed.header.code.to_string(),
+                        value: parsed.raw_value.clone(),
+                    };
+                    report(diagnostics, Severity::Warning, error);
+                }
+                Some(Ok((number, parsed)))
+            }
+            Err(error) => {
+                report(diagnostics, Severity::Error, error);
+                None
+            }
         }
+    });
+    let blocks = collect_blocks(lines).unwrap_or_default();
+
+    for mismatch in blocks.iter().flat_map(Block::check_counts) {
+        diagnostics.report(Diagnostic::new(
+            Severity::Warning,
+            file,
+            Some(mismatch.line),
+            DiagnosticKind::CountMismatch { mismatch },
+        ));
     }
+    blocks
+}
 
-    fn add_line(&mut self, line: Line) {
-        self.entries.push(line);
-    }
+/// Returns every count mismatch of the decoded lines of a file, see
+/// [`Block::check_counts`].
+pub fn check_counts<S: AsRef<str>>(lines: S) -> EdigeoResult<Vec<CountMismatch>> {
+    Ok(parse_blocks(lines)?
+        .iter()
+        .flat_map(Block::check_counts)
+        .collect())
+}
+
+/// Splits the decoded lines of a file into its [`Block`]s like [`parse_blocks`],
+/// failing with [`EdigeoError::CountMismatch`] on the first block whose counts
+/// do not match its zones.
+pub fn parse_blocks_checked<S: AsRef<str>>(lines: S) -> EdigeoResult<Vec<Block>> {
+    checked(parse_blocks(lines)?)
 }
 
-pub trait BlockParse {
-    fn parse<S: AsRef<str>>(lines: S) -> Self;
+/// Returns the blocks, or the first count mismatch found in them.
+fn checked(blocks: Vec<Block>) -> EdigeoResult<Vec<Block>> {
+    match blocks.iter().flat_map(Block::check_counts).next() {
+        Some(mismatch) => Err(EdigeoError::CountMismatch(mismatch)),
+        None => Ok(blocks),
+    }
 }
 
+/// The `.THF` exchange descriptor file.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct THFFile {
     support_block: Block,
     batch_block: Block,
 }
 
+impl THFFile {
+    /// Checks the value sizes of the raw `.THF` bytes, then decodes and
+    /// parses them into a [`THFFile`].
+    pub fn new(data: &[u8]) -> EdigeoResult<Self> {
+        THFFile::parse_bytes(data, ValueSize::default())
+    }
+
+    /// Returns `true` if neither the support nor the batch descriptor holds any line.
+    pub fn is_empty(&self) -> bool {
+        self.support_block.entries.is_empty() && self.batch_block.entries.is_empty()
+    }
+
+    /// Returns the name of the lot (`LON`), e.g. `ED0A01`.
+    pub fn lot_name(&self) -> Option<String> {
+        self.batch_block.text(ZoneName::LON)
+    }
+
+    /// Returns the `(GDN, GDI)` pairs of the lot, mapping each data file name
+    /// (e.g. `T1`) to the identifier of the subset it holds (e.g. `SeTOP_1`).
+    pub fn subsets(&self) -> Vec<(String, String)> {
+        let text = |line: &Line| match &line.parsed_value {
+            Some(FormatResult::Text(value)) => value.clone(),
+            _ => String::new(),
+        };
+        self.batch_block
+            .all(ZoneName::GDN)
+            .map(text)
+            .zip(self.batch_block.all(ZoneName::GDI).map(text))
+            .collect()
+    }
+}
+
 impl BlockParse for THFFile {
-    fn parse<S: AsRef<str>>(lines: S) -> Self {
-        let mut support_block = Block::new(BlockIdent::GTS);
-        let mut batch_block = Block::new(BlockIdent::GTL);
-        let mut current_block: Option<&mut Block> = None;
-
-        for line in lines.as_ref().lines() {
-            if line.is_empty() {
-                continue;
-            }
-            let data = Line::parse_line(&line);
-
-            match data.header.code {
-                Code::KWCode(KeyWordCode::BOM) => {}
-                Code::RTY => match data.parsed_value {
-                    Some(FormatResult::Text(ref value)) if value == "GTS" => {
-                        current_block = Some(&mut support_block);
-                    }
-                    Some(FormatResult::Text(ref value)) if value == "GTL" => {
-                        current_block = Some(&mut batch_block);
-                    }
-                    _ => {}
-                },
-                Code::KWCode(KeyWordCode::EOM) => current_block = None,
-                _ => {
-                    if let Some(block) = &mut current_block {
-                        block.add_line(data);
-                    }
-                }
+    fn from_blocks(blocks: Vec<Block>) -> Self {
+        let mut support_block = Block::new("GTS", 0);
+        let mut batch_block = Block::new("GTL", 0);
+
+        for block in blocks {
+            match block.id() {
+                "GTS" => support_block = block,
+                "GTL" => batch_block = block,
+                _ => {}
             }
         }
 
@@ -467,47 +417,105 @@ impl BlockParse for THFFile {
     }
 }
 
-#[derive(Debug)]
-pub struct GENFile {
-    geographical_descriptor_block: Block,
-    geographical_data_block: Block,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Diagnostics;
+
+    const TRUNCATED: &str = "BOMT 12:ED0A01T1.VEC\n\
+        RTYSA03:PAR\n\
+        RIDSA06:Arc_12\n\
+        PTCSN01:3\n\
+        CORCC23:+965015.00;+6560953.22;\n\
+        CORCC23:+965010.22;+6560945.33;\n\
+        ATCSN01:0\n\
+        QACSN01:0\n";
+
+    #[test]
+    fn test_check_counts() {
+        let mismatches = check_counts(TRUNCATED).unwrap();
+        assert_eq!(
+            mismatches,
+            vec![CountMismatch {
+                line: 2,
+                rid: "Arc_12".to_string(),
+                count: ZoneName::PTC,
+                zone: ZoneName::COR,
+                declared: 3,
+                found: 2,
+            }]
+        );
+        assert!(matches!(
+            parse_blocks_checked(TRUNCATED),
+            Err(EdigeoError::CountMismatch(_))
+        ));
+    }
 
-impl BlockParse for GENFile {
-    fn parse<S: AsRef<str>>(lines: S) -> Self {
-        let mut geographical_descriptor_block = Block::new(BlockIdent::DEG);
-        let mut geographical_data_block = Block::new(BlockIdent::GSE);
-        let mut current_block: Option<&mut Block> = None;
+    #[test]
+    fn test_parse_blocks_lenient() {
+        let data = [
+            TRUNCATED.as_bytes(),
+            b"XYZSA03:Foo\nRTYSA03:PNO\nRIDSA07:Noeud_1\nCORCC02:+1;\n",
+        ]
+        .concat();
+        assert!(parse_blocks_bytes(&data, ValueSize::Check).is_err());
+
+        let mut diagnostics = Diagnostics::default();
+        let blocks = parse_blocks_lenient(&data, ValueSize::Check, "T1", &mut diagnostics);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].rid(), "Noeud_1");
+
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line, &d.kind))
+            .collect();
+        assert!(matches!(
+            kinds[..],
+            [
+                (Severity::Error, Some(9), DiagnosticKind::UnknownCode { .. }),
+                (
+                    Severity::Warning,
+                    Some(12),
+                    DiagnosticKind::Recovered { .. }
+                ),
+                (
+                    Severity::Warning,
+                    Some(2),
+                    DiagnosticKind::CountMismatch { .. }
+                ),
+            ]
+        ));
+    }
 
-        for line in lines.as_ref().lines() {
-            if line.is_empty() {
-                continue;
+    #[test]
+    fn test_parse_blocks_lenient_invalid_date() {
+        let data = b"RTYSA03:GTS\r\nTDASD08:20241399\r\nTDASD08:20240116\r\n";
+        let mut diagnostics = Diagnostics::default();
+        let blocks = parse_blocks_lenient(data, ValueSize::Ignore, "X.THF", &mut diagnostics);
+
+        let dates: Vec<_> = blocks[0]
+            .all(ZoneName::TDA)
+            .map(|l| &l.parsed_value)
+            .collect();
+        assert_eq!(
+            dates,
+            [
+                &None,
+                &Some(FormatResult::Date(
+                    chrono::NaiveDate::from_ymd_opt(2024, 1, 16).unwrap()
+                ))
+            ]
+        );
+        let diagnostics = diagnostics.into_vec();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].line, Some(2));
+        assert_eq!(
+            diagnostics[0].kind,
+            DiagnosticKind::Recovered {
+                error: "Invalid Value \"20241399\" for TDA".to_string()
             }
-            let data = Line::parse_line(&line);
-
-            match data.header.code {
-                Code::KWCode(KeyWordCode::BOM) => {}
-                Code::RTY => match data.parsed_value {
-                    Some(FormatResult::Text(ref value)) if value == "DEG" => {
-                        current_block = Some(&mut geographical_descriptor_block);
-                    }
-                    Some(FormatResult::Text(ref value)) if value == "GSE" => {
-                        current_block = Some(&mut geographical_data_block);
-                    }
-                    _ => {}
-                },
-                Code::KWCode(KeyWordCode::EOM) => current_block = None,
-                _ => {
-                    if let Some(block) = &mut current_block {
-                        block.add_line(data);
-                    }
-                }
-            }
-        }
-
-        Self {
-            geographical_descriptor_block,
-            geographical_data_block,
-        }
+        );
+        assert!(parse_blocks("RTYSA03:GTS\nTDASD08:20241399").is_ok());
     }
 }
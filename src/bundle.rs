@@ -1,12 +1,9 @@
 //! EdigeoBundle is a grouping of [`EDIGéO`] files from directories, such as [`EdigeoDir`].
-use encoding_rs::WINDOWS_1252;
-use std::{borrow::Cow, path::Path};
-
-use crate::THFFile;
-
-struct Bundle {
-    pub thf: THFFile,
-}
+use crate::diagnostics::DiagnosticSink;
+use crate::error::{EdigeoError, EdigeoResult};
+use crate::idu::SheetId;
+use crate::{decode_file, decode_file_with, parse_blocks_bytes, CountMismatch, ValueSize};
+use std::borrow::Cow;
 
 /// Represents a collections of Edigeo files for various file types.
 ///
@@ -14,6 +11,7 @@ struct Bundle {
 /// required for Edigeo data processing. Some files are mandatory, while
 /// others are optional, depending on the context of usage.
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EdigeoBundle {
     /// Path to the .thf file, containing metadata for Edigeo.
     pub thf: Vec<u8>,
@@ -35,6 +33,9 @@ pub struct EdigeoBundle {
     pub gen: Option<Vec<u8>>,
     /// Optional path to the .scd file, including sector code data.
     pub scd: Option<Vec<u8>>,
+    /// Path of the directory or archive the lot was read from, e.g.
+    /// `edigeo-740240000A01.tar`, holding its [`SheetId`].
+    pub name: Option<String>,
 }
 
 impl EdigeoBundle {
@@ -50,13 +51,96 @@ impl EdigeoBundle {
             && !&self.qal.is_empty()
     }
 
+    /// Returns the identifier of the sheet found in the bundle's
+    /// [`name`](EdigeoBundle::name).
+    pub fn sheet(&self) -> Option<SheetId> {
+        self.name.as_deref().and_then(SheetId::find)
+    }
+
+    /// Stores the content of a file in the slot matching the end of its name,
+    /// e.g. `ED0A01T1.VEC` in [`EdigeoBundle::t1`], ignoring case. Returns
+    /// `false` when the file is not part of a lot.
+    pub fn insert(&mut self, name: &str, data: Vec<u8>) -> bool {
+        let target = match name.to_ascii_uppercase() {
+            p if p.ends_with(".THF") => &mut self.thf,
+            p if p.ends_with(".GEO") => &mut self.geo,
+            p if p.ends_with("T1.VEC") => &mut self.t1,
+            p if p.ends_with("T2.VEC") => &mut self.t2,
+            p if p.ends_with("T3.VEC") => &mut self.t3,
+            p if p.ends_with("S1.VEC") => &mut self.s1,
+            p if p.ends_with(".QAL") => &mut self.qal,
+            p if p.ends_with(".DIC") => self.dic.get_or_insert_with(Vec::new),
+            p if p.ends_with(".GEN") => self.gen.get_or_insert_with(Vec::new),
+            p if p.ends_with(".SCD") => self.scd.get_or_insert_with(Vec::new),
+            _ => return false,
+        };
+        *target = data;
+        true
+    }
+
+    /// Builds a bundle from file names and their contents, e.g. a
+    /// `HashMap<String, Vec<u8>>` of uploaded files.
+    ///
+    /// Returns [`EdigeoError::IncompleteBundle`] when a mandatory file is
+    /// missing.
+    pub fn from_files<I, K, V>(files: I) -> EdigeoResult<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<Vec<u8>>,
+    {
+        let mut bundle = Self::default();
+        for (name, data) in files {
+            bundle.insert(name.as_ref(), data.into());
+        }
+        bundle.completed()
+    }
+
+    /// Returns the bundle if all mandatory files are present, otherwise
+    /// [`EdigeoError::IncompleteBundle`].
+    pub fn completed(self) -> EdigeoResult<Self> {
+        match self.is_completed() {
+            true => Ok(self),
+            false => Err(EdigeoError::IncompleteBundle),
+        }
+    }
+
+    /// Checks the declared counts of every file of the bundle, see
+    /// [`Block::check_counts`](crate::Block::check_counts). Mismatches are
+    /// returned with the name of their file, e.g. `T1`. Value sizes are not
+    /// checked.
+    pub fn check_counts(&self) -> EdigeoResult<Vec<(&'static str, CountMismatch)>> {
+        let files = [
+            ("THF", Some(&self.thf)),
+            ("GEO", Some(&self.geo)),
+            ("QAL", Some(&self.qal)),
+            ("T1", Some(&self.t1)),
+            ("T2", Some(&self.t2)),
+            ("T3", Some(&self.t3)),
+            ("S1", Some(&self.s1)),
+            ("DIC", self.dic.as_ref()),
+            ("GEN", self.gen.as_ref()),
+            ("SCD", self.scd.as_ref()),
+        ];
+        let mut mismatches = Vec::new();
+        for (name, data) in files {
+            let Some(data) = data else { continue };
+            for block in parse_blocks_bytes(data, ValueSize::Ignore)? {
+                mismatches.extend(block.check_counts().into_iter().map(|m| (name, m)));
+            }
+        }
+        Ok(mismatches)
+    }
+
     /// Raw `Bytes` are encoded in `Latin1 (WINDOWS_1252)` and are decoded to
     /// `UTF-8` strings
     pub fn decode_thf(&self) -> Cow<'_, str> {
-        let (cow, _encoding_used, had_errors) = WINDOWS_1252.decode(&self.thf);
-        if had_errors {
-            eprintln!("Warning: Encoding errors occurred");
-        }
-        cow
+        decode_file(&self.thf)
+    }
+
+    /// Decodes the `.THF` file like [`EdigeoBundle::decode_thf`], reporting
+    /// encoding errors to the sink.
+    pub fn decode_thf_with(&self, diagnostics: &mut dyn DiagnosticSink) -> Cow<'_, str> {
+        decode_file_with(&self.thf, "THF", diagnostics)
     }
 }
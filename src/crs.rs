@@ -0,0 +1,657 @@
+//! Contains the coordinate reference systems named by the `REL` code of a `.GEO` file.
+//!
+//! The codes are those of the IGN register used by the DGFiP, e.g. `LAMB93`
+//! or `RGF93CC46`.
+use crate::error::*;
+use crate::geometry::Coord;
+
+/// Parameters of a projected coordinate reference system.
+///
+/// With the `serde` feature a projection is represented by its `REL` code.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
+pub struct Projection {
+    /// The `REL` code, e.g. `LAMB93`
+    pub code: String,
+    /// EPSG identifier of the projection
+    pub epsg: u32,
+    /// Human readable name, e.g. `RGF93 / Lambert-93`
+    pub name: String,
+    kind: ProjectionKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Datum {
+    /// Réseau Géodésique Français 1993, GRS 1980 ellipsoid
+    Rgf93,
+    /// Nouvelle Triangulation Française, Clarke 1880 (IGN) ellipsoid, Paris meridian
+    NtfParis,
+    /// A GRS 1980 based datum of the overseas departments
+    Overseas(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ProjectionKind {
+    /// Lambert conformal conic with two standard parallels
+    Lambert2Sp {
+        datum: Datum,
+        lat_0: f64,
+        lat_1: f64,
+        lat_2: f64,
+        lon_0: f64,
+        x_0: f64,
+        y_0: f64,
+    },
+    /// Lambert conformal conic with one standard parallel and a scale factor
+    Lambert1Sp {
+        datum: Datum,
+        lat_0: f64,
+        k_0: f64,
+        x_0: f64,
+        y_0: f64,
+    },
+    /// Universal transverse mercator
+    Utm {
+        datum: Datum,
+        zone: u32,
+        south: bool,
+    },
+}
+
+impl Projection {
+    /// Looks up the projection of a `REL` code, `None` if the code is unknown.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let rgf93_cc = |zone: u32| ProjectionKind::Lambert2Sp {
+            datum: Datum::Rgf93,
+            lat_0: zone as f64,
+            lat_1: zone as f64 - 0.75,
+            lat_2: zone as f64 + 0.75,
+            lon_0: 3.0,
+            x_0: 1_700_000.0,
+            y_0: (zone as f64 - 41.0) * 1_000_000.0 + 200_000.0,
+        };
+        // NTF Lambert zones I to IV, in degrees from the Paris meridian.
+        let ntf = |zone: u32, carto: bool| {
+            let (lat_0, k_0, y_0) = match zone {
+                1 => (49.5, 0.99987734, 200_000.0),
+                2 => (46.8, 0.99987742, 200_000.0),
+                3 => (44.1, 0.99987750, 200_000.0),
+                _ => (42.165, 0.99994471, 185_861.369),
+            };
+            ProjectionKind::Lambert1Sp {
+                datum: Datum::NtfParis,
+                lat_0,
+                k_0,
+                x_0: if zone == 4 { 234.358 } else { 600_000.0 },
+                y_0: if carto {
+                    y_0 + zone as f64 * 1_000_000.0
+                } else {
+                    y_0
+                },
+            }
+        };
+        let utm = |datum: Datum, zone: u32, south: bool| ProjectionKind::Utm { datum, zone, south };
+
+        let (epsg, name, kind) = match code {
+            "LAMB93" => (
+                2154,
+                "RGF93 / Lambert-93".to_string(),
+                ProjectionKind::Lambert2Sp {
+                    datum: Datum::Rgf93,
+                    lat_0: 46.5,
+                    lat_1: 49.0,
+                    lat_2: 44.0,
+                    lon_0: 3.0,
+                    x_0: 700_000.0,
+                    y_0: 6_600_000.0,
+                },
+            ),
+            c if c.starts_with("RGF93CC") => {
+                let zone = c[7..]
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|z| (42..=50).contains(z))?;
+                (3900 + zone, format!("RGF93 / CC{zone}"), rgf93_cc(zone))
+            }
+            "LAMBE" => (
+                27572,
+                "NTF (Paris) / Lambert zone II".to_string(),
+                ntf(2, true),
+            ),
+            "LAMB1" | "LAMB2" | "LAMB3" | "LAMB4" => {
+                let zone = code[4..].parse::<u32>().ok()?;
+                (
+                    27560 + zone,
+                    format!(
+                        "NTF (Paris) / Lambert {} France",
+                        ["Nord", "Centre", "Sud", "Corse"][zone as usize - 1]
+                    ),
+                    ntf(zone, false),
+                )
+            }
+            "LAMB1C" | "LAMB2C" | "LAMB3C" | "LAMB4C" => {
+                let zone = code[4..5].parse::<u32>().ok()?;
+                (
+                    27570 + zone,
+                    format!(
+                        "NTF (Paris) / Lambert zone {}",
+                        ["I", "II", "III", "IV"][zone as usize - 1]
+                    ),
+                    ntf(zone, true),
+                )
+            }
+            "UTM20W84GUAD" | "UTM20W84MART" => (
+                4559,
+                "RRAF 1991 / UTM zone 20N".to_string(),
+                utm(Datum::Overseas("RRAF_1991"), 20, false),
+            ),
+            "UTM22RGFG95" => (
+                2972,
+                "RGFG95 / UTM zone 22N".to_string(),
+                utm(Datum::Overseas("RGFG95"), 22, false),
+            ),
+            "RGR92UTM40S" => (
+                2975,
+                "RGR92 / UTM zone 40S".to_string(),
+                utm(Datum::Overseas("RGR92"), 40, true),
+            ),
+            "RGM04UTM38S" => (
+                4471,
+                "RGM04 / UTM zone 38S".to_string(),
+                utm(Datum::Overseas("RGM04"), 38, true),
+            ),
+            "RGSPM06U21" => (
+                4467,
+                "RGSPM06 / UTM zone 21N".to_string(),
+                utm(Datum::Overseas("RGSPM06"), 21, false),
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            code: code.to_string(),
+            epsg,
+            name,
+            kind,
+        })
+    }
+
+    /// Returns the projection in the ESRI WKT dialect expected in `.prj` files.
+    pub fn to_esri_wkt(&self) -> String {
+        let geogcs = |datum: &Datum| match datum {
+            Datum::Rgf93 => "GEOGCS[\"GCS_RGF_1993\",DATUM[\"D_RGF_1993\",\
+                 SPHEROID[\"GRS_1980\",6378137.0,298.257222101]],\
+                 PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]]"
+                .to_string(),
+            Datum::NtfParis => "GEOGCS[\"GCS_NTF_Paris\",DATUM[\"D_NTF\",\
+                 SPHEROID[\"Clarke_1880_IGN\",6378249.2,293.4660212936269]],\
+                 PRIMEM[\"Paris\",2.337229166666667],UNIT[\"Degree\",0.0174532925199433]]"
+                .to_string(),
+            Datum::Overseas(name) => format!(
+                "GEOGCS[\"GCS_{name}\",DATUM[\"D_{name}\",\
+                 SPHEROID[\"GRS_1980\",6378137.0,298.257222101]],\
+                 PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]]"
+            ),
+        };
+        let name = self
+            .name
+            .split([' ', '/', '-', '(', ')'])
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("_");
+        let unit = "UNIT[\"Meter\",1.0]";
+
+        match &self.kind {
+            ProjectionKind::Lambert2Sp {
+                datum,
+                lat_0,
+                lat_1,
+                lat_2,
+                lon_0,
+                x_0,
+                y_0,
+            } => format!(
+                "PROJCS[\"{name}\",{},PROJECTION[\"Lambert_Conformal_Conic\"],\
+                 PARAMETER[\"False_Easting\",{x_0:?}],PARAMETER[\"False_Northing\",{y_0:?}],\
+                 PARAMETER[\"Central_Meridian\",{lon_0:?}],PARAMETER[\"Standard_Parallel_1\",{lat_1:?}],\
+                 PARAMETER[\"Standard_Parallel_2\",{lat_2:?}],PARAMETER[\"Latitude_Of_Origin\",{lat_0:?}],{unit}]",
+                geogcs(datum)
+            ),
+            ProjectionKind::Lambert1Sp {
+                datum,
+                lat_0,
+                k_0,
+                x_0,
+                y_0,
+            } => format!(
+                "PROJCS[\"{name}\",{},PROJECTION[\"Lambert_Conformal_Conic\"],\
+                 PARAMETER[\"False_Easting\",{x_0:?}],PARAMETER[\"False_Northing\",{y_0:?}],\
+                 PARAMETER[\"Central_Meridian\",0.0],PARAMETER[\"Standard_Parallel_1\",{lat_0:?}],\
+                 PARAMETER[\"Scale_Factor\",{k_0:?}],PARAMETER[\"Latitude_Of_Origin\",{lat_0:?}],{unit}]",
+                geogcs(datum)
+            ),
+            ProjectionKind::Utm { datum, zone, south } => format!(
+                "PROJCS[\"{name}\",{},PROJECTION[\"Transverse_Mercator\"],\
+                 PARAMETER[\"False_Easting\",500000.0],PARAMETER[\"False_Northing\",{:?}],\
+                 PARAMETER[\"Central_Meridian\",{:?}],PARAMETER[\"Scale_Factor\",0.9996],\
+                 PARAMETER[\"Latitude_Of_Origin\",0.0],{unit}]",
+                geogcs(datum),
+                if *south { 10_000_000.0 } else { 0.0 },
+                *zone as f64 * 6.0 - 183.0
+            ),
+        }
+    }
+}
+
+impl Projection {
+    /// Returns the projection as a PROJJSON `ProjectedCRS`, the encoding of
+    /// coordinate reference systems in GeoArrow and GeoParquet metadata.
+    pub fn to_projjson(&self) -> String {
+        let datum = match &self.kind {
+            ProjectionKind::Lambert2Sp { datum, .. }
+            | ProjectionKind::Lambert1Sp { datum, .. }
+            | ProjectionKind::Utm { datum, .. } => datum,
+        };
+        let (base, frame, ellipsoid, meridian) = match datum {
+            Datum::Rgf93 => (
+                "RGF93".to_string(),
+                "Reseau Geodesique Francais 1993".to_string(),
+                r#"{"name":"GRS 1980","semi_major_axis":6378137,"inverse_flattening":298.257222101}"#,
+                "",
+            ),
+            Datum::NtfParis => (
+                "NTF (Paris)".to_string(),
+                "Nouvelle Triangulation Francaise (Paris)".to_string(),
+                r#"{"name":"Clarke 1880 (IGN)","semi_major_axis":6378249.2,"inverse_flattening":293.466021293627}"#,
+                r#","prime_meridian":{"name":"Paris","longitude":2.33722917}"#,
+            ),
+            Datum::Overseas(name) => (
+                name.to_string(),
+                name.to_string(),
+                r#"{"name":"GRS 1980","semi_major_axis":6378137,"inverse_flattening":298.257222101}"#,
+                "",
+            ),
+        };
+        let parameter = |name: &str, value: f64, unit: &str, code: u32| {
+            format!(
+                r#"{{"name":"{name}","value":{value:?},"unit":"{unit}","id":{{"authority":"EPSG","code":{code}}}}}"#
+            )
+        };
+        let (method, code, parameters) = match &self.kind {
+            ProjectionKind::Lambert2Sp {
+                lat_0,
+                lat_1,
+                lat_2,
+                lon_0,
+                x_0,
+                y_0,
+                ..
+            } => (
+                "Lambert Conic Conformal (2SP)",
+                9802,
+                vec![
+                    parameter("Latitude of false origin", *lat_0, "degree", 8821),
+                    parameter("Longitude of false origin", *lon_0, "degree", 8822),
+                    parameter("Latitude of 1st standard parallel", *lat_1, "degree", 8823),
+                    parameter("Latitude of 2nd standard parallel", *lat_2, "degree", 8824),
+                    parameter("Easting at false origin", *x_0, "metre", 8826),
+                    parameter("Northing at false origin", *y_0, "metre", 8827),
+                ],
+            ),
+            ProjectionKind::Lambert1Sp {
+                lat_0,
+                k_0,
+                x_0,
+                y_0,
+                ..
+            } => (
+                "Lambert Conic Conformal (1SP)",
+                9801,
+                vec![
+                    parameter("Latitude of natural origin", *lat_0, "degree", 8801),
+                    parameter("Longitude of natural origin", 0.0, "degree", 8802),
+                    parameter("Scale factor at natural origin", *k_0, "unity", 8805),
+                    parameter("False easting", *x_0, "metre", 8806),
+                    parameter("False northing", *y_0, "metre", 8807),
+                ],
+            ),
+            ProjectionKind::Utm { zone, south, .. } => (
+                "Transverse Mercator",
+                9807,
+                vec![
+                    parameter("Latitude of natural origin", 0.0, "degree", 8801),
+                    parameter(
+                        "Longitude of natural origin",
+                        *zone as f64 * 6.0 - 183.0,
+                        "degree",
+                        8802,
+                    ),
+                    parameter("Scale factor at natural origin", 0.9996, "unity", 8805),
+                    parameter("False easting", 500_000.0, "metre", 8806),
+                    parameter(
+                        "False northing",
+                        if *south { 10_000_000.0 } else { 0.0 },
+                        "metre",
+                        8807,
+                    ),
+                ],
+            ),
+        };
+        let axis = |name: &str, abbreviation: &str, direction: &str, unit: &str| {
+            format!(
+                r#"{{"name":"{name}","abbreviation":"{abbreviation}","direction":"{direction}","unit":"{unit}"}}"#
+            )
+        };
+        format!(
+            concat!(
+                r#"{{"$schema":"https://proj.org/schemas/v0.7/projjson.schema.json","#,
+                r#""type":"ProjectedCRS","name":"{}","#,
+                r#""base_crs":{{"name":"{}","datum":{{"type":"GeodeticReferenceFrame","name":"{}","ellipsoid":{}{}}},"#,
+                r#""coordinate_system":{{"subtype":"ellipsoidal","axis":[{},{}]}}}},"#,
+                r#""conversion":{{"name":"{}","method":{{"name":"{}","id":{{"authority":"EPSG","code":{}}}}},"parameters":[{}]}},"#,
+                r#""coordinate_system":{{"subtype":"Cartesian","axis":[{},{}]}},"#,
+                r#""id":{{"authority":"EPSG","code":{}}}}}"#
+            ),
+            self.name,
+            base,
+            frame,
+            ellipsoid,
+            meridian,
+            axis("Geodetic latitude", "Lat", "north", "degree"),
+            axis("Geodetic longitude", "Lon", "east", "degree"),
+            self.name,
+            method,
+            code,
+            parameters.join(","),
+            axis("Easting", "X", "east", "metre"),
+            axis("Northing", "Y", "north", "metre"),
+            self.epsg
+        )
+    }
+}
+
+/// Semi-major axis and first eccentricity of the GRS 1980 ellipsoid.
+const GRS80: (f64, f64) = (6_378_137.0, 0.081_819_191_042_815_79);
+/// Semi-major axis and first eccentricity of the Clarke 1880 (IGN) ellipsoid.
+const CLARKE_IGN: (f64, f64) = (6_378_249.2, 0.082_483_256_763_155_87);
+/// Longitude of the Paris meridian from Greenwich, in degrees.
+const PARIS: f64 = 2.337_229_166_666_667;
+/// Translation from NTF to WGS 84 geocentric coordinates published by the IGN.
+const NTF_TO_WGS84: (f64, f64, f64) = (-168.0, -60.0, 320.0);
+
+impl Datum {
+    fn ellipsoid(&self) -> (f64, f64) {
+        match self {
+            Datum::NtfParis => CLARKE_IGN,
+            Datum::Rgf93 | Datum::Overseas(_) => GRS80,
+        }
+    }
+}
+
+/// Isometric latitude term `t` of the Lambert projection.
+fn lambert_t(lat: f64, e: f64) -> f64 {
+    let sin = e * lat.sin();
+    (std::f64::consts::FRAC_PI_4 - lat / 2.0).tan() / ((1.0 - sin) / (1.0 + sin)).powf(e / 2.0)
+}
+
+/// Latitude of an isometric latitude term `t`, by fixed point iteration.
+fn lambert_lat(t: f64, e: f64) -> f64 {
+    let mut lat = std::f64::consts::FRAC_PI_2 - 2.0 * t.atan();
+    for _ in 0..20 {
+        let sin = e * lat.sin();
+        let next = std::f64::consts::FRAC_PI_2
+            - 2.0 * (t * ((1.0 - sin) / (1.0 + sin)).powf(e / 2.0)).atan();
+        if (next - lat).abs() < 1e-12 {
+            return next;
+        }
+        lat = next;
+    }
+    lat
+}
+
+/// Inverse of a Lambert conformal conic projection of cone constant `n` and
+/// radius `c * t^n`, the pole being at (`x_s`, `y_s`). Returns the longitude
+/// from `lon_0` and the latitude, in radians.
+fn lambert_inverse(e: f64, n: f64, c: f64, x_s: f64, y_s: f64, coord: &Coord) -> (f64, f64) {
+    let (dx, dy) = (coord.x - x_s, y_s - coord.y);
+    let t = (dx.hypot(dy) / c).powf(1.0 / n);
+    (dx.atan2(dy) / n, lambert_lat(t, e))
+}
+
+/// Inverse of the transverse Mercator projection, after Snyder. Returns the
+/// longitude from the central meridian and the latitude, in radians.
+fn utm_inverse((a, e): (f64, f64), x: f64, y: f64) -> (f64, f64) {
+    let k_0 = 0.9996;
+    let e2 = e * e;
+    let ep2 = e2 / (1.0 - e2);
+    let mu = y / k_0 / (a * (1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let lat_1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1.powi(2) / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + 151.0 * e1.powi(3) / 96.0 * (6.0 * mu).sin()
+        + 1097.0 * e1.powi(4) / 512.0 * (8.0 * mu).sin();
+    let (sin, cos, tan) = (lat_1.sin(), lat_1.cos(), lat_1.tan());
+    let c_1 = ep2 * cos * cos;
+    let t_1 = tan * tan;
+    let n_1 = a / (1.0 - e2 * sin * sin).sqrt();
+    let r_1 = a * (1.0 - e2) / (1.0 - e2 * sin * sin).powf(1.5);
+    let d = x / (n_1 * k_0);
+    let lat = lat_1
+        - n_1 * tan / r_1
+            * (d.powi(2) / 2.0
+                - (5.0 + 3.0 * t_1 + 10.0 * c_1 - 4.0 * c_1.powi(2) - 9.0 * ep2) * d.powi(4)
+                    / 24.0
+                + (61.0 + 90.0 * t_1 + 298.0 * c_1 + 45.0 * t_1.powi(2)
+                    - 252.0 * ep2
+                    - 3.0 * c_1.powi(2))
+                    * d.powi(6)
+                    / 720.0);
+    let lon = (d - (1.0 + 2.0 * t_1 + c_1) * d.powi(3) / 6.0
+        + (5.0 - 2.0 * c_1 + 28.0 * t_1 - 3.0 * c_1.powi(2) + 8.0 * ep2 + 24.0 * t_1.powi(2))
+            * d.powi(5)
+            / 120.0)
+        / cos;
+    (lon, lat)
+}
+
+/// Shifts NTF geographic coordinates, in radians from Greenwich, to WGS 84
+/// through their geocentric coordinates.
+fn ntf_to_wgs84(lon: f64, lat: f64) -> (f64, f64) {
+    let (a, e) = CLARKE_IGN;
+    let e2 = e * e;
+    let n = a / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+    let (tx, ty, tz) = NTF_TO_WGS84;
+    let x = n * lat.cos() * lon.cos() + tx;
+    let y = n * lat.cos() * lon.sin() + ty;
+    let z = n * (1.0 - e2) * lat.sin() + tz;
+
+    let (a, e) = GRS80;
+    let e2 = e * e;
+    let p = x.hypot(y);
+    let mut lat = z.atan2(p * (1.0 - e2));
+    for _ in 0..10 {
+        let n = a / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        lat = (z + e2 * n * lat.sin()).atan2(p);
+    }
+    (y.atan2(x), lat)
+}
+
+impl Projection {
+    /// Converts projected coordinates into WGS 84 longitudes (`x`) and
+    /// latitudes (`y`) in degrees.
+    ///
+    /// RGF93 and the overseas frames are taken as WGS 84, NTF coordinates are
+    /// shifted with the three-parameter transformation of the IGN, accurate to
+    /// a few meters.
+    pub fn to_wgs84(&self, coord: &Coord) -> Coord {
+        let (lon, lat) = match &self.kind {
+            ProjectionKind::Lambert2Sp {
+                datum,
+                lat_0,
+                lat_1,
+                lat_2,
+                lon_0,
+                x_0,
+                y_0,
+            } => {
+                let (a, e) = datum.ellipsoid();
+                let m = |lat: f64| lat.cos() / (1.0 - (e * lat.sin()).powi(2)).sqrt();
+                let (lat_0, lat_1, lat_2) =
+                    (lat_0.to_radians(), lat_1.to_radians(), lat_2.to_radians());
+                let n = (m(lat_1).ln() - m(lat_2).ln())
+                    / (lambert_t(lat_1, e).ln() - lambert_t(lat_2, e).ln());
+                let c = a * m(lat_1) / (n * lambert_t(lat_1, e).powf(n));
+                let y_s = y_0 + c * lambert_t(lat_0, e).powf(n);
+                let (lon, lat) = lambert_inverse(e, n, c, *x_0, y_s, coord);
+                (lon + lon_0.to_radians(), lat)
+            }
+            ProjectionKind::Lambert1Sp {
+                datum,
+                lat_0,
+                k_0,
+                x_0,
+                y_0,
+            } => {
+                let (a, e) = datum.ellipsoid();
+                let lat_0 = lat_0.to_radians();
+                let n = lat_0.sin();
+                let m_0 = lat_0.cos() / (1.0 - (e * n).powi(2)).sqrt();
+                let c = a * k_0 * m_0 / (n * lambert_t(lat_0, e).powf(n));
+                // The radius at the origin simplifies to `a k_0 m_0 / n`.
+                let y_s = y_0 + a * k_0 * m_0 / n;
+                let (lon, lat) = lambert_inverse(e, n, c, *x_0, y_s, coord);
+                match datum {
+                    Datum::NtfParis => ntf_to_wgs84(lon + PARIS.to_radians(), lat),
+                    _ => (lon, lat),
+                }
+            }
+            ProjectionKind::Utm { datum, zone, south } => {
+                let false_northing = if *south { 10_000_000.0 } else { 0.0 };
+                let (lon, lat) = utm_inverse(
+                    datum.ellipsoid(),
+                    coord.x - 500_000.0,
+                    coord.y - false_northing,
+                );
+                (lon + (*zone as f64 * 6.0 - 183.0).to_radians(), lat)
+            }
+        };
+        Coord {
+            x: lon.to_degrees(),
+            y: lat.to_degrees(),
+        }
+    }
+}
+
+impl From<Projection> for String {
+    fn from(projection: Projection) -> Self {
+        projection.code
+    }
+}
+
+impl TryFrom<String> for Projection {
+    type Error = EdigeoError;
+    fn try_from(code: String) -> EdigeoResult<Self> {
+        Projection::from_code(&code).ok_or(EdigeoError::InvalidFormat(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_from_code() {
+        let test_cases = [
+            ("LAMB93", 2154),
+            ("RGF93CC42", 3942),
+            ("RGF93CC50", 3950),
+            ("LAMBE", 27572),
+            ("LAMB3", 27563),
+            ("LAMB4C", 27574),
+            ("RGR92UTM40S", 2975),
+        ];
+        for (code, epsg) in test_cases {
+            assert_eq!(Projection::from_code(code).unwrap().epsg, epsg);
+        }
+        assert!(Projection::from_code("RGF93CC51").is_none());
+        assert!(Projection::from_code("UNKNOWN").is_none());
+    }
+
+    #[test]
+    fn test_lambert93_esri_wkt() {
+        let wkt = Projection::from_code("LAMB93").unwrap().to_esri_wkt();
+        assert!(wkt.starts_with("PROJCS[\"RGF93_Lambert_93\",GEOGCS[\"GCS_RGF_1993\""));
+        assert!(wkt.contains("PARAMETER[\"False_Northing\",6600000.0]"));
+    }
+
+    #[test]
+    fn test_lambert93_projjson() {
+        let json: serde_json::Value =
+            serde_json::from_str(&Projection::from_code("LAMB93").unwrap().to_projjson()).unwrap();
+        assert_eq!(json["type"], "ProjectedCRS");
+        assert_eq!(json["id"]["code"], 2154);
+        assert_eq!(json["base_crs"]["datum"]["ellipsoid"]["name"], "GRS 1980");
+        assert_eq!(json["conversion"]["parameters"][5]["value"], 6600000.0);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&Projection::from_code("LAMBE").unwrap().to_projjson()).unwrap();
+        assert_eq!(json["base_crs"]["datum"]["prime_meridian"]["name"], "Paris");
+    }
+
+    #[test]
+    fn test_to_wgs84() {
+        let close = |c: Coord, lon: f64, lat: f64, tolerance: f64| {
+            assert!(
+                (c.x - lon).abs() < tolerance && (c.y - lat).abs() < tolerance,
+                "{c:?} != ({lon}, {lat})"
+            )
+        };
+        let lamb93 = Projection::from_code("LAMB93").unwrap();
+        let origin = Coord {
+            x: 700_000.0,
+            y: 6_600_000.0,
+        };
+        close(lamb93.to_wgs84(&origin), 3.0, 46.5, 1e-9);
+        // The Eiffel tower.
+        let eiffel = Coord {
+            x: 648_237.3,
+            y: 6_862_271.7,
+        };
+        close(lamb93.to_wgs84(&eiffel), 2.2945, 48.8583, 1e-3);
+
+        let lambe = Projection::from_code("LAMBE").unwrap();
+        let origin = Coord {
+            x: 600_000.0,
+            y: 2_200_000.0,
+        };
+        close(lambe.to_wgs84(&origin), PARIS, 46.8, 1e-2);
+
+        let utm = Projection::from_code("RGR92UTM40S").unwrap();
+        let equator = Coord {
+            x: 500_000.0,
+            y: 10_000_000.0,
+        };
+        close(utm.to_wgs84(&equator), 57.0, 0.0, 1e-9);
+    }
+
+    #[test]
+    fn test_lambert_inverse() {
+        // Example ALG0004 of the IGN note NTG_71, Lambert zone I, the longitude
+        // being counted from Greenwich.
+        let (lon, lat) = lambert_inverse(
+            0.0824832568,
+            0.760405966,
+            11603796.9767,
+            600000.0,
+            5657616.674,
+            &Coord {
+                x: 1029705.083,
+                y: 272723.849,
+            },
+        );
+        assert!((lon + PARIS.to_radians() - 0.145512099).abs() < 1e-9);
+        assert!((lat - 0.872664626).abs() < 1e-9);
+    }
+}
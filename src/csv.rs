@@ -0,0 +1,229 @@
+//! Contains the CSV/TSV attribute table writer, emitting one file per object
+//! type of a [`Lot`].
+//!
+//! Each table has the record identifier followed by the attributes of the
+//! object type as listed by the `AAP` zones of the SCD, named after their
+//! dictionary labels (`ID`, `COAR`, `IDU`, `INDP`, `SUPF`, `TEX`...). Files
+//! are encoded in UTF-8 and quoted as in RFC 4180, ready for spreadsheets and
+//! `pandas.read_csv`.
+use crate::error::EdigeoResult;
+use crate::lot::{layer_name, ColumnNames, Feature, Lot};
+use crate::FormatResult;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Geometry columns appended to the attribute tables.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CsvGeometry {
+    /// Attributes only
+    #[default]
+    None,
+    /// A `WKT` column holding the geometry as Well-Known Text
+    Wkt,
+    /// `X` and `Y` columns holding the centroid of the geometry
+    Centroid,
+}
+
+/// Writes the features of a [`Lot`] as one CSV or TSV file per object type.
+/// ```ignore
+///     let lot = Lot::from_bundle(&EdigeoReader::new(file).read_bundle()).unwrap();
+///     let writer = CsvWriter { geometry: CsvGeometry::Wkt, ..CsvWriter::new(b'\t') };
+///     let written = writer.write_lot(&lot, "out/")?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsvWriter {
+    /// Field separator, `,` for CSV or `\t` for TSV files
+    pub delimiter: u8,
+    /// Geometry columns to write after the attributes
+    pub geometry: CsvGeometry,
+    /// Writes the dictionary descriptions (`AVD`) of coded values instead of
+    /// their codes, e.g. `Bâti dur` for `01`
+    pub decode_values: bool,
+}
+
+impl Default for CsvWriter {
+    fn default() -> Self {
+        Self::new(b',')
+    }
+}
+
+impl CsvWriter {
+    /// Constructor method for creating a new [`CsvWriter`] separating fields
+    /// with `delimiter`, without geometry nor decoding of coded values.
+    pub fn new(delimiter: u8) -> Self {
+        Self {
+            delimiter,
+            geometry: CsvGeometry::None,
+            decode_values: false,
+        }
+    }
+
+    /// Extension of the files written: `tsv` for tab separated values.
+    fn extension(&self) -> &'static str {
+        match self.delimiter {
+            b'\t' => "tsv",
+            _ => "csv",
+        }
+    }
+
+    /// Writes every object type of the lot into `dir` and returns the paths of
+    /// the files written, e.g. `PARCELLE.csv`.
+    pub fn write_lot<P: AsRef<Path>>(&self, lot: &Lot, dir: P) -> EdigeoResult<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        lot.layers()
+            .iter()
+            .map(|(object_type, features)| {
+                let path = dir
+                    .as_ref()
+                    .join(layer_name(object_type))
+                    .with_extension(self.extension());
+                let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                self.write_layer(lot, object_type, features, &mut file)?;
+                file.flush()?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// Writes the table of one object type, header line first.
+    pub fn write_layer<W: Write>(
+        &self,
+        lot: &Lot,
+        object_type: &str,
+        features: &[Feature],
+        writer: &mut W,
+    ) -> EdigeoResult<()> {
+        let mut names = ColumnNames::default();
+        let mut header = vec![names.unique("ID")];
+        let columns = lot.attribute_columns(object_type, features, &mut names);
+        header.extend(columns.iter().map(|column| column.name.clone()));
+        match self.geometry {
+            CsvGeometry::None => {}
+            CsvGeometry::Wkt => header.push(names.unique("WKT")),
+            CsvGeometry::Centroid => {
+                header.push(names.unique("X"));
+                header.push(names.unique("Y"));
+            }
+        }
+        self.write_record(writer, &header)?;
+
+        for feature in features {
+            let mut record = vec![feature.id.clone()];
+            record.extend(
+                columns
+                    .iter()
+                    .map(|column| self.value(lot, feature, &column.id)),
+            );
+            match self.geometry {
+                CsvGeometry::None => {}
+                CsvGeometry::Wkt => record.push(
+                    feature
+                        .geometry
+                        .as_ref()
+                        .map(|g| g.to_wkt())
+                        .unwrap_or_default(),
+                ),
+                CsvGeometry::Centroid => {
+                    let centroid = feature.geometry.as_ref().and_then(|g| g.centroid());
+                    record.push(centroid.map(|c| c.x.to_string()).unwrap_or_default());
+                    record.push(centroid.map(|c| c.y.to_string()).unwrap_or_default());
+                }
+            }
+            self.write_record(writer, &record)?;
+        }
+        Ok(())
+    }
+
+    /// Formats an attribute value: trimmed text, ISO 8601 dates and, when
+    /// requested, the description of coded values.
+    fn value(&self, lot: &Lot, feature: &Feature, attribute: &str) -> String {
+        let value = match feature.attribute(attribute) {
+            None => return String::new(),
+            Some(FormatResult::Date(date)) => return date.format("%Y-%m-%d").to_string(),
+            Some(value) => value.to_string(),
+        };
+        let value = value.trim();
+        match self.decode_values {
+            true => lot.value_description(attribute, value).unwrap_or(value),
+            false => value,
+        }
+        .to_string()
+    }
+
+    /// Writes one line, quoting the fields holding the delimiter, quotes or
+    /// line breaks.
+    fn write_record<W: Write>(&self, writer: &mut W, fields: &[String]) -> EdigeoResult<()> {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(&[self.delimiter])?;
+            }
+            let quoted = field
+                .bytes()
+                .any(|b| b == self.delimiter || matches!(b, b'"' | b'\r' | b'\n'));
+            match quoted {
+                true => write!(writer, "\"{}\"", field.replace('"', "\"\""))?,
+                false => writer.write_all(field.as_bytes())?,
+            }
+        }
+        writer.write_all(b"\r\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Geometry;
+    use crate::test_support::{coord, feature, text};
+
+    #[test]
+    fn test_write_layer() {
+        let point = Some(Geometry::Point(coord(1.5, 2.0)));
+        let features = [
+            feature(
+                "Objet_1",
+                "PARCELLE_id",
+                &[
+                    ("TEX_id", text("Les \"Prés\", haut ")),
+                    ("SUPF_id", FormatResult::Int(120)),
+                ],
+                point.clone(),
+            ),
+            feature(
+                "Objet_2",
+                "PARCELLE_id",
+                &[("SUPF_id", FormatResult::Float(9.5))],
+                point,
+            ),
+        ];
+        let writer = CsvWriter {
+            geometry: CsvGeometry::Centroid,
+            ..CsvWriter::default()
+        };
+        let mut csv = Vec::new();
+        writer
+            .write_layer(&Lot::default(), "PARCELLE_id", &features, &mut csv)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "ID,TEX,SUPF,X,Y\r\n\
+             Objet_1,\"Les \"\"Prés\"\", haut\",120,1.5,2\r\n\
+             Objet_2,,9.5,1.5,2\r\n"
+        );
+
+        let writer = CsvWriter {
+            geometry: CsvGeometry::Wkt,
+            ..CsvWriter::new(b'\t')
+        };
+        let mut tsv = Vec::new();
+        writer
+            .write_layer(&Lot::default(), "PARCELLE_id", &features[1..], &mut tsv)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(tsv).unwrap(),
+            "ID\tSUPF\tWKT\r\nObjet_2\t9.5\tPOINT (1.5 2)\r\n"
+        );
+    }
+}
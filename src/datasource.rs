@@ -0,0 +1,186 @@
+//! Contains the [`geozero`] integration, enabled by the `geozero` feature.
+//!
+//! A [`Lot`] is a [`GeozeroDatasource`] emitting its assembled features, so
+//! any geozero writer (GeoJSON, FlatGeobuf, WKB, SVG, CSV, GeoArrow, ...) can
+//! be driven straight from the vector topology.
+//!
+//! ```no_run
+//! use edigeo::*;
+//! use geozero::{geojson::GeoJsonWriter, GeozeroDatasource};
+//!
+//! let mut lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo.tar.bz2").read_bundle()).unwrap();
+//! let mut out = Vec::new();
+//! lot.process(&mut GeoJsonWriter::new(&mut out)).unwrap();
+//! ```
+use crate::geometry::{Coord, Geometry, LineString};
+use crate::lot::{layer_name, Feature, Lot};
+use crate::FormatResult;
+use geozero::error::Result;
+use geozero::{
+    ColumnValue, FeatureProcessor, FeatureProperties, GeomProcessor, GeozeroDatasource,
+    GeozeroGeometry, PropertyProcessor,
+};
+
+fn process_coords<P: GeomProcessor>(processor: &mut P, coords: &[Coord]) -> Result<()> {
+    for (idx, c) in coords.iter().enumerate() {
+        processor.xy(c.x, c.y, idx)?;
+    }
+    Ok(())
+}
+
+fn process_line<P: GeomProcessor>(
+    processor: &mut P,
+    line: &LineString,
+    tagged: bool,
+    idx: usize,
+) -> Result<()> {
+    processor.linestring_begin(tagged, line.len(), idx)?;
+    process_coords(processor, line)?;
+    processor.linestring_end(tagged, idx)
+}
+
+fn process_polygon<P: GeomProcessor>(
+    processor: &mut P,
+    rings: &[LineString],
+    tagged: bool,
+    idx: usize,
+) -> Result<()> {
+    processor.polygon_begin(tagged, rings.len(), idx)?;
+    for (i, ring) in rings.iter().enumerate() {
+        process_line(processor, ring, false, i)?;
+    }
+    processor.polygon_end(tagged, idx)
+}
+
+impl GeozeroGeometry for Geometry {
+    fn process_geom<P: GeomProcessor>(&self, processor: &mut P) -> Result<()> {
+        match self {
+            Geometry::Point(c) => {
+                processor.point_begin(0)?;
+                processor.xy(c.x, c.y, 0)?;
+                processor.point_end(0)
+            }
+            Geometry::MultiPoint(points) => {
+                processor.multipoint_begin(points.len(), 0)?;
+                process_coords(processor, points)?;
+                processor.multipoint_end(0)
+            }
+            Geometry::LineString(line) => process_line(processor, line, true, 0),
+            Geometry::MultiLineString(lines) => {
+                processor.multilinestring_begin(lines.len(), 0)?;
+                for (idx, line) in lines.iter().enumerate() {
+                    process_line(processor, line, false, idx)?;
+                }
+                processor.multilinestring_end(0)
+            }
+            Geometry::Polygon(rings) => process_polygon(processor, rings, true, 0),
+            Geometry::MultiPolygon(polygons) => {
+                processor.multipolygon_begin(polygons.len(), 0)?;
+                for (idx, rings) in polygons.iter().enumerate() {
+                    process_polygon(processor, rings, false, idx)?;
+                }
+                processor.multipolygon_end(0)
+            }
+        }
+    }
+}
+
+/// Emits the record identifier and object type of the feature, then every
+/// attribute with a value, named after its SCD identifier without the `_id`
+/// suffix, e.g. `SUPF`.
+impl FeatureProperties for Feature {
+    fn process_properties<P: PropertyProcessor>(&self, processor: &mut P) -> Result<bool> {
+        if processor.property(0, "id", &ColumnValue::String(&self.id))? {
+            return Ok(true);
+        }
+        let object_type = layer_name(&self.object_type);
+        if processor.property(1, "object_type", &ColumnValue::String(object_type))? {
+            return Ok(true);
+        }
+
+        let values = self
+            .attributes
+            .iter()
+            .filter_map(|attribute| Some((layer_name(&attribute.id), attribute.value.as_ref()?)));
+        for (idx, (name, value)) in values.enumerate() {
+            let text;
+            let value = match value {
+                FormatResult::Float(value) => ColumnValue::Double(*value),
+                FormatResult::Int(value) => ColumnValue::Int(*value),
+                FormatResult::Date(date) => {
+                    text = date.format("%Y-%m-%d").to_string();
+                    ColumnValue::DateTime(&text)
+                }
+                FormatResult::Text(value) => ColumnValue::String(value),
+                value => {
+                    text = value.to_string();
+                    ColumnValue::String(&text)
+                }
+            };
+            if processor.property(idx + 2, name, &value)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Emits every assembled feature of the lot as a dataset named after the lot,
+/// with the SRID of its geographic reference. Features without geometry are
+/// emitted with their properties only.
+impl GeozeroDatasource for Lot {
+    fn process<P: FeatureProcessor>(&mut self, processor: &mut P) -> Result<()> {
+        let srid = self.srid().map(|srid| srid as i32);
+
+        processor.dataset_begin(Some(&self.name))?;
+        for (idx, feature) in self.features().iter().enumerate() {
+            let idx = idx as u64;
+            processor.feature_begin(idx)?;
+            processor.properties_begin()?;
+            feature.process_properties(processor)?;
+            processor.properties_end()?;
+            if let Some(geometry) = &feature.geometry {
+                processor.geometry_begin()?;
+                processor.srid(srid)?;
+                geometry.process_geom(processor)?;
+                processor.geometry_end()?;
+            }
+            processor.feature_end(idx)?;
+        }
+        processor.dataset_end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{coord, feature};
+    use geozero::ToJson;
+
+    #[test]
+    fn test_feature_properties() {
+        let feature = feature(
+            "Objet_1",
+            "PARCELLE_id",
+            &[
+                ("SUPF_id", FormatResult::Float(37054.0)),
+                ("IDU_id", FormatResult::Text("0A01".to_string())),
+            ],
+            None,
+        );
+
+        let properties = feature.properties().unwrap();
+        assert_eq!(properties["object_type"], "PARCELLE");
+        assert_eq!(properties["SUPF"], "37054");
+        assert_eq!(properties["IDU"], "0A01");
+    }
+
+    #[test]
+    fn test_geometry_to_json() {
+        let geometry = Geometry::LineString(vec![coord(1.0, 2.0), coord(3.0, 4.0)]);
+        assert_eq!(
+            geometry.to_json().unwrap(),
+            r#"{"type": "LineString", "coordinates": [[1,2],[3,4]]}"#
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Contains the [`Diagnostic`]s reported by lenient parsing to a
+//! [`DiagnosticSink`], instead of failing or printing to stderr.
+//!
+//! ```no_run
+//! use edigeo::{diagnostics::*, *};
+//!
+//! let bundle = EdigeoReader::new("data/edigeo.tar.bz2").read_bundle();
+//! let mut diagnostics = Diagnostics::default();
+//! let lot = Lot::from_bundle_lenient(&bundle, ValueSize::Check, &mut diagnostics);
+//! for diagnostic in diagnostics.iter() {
+//!     println!("{diagnostic}");
+//! }
+//! ```
+use crate::blocks::CountMismatch;
+use crate::error::EdigeoError;
+use std::fmt;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Severity {
+    /// The data was read, possibly after a correction
+    Warning,
+    /// The data was dropped
+    Error,
+}
+
+/// What a [`Diagnostic`] is about.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum DiagnosticKind {
+    /// A file that is not plain `WINDOWS_1252`, e.g. re-saved as `UTF-8`, so
+    /// its accented characters are decoded wrongly
+    Encoding,
+    /// A header code that is not defined by the standard, the line is skipped
+    UnknownCode {
+        /// The code, e.g. `XYZ`
+        code: String,
+    },
+    /// A count zone that differs from the number of values it counts
+    CountMismatch {
+        /// The mismatch
+        mismatch: CountMismatch,
+    },
+    /// An error that did not stop the parsing: the line is skipped when the
+    /// severity is [`Severity::Error`], read anyway otherwise
+    Recovered {
+        /// Description of the error
+        error: String,
+    },
+}
+
+/// A problem found while reading or parsing a lot.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    /// How serious the problem is
+    pub severity: Severity,
+    /// Name of the file, e.g. `THF` or `T1`
+    pub file: Option<String>,
+    /// Line number in the file, starting at 1
+    pub line: Option<usize>,
+    /// What the problem is
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub kind: DiagnosticKind,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic located in a file.
+    pub fn new(severity: Severity, file: &str, line: Option<usize>, kind: DiagnosticKind) -> Self {
+        Self {
+            severity,
+            file: Some(file.to_string()),
+            line,
+            kind,
+        }
+    }
+
+    /// Creates the diagnostic of an error found on a line of a file.
+    pub fn from_error(severity: Severity, file: &str, line: usize, error: EdigeoError) -> Self {
+        let kind = match error {
+            EdigeoError::UnknownCode(code) => DiagnosticKind::UnknownCode { code },
+            EdigeoError::CountMismatch(mismatch) => DiagnosticKind::CountMismatch { mismatch },
+            error => DiagnosticKind::Recovered {
+                error: error.to_string(),
+            },
+        };
+        Self::new(severity, file, Some(line), kind)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.severity)?;
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, " {file}:{line}")?,
+            (Some(file), None) => write!(f, " {file}")?,
+            (None, Some(line)) => write!(f, " line {line}")?,
+            (None, None) => {}
+        }
+        match &self.kind {
+            DiagnosticKind::Encoding => write!(f, ": not encoded in WINDOWS_1252"),
+            DiagnosticKind::UnknownCode { code } => write!(f, ": unknown code {code:?}"),
+            DiagnosticKind::CountMismatch { mismatch } => write!(f, ": {mismatch}"),
+            DiagnosticKind::Recovered { error } => write!(f, ": {error}"),
+        }
+    }
+}
+
+/// Receives the [`Diagnostic`]s of the readers and parsers.
+///
+/// Implemented by [`Diagnostics`] to collect them, by closures to forward them
+/// to a logger and by `()` to discard them.
+pub trait DiagnosticSink {
+    /// Reports a diagnostic.
+    fn report(&mut self, diagnostic: Diagnostic);
+}
+
+impl DiagnosticSink for () {
+    fn report(&mut self, _diagnostic: Diagnostic) {}
+}
+
+impl<F: FnMut(Diagnostic)> DiagnosticSink for F {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self(diagnostic)
+    }
+}
+
+/// A [`DiagnosticSink`] collecting the diagnostics in the order reported.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Returns the collected diagnostics.
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    /// Returns the number of collected diagnostics.
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    /// Returns `true` when nothing was reported.
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Returns `true` when some data was dropped.
+    pub fn has_errors(&self) -> bool {
+        self.iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Returns the collected diagnostics.
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+impl DiagnosticSink for Diagnostics {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
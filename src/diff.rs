@@ -0,0 +1,345 @@
+//! Contains the comparison of two millésimes (quarterly editions) of the same
+//! lot, see [`Lot::diff`].
+//!
+//! Objects are matched across editions by a stable key rather than by their
+//! record identifier, which is renumbered at each edition: the `IDU_id`
+//! attribute for parcels, sections and communes, the vertices of their
+//! geometry for every other object, such as buildings.
+//!
+//! ```no_run
+//! use edigeo::*;
+//!
+//! let read = |path| Lot::from_bundle(&EdigeoReader::new(path).read_bundle()).unwrap();
+//! let (old, new) = (read("2024T1/edigeo-740240000A01.tar.bz2"), read("2024T2/edigeo-740240000A01.tar.bz2"));
+//! let diff = old.diff(&new);
+//! for parcel in diff.removed.iter().filter(|f| f.object_type == "PARCELLE_id") {
+//!     println!("parcel {:?} removed", parcel.attribute("IDU_id"));
+//! }
+//! ```
+use crate::geometry::{signed_area, Coord, Geometry};
+use crate::lot::{Feature, Lot};
+use crate::FormatResult;
+use chrono::NaiveDate;
+use std::collections::{BTreeSet, HashMap};
+
+/// Precision in meters of the vertices compared across editions.
+const PRECISION: f64 = 0.01;
+
+/// A difference between the values of an attribute of an object.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttributeChange {
+    /// SCD identifier of the attribute, e.g. `SUPF_id`
+    pub id: String,
+    /// Value in the older edition
+    pub old: Option<FormatResult>,
+    /// Value in the newer edition
+    pub new: Option<FormatResult>,
+}
+
+/// A difference between the geometries of an object.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeometryChange {
+    /// Area of the older polygon, `None` for other geometries
+    pub old_area: Option<f64>,
+    /// Area of the newer polygon, `None` for other geometries
+    pub new_area: Option<f64>,
+    /// Vertices of the newer geometry missing from the older one
+    pub added_vertices: Vec<Coord>,
+    /// Vertices of the older geometry missing from the newer one
+    pub removed_vertices: Vec<Coord>,
+}
+
+/// An object found in both editions with different attributes or geometry.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureChange {
+    /// The object in the older edition
+    pub old: Feature,
+    /// The object in the newer edition
+    pub new: Feature,
+    /// Attributes whose value differs, in SCD identifier order
+    pub attributes: Vec<AttributeChange>,
+    /// Difference of geometry, `None` when the vertices are the same
+    pub geometry: Option<GeometryChange>,
+    /// Last update date (`UDA`) of the object's quality in the older edition
+    pub old_update: Option<NaiveDate>,
+    /// Last update date (`UDA`) of the object's quality in the newer edition
+    pub new_update: Option<NaiveDate>,
+}
+
+impl FeatureChange {
+    /// Whether the quality of the object records an update since the older
+    /// edition, confirming the change. Changes without one may come from a
+    /// re-digitization of the sheet rather than from a cadastral event.
+    pub fn is_dated(&self) -> bool {
+        match (self.old_update, self.new_update) {
+            (Some(old), Some(new)) => new > old,
+            (None, new) => new.is_some(),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Objects added, removed and modified between two editions of a lot.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LotDiff {
+    /// Objects of the newer edition missing from the older one
+    pub added: Vec<Feature>,
+    /// Objects of the older edition missing from the newer one
+    pub removed: Vec<Feature>,
+    /// Objects of both editions that differ
+    pub modified: Vec<FeatureChange>,
+}
+
+impl LotDiff {
+    /// Whether both editions hold the same objects.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Identity of an object across editions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    /// Value of the `IDU_id` attribute
+    Idu(String),
+    /// Vertices of the geometry
+    Vertices(Vec<(i64, i64)>),
+    /// Record identifier of an object without identifier or geometry
+    Id(String),
+}
+
+/// A coordinate rounded to the [`PRECISION`].
+fn vertex(coord: &Coord) -> (i64, i64) {
+    (
+        (coord.x / PRECISION).round() as i64,
+        (coord.y / PRECISION).round() as i64,
+    )
+}
+
+/// Sorted vertices of a geometry, independent of the starting point and
+/// direction of its rings.
+fn vertices(geometry: &Geometry) -> Vec<(i64, i64)> {
+    let vertices: BTreeSet<_> = geometry.coords().into_iter().map(vertex).collect();
+    vertices.into_iter().collect()
+}
+
+fn key(feature: &Feature) -> (String, Key) {
+    let key = match (feature.attribute("IDU_id"), &feature.geometry) {
+        (Some(idu), _) => Key::Idu(idu.to_string().trim().to_string()),
+        (None, Some(geometry)) => Key::Vertices(vertices(geometry)),
+        (None, None) => Key::Id(feature.id.clone()),
+    };
+    (feature.object_type.clone(), key)
+}
+
+/// Area of a polygon, its holes having a negative signed area.
+fn area(geometry: &Geometry) -> Option<f64> {
+    let polygons = match geometry {
+        Geometry::Polygon(polygon) => std::slice::from_ref(polygon),
+        Geometry::MultiPolygon(polygons) => polygons,
+        _ => return None,
+    };
+    Some(
+        polygons
+            .iter()
+            .flatten()
+            .map(|ring| signed_area(ring))
+            .sum(),
+    )
+}
+
+fn attribute_changes(old: &Feature, new: &Feature) -> Vec<AttributeChange> {
+    let ids: BTreeSet<_> = old
+        .attributes
+        .iter()
+        .chain(&new.attributes)
+        .map(|attribute| attribute.id.as_str())
+        .collect();
+    ids.into_iter()
+        .map(|id| AttributeChange {
+            id: id.to_string(),
+            old: old.attribute(id).cloned(),
+            new: new.attribute(id).cloned(),
+        })
+        .filter(|change| change.old != change.new)
+        .collect()
+}
+
+fn geometry_change(old: &Option<Geometry>, new: &Option<Geometry>) -> Option<GeometryChange> {
+    let old_vertices = old.as_ref().map(vertices).unwrap_or_default();
+    let new_vertices = new.as_ref().map(vertices).unwrap_or_default();
+    if old_vertices == new_vertices {
+        return None;
+    }
+    let missing = |geometry: &Option<Geometry>, other: &[(i64, i64)]| -> Vec<Coord> {
+        let coords = geometry.as_ref().map(|g| g.coords()).unwrap_or_default();
+        let mut missing: Vec<Coord> = Vec::new();
+        for coord in coords {
+            if other.binary_search(&vertex(coord)).is_err() && !missing.contains(coord) {
+                missing.push(*coord);
+            }
+        }
+        missing
+    };
+    Some(GeometryChange {
+        old_area: old.as_ref().and_then(area),
+        new_area: new.as_ref().and_then(area),
+        added_vertices: missing(new, &old_vertices),
+        removed_vertices: missing(old, &new_vertices),
+    })
+}
+
+impl Lot {
+    /// Compares the lot with a newer edition of the same sheet.
+    ///
+    /// Objects are matched by type and by their `IDU_id` value when they have
+    /// one, as parcels do, or else by the vertices of their geometry rounded
+    /// to the centimeter, as for buildings. A building whose outline changed
+    /// is thus reported as removed and added. Matched objects are reported
+    /// as modified when their attributes or vertices differ, with the `UDA`
+    /// update dates of their qualities, see [`FeatureChange::is_dated`].
+    pub fn diff(&self, newer: &Lot) -> LotDiff {
+        let old_features = self.features();
+        let mut unmatched: HashMap<_, Vec<usize>> = HashMap::new();
+        for (i, feature) in old_features.iter().enumerate().rev() {
+            unmatched.entry(key(feature)).or_default().push(i);
+        }
+        let update = |lot: &Lot, feature: &Feature| {
+            let quality = feature.quality.as_ref()?;
+            lot.qal.quality(&quality.id)?.updated
+        };
+
+        let mut diff = LotDiff::default();
+        let mut matched = vec![false; old_features.len()];
+        for new in newer.features() {
+            let Some(i) = unmatched.get_mut(&key(&new)).and_then(|old| old.pop()) else {
+                diff.added.push(new);
+                continue;
+            };
+            matched[i] = true;
+            let old = &old_features[i];
+            let attributes = attribute_changes(old, &new);
+            let geometry = geometry_change(&old.geometry, &new.geometry);
+            if attributes.is_empty() && geometry.is_none() {
+                continue;
+            }
+            diff.modified.push(FeatureChange {
+                old_update: update(self, old),
+                new_update: update(newer, &new),
+                old: old.clone(),
+                new,
+                attributes,
+                geometry,
+            });
+        }
+        diff.removed = old_features
+            .into_iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(feature, _)| feature)
+            .collect();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{coord, feature, line, text};
+
+    fn polygon(ring: &[(f64, f64)]) -> Option<Geometry> {
+        Some(Geometry::Polygon(vec![line(ring)]))
+    }
+
+    const SQUARE: [(f64, f64); 5] = [
+        (0.0, 0.0),
+        (10.0, 0.0),
+        (10.0, 10.0),
+        (0.0, 10.0),
+        (0.0, 0.0),
+    ];
+
+    #[test]
+    fn test_keys() {
+        let parcel = feature(
+            "Objet_1",
+            "PARCELLE_id",
+            &[("IDU_id", text("0240000A0033"))],
+            polygon(&SQUARE),
+        );
+        let building = feature(
+            "Objet_2",
+            "BATIMENT_id",
+            &[("DUR_id", text("01"))],
+            polygon(&SQUARE),
+        );
+        // The same outline starting from another corner, in the other
+        // direction.
+        let mut ring = SQUARE[1..].to_vec();
+        ring.reverse();
+        ring.push(ring[0]);
+        let moved = feature(
+            "Objet_9",
+            "BATIMENT_id",
+            &[("DUR_id", text("02"))],
+            polygon(&ring),
+        );
+
+        assert_eq!(key(&parcel).1, Key::Idu("0240000A0033".to_string()));
+        assert_eq!(key(&building), key(&moved));
+        assert_eq!(geometry_change(&building.geometry, &moved.geometry), None);
+        assert_eq!(
+            attribute_changes(&building, &moved),
+            vec![AttributeChange {
+                id: "DUR_id".to_string(),
+                old: Some(text("01")),
+                new: Some(text("02")),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_geometry_change() {
+        let square = polygon(&SQUARE);
+        let half = polygon(&[(0.0, 0.0), (5.0, 0.0), (5.0, 10.0), (0.0, 10.0), (0.0, 0.0)]);
+
+        let change = geometry_change(&square, &half).unwrap();
+        assert_eq!(change.old_area, Some(100.0));
+        assert_eq!(change.new_area, Some(50.0));
+        assert_eq!(
+            change.added_vertices,
+            vec![coord(5.0, 0.0), coord(5.0, 10.0)]
+        );
+        assert_eq!(
+            change.removed_vertices,
+            vec![coord(10.0, 0.0), coord(10.0, 10.0)]
+        );
+    }
+
+    #[test]
+    fn test_is_dated() {
+        let parcel = feature(
+            "Objet_1",
+            "PARCELLE_id",
+            &[("IDU_id", text("0240000A0033"))],
+            polygon(&SQUARE),
+        );
+        let date = |year| NaiveDate::from_ymd_opt(year, 1, 1);
+        let change = |old_update, new_update| FeatureChange {
+            old: parcel.clone(),
+            new: parcel.clone(),
+            attributes: vec![],
+            geometry: None,
+            old_update,
+            new_update,
+        };
+        assert!(change(date(2023), date(2024)).is_dated());
+        assert!(!change(date(2024), date(2024)).is_dated());
+        assert!(change(None, date(2024)).is_dated());
+        assert!(!change(None, None).is_dated());
+    }
+}
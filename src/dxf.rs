@@ -0,0 +1,362 @@
+//! Contains the DXF writer drawing a [`Lot`] for CAD software.
+//!
+//! The drawing is an ASCII DXF of release 12 (`AC1009`), read by AutoCAD and
+//! most CAD tools. Each object type is drawn on its own layer named as the
+//! `.shp` files (`PARCELLE`, `BATIMENT`...): faces and arcs become
+//! `POLYLINE`s, closed for the rings of polygons, nodes become `POINT`s and
+//! the boundary markers (`BORNE`, `BOULON`, `CROIX`) `INSERT`s of a block of
+//! the same name. Labels become `TEXT` entities with their height, rotation,
+//! width factor and alignment, on a `_TEXT` layer of the type they annotate,
+//! e.g. `PARCELLE_TEXT`.
+//!
+//! ```no_run
+//! use edigeo::{dxf::DxfWriter, *};
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle()).unwrap();
+//! DxfWriter::default().write_lot(&lot, "740240000A01.dxf").unwrap();
+//! ```
+use crate::error::EdigeoResult;
+use crate::geometry::{BoundingBox, Coord, Geometry};
+use crate::label::{Label, LABEL_OBJECT};
+use crate::lot::{layer_name, Feature, Lot};
+use std::collections::BTreeSet;
+use std::fmt::{Display, Write as _};
+use std::io::Write;
+use std::path::Path;
+
+/// Object types drawn as block references, with their block.
+const MARKERS: [&str; 3] = ["BORNE", "BOULON", "CROIX"];
+
+/// Writes a [`Lot`] as a DXF drawing.
+/// ```ignore
+///     let lot = Lot::from_bundle(&EdigeoReader::new(file).read_bundle()).unwrap();
+///     let writer = DxfWriter { marker_size: 0.5, ..DxfWriter::default() };
+///     writer.write_lot(&lot, "sheet.dxf")?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DxfWriter {
+    /// Size of the boundary marker blocks, in map units
+    pub marker_size: f64,
+    /// Writes the labels as `TEXT` entities
+    pub labels: bool,
+}
+
+impl Default for DxfWriter {
+    fn default() -> Self {
+        Self {
+            marker_size: 1.0,
+            labels: true,
+        }
+    }
+}
+
+/// AutoCAD color index of a layer.
+fn color(layer: &str) -> u8 {
+    match layer.strip_suffix("_TEXT").unwrap_or(layer) {
+        "BATIMENT" => 1,
+        "SECTION" => 4,
+        "COMMUNE" => 6,
+        "SUBDSECT" | "SUBDFISC" | "LIEUDIT" | "TRONROUTE" | "ZONCOMMUNI" => 8,
+        "TRONFLUV" => 5,
+        "TSURF" | "TLINE" | "TPOINT" => 3,
+        _ => 7,
+    }
+}
+
+/// Layer of the text of a label: the layer of the annotated object with a
+/// `_TEXT` suffix.
+fn text_layer(label: &Label) -> String {
+    match &label.object_type {
+        Some(object_type) => format!("{}_TEXT", layer_name(object_type)),
+        None => LABEL_OBJECT.to_string(),
+    }
+}
+
+/// Escapes the characters outside ASCII as `\U+XXXX`, dropping the control
+/// characters.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if c.is_ascii_control() => {}
+            c if c.is_ascii() => escaped.push(c),
+            c if (c as u32) <= 0xFFFF => {
+                let _ = write!(escaped, "\\U+{:04X}", c as u32);
+            }
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+/// A DXF file being written as group code and value pairs.
+#[derive(Debug, Default)]
+struct Dxf(String);
+
+impl Dxf {
+    fn pair<T: Display>(&mut self, code: u16, value: T) -> &mut Self {
+        let _ = write!(self.0, "{code:>3}\n{value}\n");
+        self
+    }
+
+    fn point(&mut self, code: u16, c: &Coord) -> &mut Self {
+        self.pair(code, c.x).pair(code + 10, c.y)
+    }
+
+    fn polyline(&mut self, layer: &str, points: &[Coord], closed: bool) {
+        // Closed polylines join their last vertex to the first.
+        let points = match closed && points.len() > 1 && points.first() == points.last() {
+            true => &points[..points.len() - 1],
+            false => points,
+        };
+        if points.len() < 2 {
+            return;
+        }
+        self.pair(0, "POLYLINE")
+            .pair(8, layer)
+            .pair(66, 1)
+            .point(10, &Coord { x: 0.0, y: 0.0 })
+            .pair(70, u8::from(closed));
+        for c in points {
+            self.pair(0, "VERTEX").pair(8, layer).point(10, c);
+        }
+        self.pair(0, "SEQEND").pair(8, layer);
+    }
+
+    fn table(&mut self, name: &str, entries: usize) {
+        self.pair(0, "TABLE").pair(2, name).pair(70, entries);
+    }
+}
+
+impl DxfWriter {
+    /// Writes the lot into a DXF file.
+    pub fn write_lot<P: AsRef<Path>>(&self, lot: &Lot, path: P) -> EdigeoResult<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(lot, &mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Writes the lot as a DXF drawing.
+    pub fn write<W: Write>(&self, lot: &Lot, writer: &mut W) -> EdigeoResult<()> {
+        let layers = lot.layers();
+        let labels = match self.labels {
+            true => lot.labels(),
+            false => Vec::new(),
+        };
+        let mut names: BTreeSet<String> = layers
+            .keys()
+            .filter(|object_type| object_type.as_str() != LABEL_OBJECT)
+            .map(|object_type| layer_name(object_type).to_string())
+            .collect();
+        names.extend(labels.iter().filter(|l| l.text.is_some()).map(text_layer));
+        let extent = layers
+            .values()
+            .flatten()
+            .filter_map(|f| f.geometry.as_ref()?.bbox())
+            .reduce(|a, b| a.expand(&b));
+
+        let mut dxf = Dxf::default();
+        self.header(&mut dxf, extent);
+        self.tables(&mut dxf, &names);
+        self.blocks(&mut dxf);
+
+        dxf.pair(0, "SECTION").pair(2, "ENTITIES");
+        for (object_type, features) in &layers {
+            if object_type == LABEL_OBJECT {
+                continue;
+            }
+            for feature in features {
+                self.feature(&mut dxf, feature);
+            }
+        }
+        for label in &labels {
+            self.text(&mut dxf, label);
+        }
+        dxf.pair(0, "ENDSEC").pair(0, "EOF");
+
+        writer.write_all(dxf.0.as_bytes())?;
+        Ok(())
+    }
+
+    fn header(&self, dxf: &mut Dxf, extent: Option<BoundingBox>) {
+        dxf.pair(0, "SECTION").pair(2, "HEADER");
+        dxf.pair(9, "$ACADVER").pair(1, "AC1009");
+        if let Some(extent) = extent {
+            dxf.pair(9, "$EXTMIN").point(10, &extent.min);
+            dxf.pair(9, "$EXTMAX").point(10, &extent.max);
+        }
+        dxf.pair(0, "ENDSEC");
+    }
+
+    fn tables(&self, dxf: &mut Dxf, layers: &BTreeSet<String>) {
+        dxf.pair(0, "SECTION").pair(2, "TABLES");
+        dxf.table("LTYPE", 1);
+        dxf.pair(0, "LTYPE")
+            .pair(2, "CONTINUOUS")
+            .pair(70, 0)
+            .pair(3, "Solid line")
+            .pair(72, 65)
+            .pair(73, 0)
+            .pair(40, 0.0);
+        dxf.pair(0, "ENDTAB");
+
+        dxf.table("LAYER", layers.len() + 1);
+        for layer in std::iter::once("0").chain(layers.iter().map(String::as_str)) {
+            dxf.pair(0, "LAYER")
+                .pair(2, layer)
+                .pair(70, 0)
+                .pair(62, color(layer))
+                .pair(6, "CONTINUOUS");
+        }
+        dxf.pair(0, "ENDTAB");
+
+        dxf.table("STYLE", 1);
+        dxf.pair(0, "STYLE")
+            .pair(2, "STANDARD")
+            .pair(70, 0)
+            .pair(40, 0.0)
+            .pair(41, 1.0)
+            .pair(50, 0.0)
+            .pair(71, 0)
+            .pair(42, 2.5)
+            .pair(3, "txt")
+            .pair(4, "");
+        dxf.pair(0, "ENDTAB");
+        dxf.pair(0, "ENDSEC");
+    }
+
+    /// Blocks of the boundary markers, drawn on layer `0` to take the layer
+    /// of their references: a circle for `BORNE`, a small circle around a
+    /// point for `BOULON` and a cross for `CROIX`.
+    fn blocks(&self, dxf: &mut Dxf) {
+        let size = self.marker_size;
+        let origin = Coord { x: 0.0, y: 0.0 };
+        dxf.pair(0, "SECTION").pair(2, "BLOCKS");
+        for name in MARKERS {
+            dxf.pair(0, "BLOCK")
+                .pair(8, 0)
+                .pair(2, name)
+                .pair(70, 0)
+                .point(10, &origin)
+                .pair(3, name);
+            match name {
+                "BORNE" => {
+                    dxf.pair(0, "CIRCLE")
+                        .pair(8, 0)
+                        .point(10, &origin)
+                        .pair(40, size / 2.0);
+                }
+                "BOULON" => {
+                    dxf.pair(0, "CIRCLE")
+                        .pair(8, 0)
+                        .point(10, &origin)
+                        .pair(40, size / 4.0);
+                    dxf.pair(0, "POINT").pair(8, 0).point(10, &origin);
+                }
+                _ => {
+                    for (dx, dy) in [(size / 2.0, 0.0), (0.0, size / 2.0)] {
+                        dxf.pair(0, "LINE")
+                            .pair(8, 0)
+                            .point(10, &Coord { x: -dx, y: -dy })
+                            .point(11, &Coord { x: dx, y: dy });
+                    }
+                }
+            }
+            dxf.pair(0, "ENDBLK").pair(8, 0);
+        }
+        dxf.pair(0, "ENDSEC");
+    }
+
+    fn feature(&self, dxf: &mut Dxf, feature: &Feature) {
+        let Some(geometry) = &feature.geometry else {
+            return;
+        };
+        let layer = layer_name(&feature.object_type);
+        match geometry {
+            Geometry::Point(_) | Geometry::MultiPoint(_) => {
+                for c in geometry.coords() {
+                    match MARKERS.contains(&layer) {
+                        true => dxf.pair(0, "INSERT").pair(8, layer).pair(2, layer),
+                        false => dxf.pair(0, "POINT").pair(8, layer),
+                    }
+                    .point(10, c);
+                }
+            }
+            Geometry::LineString(line) => dxf.polyline(layer, line, false),
+            Geometry::MultiLineString(lines) => {
+                lines.iter().for_each(|l| dxf.polyline(layer, l, false))
+            }
+            Geometry::Polygon(rings) => rings.iter().for_each(|r| dxf.polyline(layer, r, true)),
+            Geometry::MultiPolygon(polygons) => polygons
+                .iter()
+                .flatten()
+                .for_each(|r| dxf.polyline(layer, r, true)),
+        }
+    }
+
+    fn text(&self, dxf: &mut Dxf, label: &Label) {
+        let (Some(anchor), Some(text)) = (&label.anchor, &label.text) else {
+            return;
+        };
+        // TEXT alignment codes: 0 left, 1 center, 2 right, then 0 baseline,
+        // 1 bottom, 2 middle, 3 top.
+        let horizontal = match label.horizontal_alignment.as_deref() {
+            Some("3") => 1,
+            Some("4") => 2,
+            _ => 0,
+        };
+        let vertical = match label.vertical_alignment.as_deref() {
+            Some("2" | "3") => 3,
+            Some("4") => 2,
+            Some("6") => 1,
+            _ => 0,
+        };
+        dxf.pair(0, "TEXT")
+            .pair(8, text_layer(label))
+            .point(10, anchor)
+            .pair(40, label.height.unwrap_or(2.0))
+            .pair(1, escape(text))
+            .pair(50, label.rotation);
+        if let Some(width_factor) = label.width_factor.filter(|w| *w > 0.0) {
+            dxf.pair(41, width_factor);
+        }
+        if horizontal != 0 || vertical != 0 {
+            // Aligned texts are placed by their alignment point.
+            dxf.pair(72, horizontal)
+                .point(11, anchor)
+                .pair(73, vertical);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Les Prés\n"), "Les Pr\\U+00E9s");
+        assert_eq!(escape("A"), "A");
+    }
+
+    #[test]
+    fn test_polyline() {
+        let ring: Vec<Coord> = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]
+            .iter()
+            .map(|&(x, y)| Coord { x, y })
+            .collect();
+        let mut dxf = Dxf::default();
+        dxf.polyline("PARCELLE", &ring, true);
+        assert_eq!(dxf.0.matches("VERTEX").count(), 3);
+        assert!(dxf.0.starts_with(
+            "  0\nPOLYLINE\n  8\nPARCELLE\n 66\n1\n 10\n0\n 20\n0\n 70\n1\n  0\nVERTEX\n"
+        ));
+        assert!(dxf.0.ends_with("  0\nSEQEND\n  8\nPARCELLE\n"));
+
+        let mut dxf = Dxf::default();
+        dxf.polyline("TLINE", &ring[..1], false);
+        assert!(dxf.0.is_empty());
+    }
+}
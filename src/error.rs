@@ -21,6 +21,80 @@ pub enum EdigeoError {
     /// ParseFloatError converting from '03.3' -> 3.3
     #[error("ParseFloat Error {0}")]
     ParseFloatError(std::num::ParseFloatError),
+    /// A header code that is not defined by the standard
+    #[error("Unknown Code {0:?}")]
+    UnknownCode(String),
+    /// A line without the `:` separating its header from its value
+    #[error("Invalid Line {0:?}")]
+    InvalidLine(String),
+    /// A value whose size differs from the size declared by its header
+    #[error("Value Size Mismatch: {code} declares {declared} bytes, {found} found")]
+    ValueSizeMismatch {
+        /// Code of the line, e.g. `RID`
+        code: String,
+        /// Size declared by the header
+        declared: usize,
+        /// Size of the value
+        found: usize,
+    },
+    /// A value that cannot be parsed in the format declared by its header,
+    /// e.g. the date `20241399`
+    #[error("Invalid Value {value:?} for {code}")]
+    InvalidValue {
+        /// Code of the line, e.g. `TDA`
+        code: String,
+        /// The raw value
+        value: String,
+    },
+    /// An error located on a line of a file, numbered from 1
+    #[error("Line {line}: {source}")]
+    AtLine {
+        /// Line number
+        line: usize,
+        /// The error found on the line
+        source: Box<EdigeoError>,
+    },
+    /// A mandatory file of the lot is missing
+    #[error("All necessary EDIGéO files not present")]
+    IncompleteBundle,
+    /// An archive that cannot be unpacked or does not hold the expected lots
+    #[error("Invalid Archive: {0}")]
+    InvalidArchive(String),
+    /// A sheet or parcel identifier that is malformed or cannot be derived
+    #[error("Invalid Identifier {0}")]
+    InvalidIdentifier(String),
+    /// Lots that cannot be merged, e.g. from different projections
+    #[error("Incompatible Lots {0}")]
+    IncompatibleLots(String),
+    /// Zoom levels that cannot be tiled, e.g. above 24
+    #[error("Invalid Zoom {0}")]
+    InvalidZoom(String),
+    /// A lot whose `REL` code names no supported projection
+    #[error("Unknown Projection {0:?}")]
+    UnknownProjection(String),
+    /// A number too wide for a `.dbf` field of at most 254 characters
+    #[error("Value Too Wide for field {field}: {value:?}")]
+    ValueTooWide {
+        /// Name of the field
+        field: String,
+        /// The formatted value
+        value: String,
+    },
+    /// Arrow Error building record batches
+    #[cfg(feature = "arrow")]
+    #[error("Arrow Error {0}")]
+    ArrowError(arrow_schema::ArrowError),
+    /// Parquet Error writing Parquet files
+    #[cfg(feature = "arrow")]
+    #[error("Parquet Error {0}")]
+    ParquetError(parquet::errors::ParquetError),
+    /// SQLite Error writing MBTiles files
+    #[cfg(feature = "mbtiles")]
+    #[error("SQLite Error {0}")]
+    SqliteError(rusqlite::Error),
+    /// A count zone differs from the number of values it counts
+    #[error("Count Mismatch {0}")]
+    CountMismatch(crate::blocks::CountMismatch),
 }
 
 /// Result Type to be used by the EDIGéO Exchange Crate
@@ -49,3 +123,24 @@ impl From<std::num::ParseFloatError> for EdigeoError {
         EdigeoError::ParseFloatError(err)
     }
 }
+
+#[cfg(feature = "arrow")]
+impl From<arrow_schema::ArrowError> for EdigeoError {
+    fn from(err: arrow_schema::ArrowError) -> Self {
+        EdigeoError::ArrowError(err)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<parquet::errors::ParquetError> for EdigeoError {
+    fn from(err: parquet::errors::ParquetError) -> Self {
+        EdigeoError::ParquetError(err)
+    }
+}
+
+#[cfg(feature = "mbtiles")]
+impl From<rusqlite::Error> for EdigeoError {
+    fn from(err: rusqlite::Error) -> Self {
+        EdigeoError::SqliteError(err)
+    }
+}
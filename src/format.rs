@@ -1,9 +1,11 @@
 //! Contains Edigeo file parse formatters using the [`FormatParser`] trait.
 use crate::header::*;
 use chrono::NaiveDate;
+use std::fmt;
 
 /// FormatResult Enum with variants being the DTypes of the parsed values
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FormatResult {
     /// Floating point number as result type
     Float(f64),
@@ -19,6 +21,29 @@ pub enum FormatResult {
     Descriptor(Vec<FormatResult>),
 }
 
+impl fmt::Display for FormatResult {
+    /// Formats the value as it appears in an EDIGéO file; descriptor
+    /// sections and coordinates are separated by `;`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatResult::Float(value) => write!(f, "{value}"),
+            FormatResult::Int(value) => write!(f, "{value}"),
+            FormatResult::Date(value) => write!(f, "{}", value.format("%Y%m%d")),
+            FormatResult::Text(value) => write!(f, "{value}"),
+            FormatResult::Coordinate((x, y)) => write!(f, "{x};{y}"),
+            FormatResult::Descriptor(sections) => {
+                for (i, section) in sections.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ";")?;
+                    }
+                    write!(f, "{section}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 /// Trait for parsing raw values into `FormatResult`.
 pub trait FormatParser {
     /// Parses a raw string value into an `Option<FormatResult>`.
@@ -54,7 +79,8 @@ impl FormatParser for IntParser {
     }
 }
 
-/// A parser for dates in `YYYYMMDD` format.
+/// A parser for dates in `YYYYMMDD` format, returning `None` for invalid
+/// dates such as `20241399`.
 struct DateParser;
 
 impl FormatParser for DateParser {
@@ -62,9 +88,9 @@ impl FormatParser for DateParser {
         if raw_value.len() != 8 {
             return None;
         }
-        Some(FormatResult::Date(
-            NaiveDate::parse_from_str(raw_value, "%Y%m%d").unwrap(),
-        ))
+        NaiveDate::parse_from_str(raw_value, "%Y%m%d")
+            .ok()
+            .map(FormatResult::Date)
     }
 }
 
@@ -95,16 +121,9 @@ struct CoordinateParser;
 
 impl FormatParser for CoordinateParser {
     fn parse(&self, raw_value: &str) -> Option<FormatResult> {
-        let coords = raw_value.split_once(";");
-
-        if let Some(coords) = coords {
-            Some(FormatResult::Coordinate((
-                coords.0.to_string(),
-                coords.1.to_string(),
-            )))
-        } else {
-            None
-        }
+        raw_value
+            .split_once(";")
+            .map(|(x, y)| FormatResult::Coordinate((x.to_string(), y.to_string())))
     }
 }
 
@@ -0,0 +1,140 @@
+//! Contains the conversions to the [`geo_types`] geometries of the Rust geo
+//! ecosystem, enabled by the `geo-types` feature.
+//!
+//! ```no_run
+//! use edigeo::{geo::GeoFeature, *};
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo.tar.bz2").read_bundle()).unwrap();
+//! let parcels: Vec<GeoFeature> = lot.layers()["PARCELLE_id"]
+//!     .iter()
+//!     .cloned()
+//!     .map(GeoFeature::from)
+//!     .collect();
+//! ```
+use crate::geometry::{Coord, Geometry, LineString, Polygon};
+use crate::lot::Feature;
+use crate::records::{Arc, Attribute, Node, Reference};
+
+impl From<Coord> for geo_types::Coord<f64> {
+    fn from(c: Coord) -> Self {
+        geo_types::coord! { x: c.x, y: c.y }
+    }
+}
+
+impl From<Coord> for geo_types::Point<f64> {
+    fn from(c: Coord) -> Self {
+        geo_types::Point::from(geo_types::Coord::from(c))
+    }
+}
+
+fn line_string(points: &LineString) -> geo_types::LineString<f64> {
+    points.iter().copied().map(geo_types::Coord::from).collect()
+}
+
+fn polygon(rings: &Polygon) -> geo_types::Polygon<f64> {
+    let mut rings = rings.iter().map(line_string);
+    let exterior = rings
+        .next()
+        .unwrap_or_else(|| geo_types::LineString::new(vec![]));
+    geo_types::Polygon::new(exterior, rings.collect())
+}
+
+impl Node {
+    /// Returns the position of the node as a [`geo_types::Point`].
+    pub fn to_point(&self) -> Option<geo_types::Point<f64>> {
+        self.coord.map(geo_types::Point::from)
+    }
+}
+
+impl From<&Arc> for geo_types::LineString<f64> {
+    fn from(arc: &Arc) -> Self {
+        line_string(&arc.points)
+    }
+}
+
+impl From<&Geometry> for geo_types::Geometry<f64> {
+    fn from(geometry: &Geometry) -> Self {
+        match geometry {
+            Geometry::Point(c) => geo_types::Point::from(*c).into(),
+            Geometry::MultiPoint(points) => points
+                .iter()
+                .copied()
+                .map(geo_types::Point::from)
+                .collect::<geo_types::MultiPoint<f64>>()
+                .into(),
+            Geometry::LineString(points) => line_string(points).into(),
+            Geometry::MultiLineString(lines) => {
+                geo_types::MultiLineString::new(lines.iter().map(line_string).collect()).into()
+            }
+            Geometry::Polygon(rings) => polygon(rings).into(),
+            Geometry::MultiPolygon(polygons) => {
+                geo_types::MultiPolygon::new(polygons.iter().map(polygon).collect()).into()
+            }
+        }
+    }
+}
+
+impl From<Geometry> for geo_types::Geometry<f64> {
+    fn from(geometry: Geometry) -> Self {
+        Self::from(&geometry)
+    }
+}
+
+/// A [`Feature`] whose geometry is a [`geo_types::Geometry`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeoFeature {
+    /// Record identifier of the `FEA` descriptor, e.g. `Objet_243907`
+    pub id: String,
+    /// Identifier of the subset holding the object
+    pub subset: String,
+    /// Object type in the SCD, e.g. `PARCELLE_id`
+    pub object_type: String,
+    /// Attribute values
+    pub attributes: Vec<Attribute>,
+    /// Reference to the quality descriptor
+    pub quality: Option<Reference>,
+    /// Geometry assembled from the nodes, arcs or faces composing the object
+    pub geometry: Option<geo_types::Geometry<f64>>,
+}
+
+impl From<Feature> for GeoFeature {
+    fn from(feature: Feature) -> Self {
+        Self {
+            id: feature.id,
+            subset: feature.subset,
+            object_type: feature.object_type,
+            attributes: feature.attributes,
+            quality: feature.quality,
+            geometry: feature.geometry.map(geo_types::Geometry::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygon_with_hole() {
+        let ring = |points: &[(f64, f64)]| -> LineString {
+            points.iter().map(|&(x, y)| Coord { x, y }).collect()
+        };
+        let geometry = Geometry::Polygon(vec![
+            ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 0.0)]),
+            ring(&[(6.0, 2.0), (8.0, 6.0), (8.0, 2.0), (6.0, 2.0)]),
+        ]);
+
+        match geo_types::Geometry::from(&geometry) {
+            geo_types::Geometry::Polygon(polygon) => {
+                assert_eq!(polygon.exterior().0.len(), 4);
+                assert_eq!(polygon.interiors().len(), 1);
+                assert_eq!(
+                    polygon.interiors()[0].0[1],
+                    geo_types::coord! { x: 8.0, y: 6.0 }
+                );
+            }
+            other => panic!("expected a polygon, got {other:?}"),
+        }
+    }
+}
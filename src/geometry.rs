@@ -0,0 +1,327 @@
+//! Contains the [`Geometry`] types assembled from the EDIGéO vector primitives.
+use crate::FormatResult;
+
+/// A planar coordinate in the units of the lot's geographic reference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coord {
+    /// Easting
+    pub x: f64,
+    /// Northing
+    pub y: f64,
+}
+
+impl Coord {
+    /// Builds a [`Coord`] from a parsed `COR`, `CM1` or `CM2` value such as
+    /// `+967887.24;+6558363.30;`.
+    pub fn from_format(value: &FormatResult) -> Option<Self> {
+        match value {
+            FormatResult::Coordinate((x, y)) => Some(Self {
+                x: x.trim().parse().ok()?,
+                y: y.trim().trim_end_matches(';').parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// An axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingBox {
+    /// Lower left corner
+    pub min: Coord,
+    /// Upper right corner
+    pub max: Coord,
+}
+
+impl BoundingBox {
+    /// Computes the bounding box of a set of coordinates, `None` if it is empty.
+    pub fn from_coords<'a, I: IntoIterator<Item = &'a Coord>>(coords: I) -> Option<Self> {
+        let mut coords = coords.into_iter();
+        let first = *coords.next()?;
+        Some(coords.fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |bbox, c| bbox.expand(&Self { min: *c, max: *c }),
+        ))
+    }
+
+    /// Returns the smallest bounding box containing both `self` and `other`.
+    pub fn expand(&self, other: &Self) -> Self {
+        Self {
+            min: Coord {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+            },
+            max: Coord {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+            },
+        }
+    }
+}
+
+/// A ring or line as an ordered list of coordinates.
+pub type LineString = Vec<Coord>;
+
+/// A polygon as its exterior ring followed by its holes.
+pub type Polygon = Vec<LineString>;
+
+/// The geometry of an assembled feature.
+///
+/// Polygon exterior rings are counter-clockwise and holes clockwise.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Geometry {
+    /// A single `PNO` node
+    Point(Coord),
+    /// Several `PNO` nodes
+    MultiPoint(Vec<Coord>),
+    /// One chain of `PAR` arcs
+    LineString(LineString),
+    /// Several disjoint chains of `PAR` arcs
+    MultiLineString(Vec<LineString>),
+    /// One or more `PFE` faces dissolved into a single polygon
+    Polygon(Polygon),
+    /// One or more `PFE` faces dissolved into disjoint polygons
+    MultiPolygon(Vec<Polygon>),
+}
+
+impl Geometry {
+    /// Returns every coordinate of the geometry.
+    pub fn coords(&self) -> Vec<&Coord> {
+        match self {
+            Geometry::Point(c) => vec![c],
+            Geometry::MultiPoint(points) | Geometry::LineString(points) => points.iter().collect(),
+            Geometry::MultiLineString(lines) | Geometry::Polygon(lines) => {
+                lines.iter().flatten().collect()
+            }
+            Geometry::MultiPolygon(polygons) => polygons.iter().flatten().flatten().collect(),
+        }
+    }
+
+    /// Returns the bounding box of the geometry.
+    pub fn bbox(&self) -> Option<BoundingBox> {
+        BoundingBox::from_coords(self.coords())
+    }
+
+    /// Returns the center of mass of the geometry: weighted by area for
+    /// polygons, by length for lines, the mean of the vertices otherwise.
+    pub fn centroid(&self) -> Option<Coord> {
+        let coords = self.coords();
+        // Relative to the first vertex, to keep the products of Lambert
+        // coordinates precise.
+        let origin = **coords.first()?;
+        let shift = |c: &Coord| (c.x - origin.x, c.y - origin.y);
+        let rings: Vec<&LineString> = match self {
+            Geometry::Point(_) | Geometry::MultiPoint(_) => Vec::new(),
+            Geometry::LineString(line) => vec![line],
+            Geometry::MultiLineString(lines) | Geometry::Polygon(lines) => lines.iter().collect(),
+            Geometry::MultiPolygon(polygons) => polygons.iter().flatten().collect(),
+        };
+        let segments = || {
+            rings
+                .iter()
+                .flat_map(|ring| ring.windows(2))
+                .map(|w| (shift(&w[0]), shift(&w[1])))
+        };
+
+        let (mut weight, mut x, mut y) = (0.0, 0.0, 0.0);
+        if matches!(self, Geometry::Polygon(_) | Geometry::MultiPolygon(_)) {
+            for ((x0, y0), (x1, y1)) in segments() {
+                let cross = x0 * y1 - x1 * y0;
+                weight += cross / 2.0;
+                x += (x0 + x1) * cross / 6.0;
+                y += (y0 + y1) * cross / 6.0;
+            }
+        } else {
+            for ((x0, y0), (x1, y1)) in segments() {
+                let length = (x1 - x0).hypot(y1 - y0);
+                weight += length;
+                x += (x0 + x1) / 2.0 * length;
+                y += (y0 + y1) / 2.0 * length;
+            }
+        }
+        if weight == 0.0 {
+            let n = coords.len() as f64;
+            (weight, x, y) = (1.0, 0.0, 0.0);
+            for (cx, cy) in coords.into_iter().map(shift) {
+                x += cx / n;
+                y += cy / n;
+            }
+        }
+        Some(Coord {
+            x: origin.x + x / weight,
+            y: origin.y + y / weight,
+        })
+    }
+}
+
+/// Returns the signed area of a closed ring, positive when counter-clockwise.
+pub fn signed_area(ring: &[Coord]) -> f64 {
+    ring.windows(2)
+        .map(|w| w[0].x * w[1].y - w[1].x * w[0].y)
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Even-odd test of whether `point` lies inside the closed `ring`.
+pub fn ring_contains(ring: &[Coord], point: &Coord) -> bool {
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Joins arcs sharing end points into the longest possible chains, reversing
+/// arcs where needed. Closed chains start and end on the same coordinate.
+pub fn merge_lines(mut arcs: Vec<LineString>) -> Vec<LineString> {
+    let mut chains = Vec::new();
+    arcs.retain(|arc| arc.len() > 1);
+
+    while let Some(mut chain) = arcs.pop() {
+        loop {
+            if chain.len() > 2 && chain.first() == chain.last() {
+                break;
+            }
+            let (first, last) = (chain[0], chain[chain.len() - 1]);
+            if let Some(i) = arcs.iter().position(|a| a[0] == last) {
+                let arc = arcs.swap_remove(i);
+                chain.extend_from_slice(&arc[1..]);
+            } else if let Some(i) = arcs.iter().position(|a| a[a.len() - 1] == last) {
+                let arc = arcs.swap_remove(i);
+                chain.extend(arc.iter().rev().skip(1));
+            } else if let Some(i) = arcs.iter().position(|a| a[a.len() - 1] == first) {
+                let mut arc = arcs.swap_remove(i);
+                arc.extend_from_slice(&chain[1..]);
+                chain = arc;
+            } else if let Some(i) = arcs.iter().position(|a| a[0] == first) {
+                let mut arc = arcs.swap_remove(i);
+                arc.reverse();
+                arc.extend_from_slice(&chain[1..]);
+                chain = arc;
+            } else {
+                break;
+            }
+        }
+        chains.push(chain);
+    }
+    chains
+}
+
+/// Assembles polygons from the boundary arcs of a set of faces.
+///
+/// Arcs are chained into closed rings, rings nested at an even depth become
+/// exterior rings and the others holes of the smallest ring containing them.
+/// Chains that cannot be closed are dropped.
+pub fn build_polygons(arcs: Vec<LineString>) -> Vec<Polygon> {
+    let mut rings: Vec<LineString> = merge_lines(arcs)
+        .into_iter()
+        .filter(|ring| ring.len() > 3 && ring.first() == ring.last())
+        .collect();
+    rings.sort_by(|a, b| signed_area(b).abs().total_cmp(&signed_area(a).abs()));
+
+    let mut polygons: Vec<Polygon> = Vec::new();
+    // Every accepted ring with its nesting depth and the polygon it belongs to.
+    let mut accepted: Vec<(LineString, usize, usize)> = Vec::new();
+    for mut ring in rings {
+        // Rings are sorted by decreasing area: the last container is the smallest.
+        let container = accepted
+            .iter()
+            .rev()
+            .find(|(outer, _, _)| {
+                let inside = ring.iter().filter(|c| ring_contains(outer, c)).count();
+                inside * 2 > ring.len()
+            })
+            .map(|(_, depth, polygon)| (*depth, *polygon));
+
+        match container {
+            Some((depth, polygon)) if depth % 2 == 0 => {
+                if signed_area(&ring) > 0.0 {
+                    ring.reverse();
+                }
+                polygons[polygon].push(ring.clone());
+                accepted.push((ring, depth + 1, polygon));
+            }
+            container => {
+                if signed_area(&ring) < 0.0 {
+                    ring.reverse();
+                }
+                let depth = container.map_or(0, |(depth, _)| depth + 1);
+                polygons.push(vec![ring.clone()]);
+                accepted.push((ring, depth, polygons.len() - 1));
+            }
+        }
+    }
+    polygons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(points: &[(f64, f64)]) -> LineString {
+        points.iter().map(|&(x, y)| Coord { x, y }).collect()
+    }
+
+    #[test]
+    fn test_merge_lines_reverses_arcs() {
+        let arcs = vec![
+            ring(&[(0.0, 0.0), (1.0, 0.0)]),
+            ring(&[(2.0, 0.0), (1.0, 0.0)]),
+        ];
+        let merged = merge_lines(arcs);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].len(), 3);
+    }
+
+    #[test]
+    fn test_build_polygons_with_hole() {
+        let arcs = vec![
+            ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]),
+            ring(&[(10.0, 10.0), (0.0, 10.0), (0.0, 0.0)]),
+            ring(&[(2.0, 2.0), (2.0, 4.0), (4.0, 4.0), (4.0, 2.0), (2.0, 2.0)]),
+        ];
+        let polygons = build_polygons(arcs);
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].len(), 2);
+        assert!(signed_area(&polygons[0][0]) > 0.0);
+        assert!(signed_area(&polygons[0][1]) < 0.0);
+    }
+
+    #[test]
+    fn test_centroid() {
+        let square = ring(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)]);
+        let hole = ring(&[(0.0, 0.0), (0.0, 2.0), (2.0, 2.0), (2.0, 0.0), (0.0, 0.0)]);
+        let centroid = |geometry: Geometry| geometry.centroid().unwrap();
+
+        assert_eq!(
+            centroid(Geometry::Polygon(vec![square.clone()])),
+            Coord { x: 2.0, y: 2.0 }
+        );
+        // The hole in the lower left quarter moves the center up and right.
+        let Coord { x, y } = centroid(Geometry::Polygon(vec![square, hole]));
+        assert!((x - 7.0 / 3.0).abs() < 1e-9 && (y - 7.0 / 3.0).abs() < 1e-9);
+        assert_eq!(
+            centroid(Geometry::LineString(ring(&[
+                (0.0, 0.0),
+                (3.0, 0.0),
+                (3.0, 1.0)
+            ]))),
+            Coord { x: 1.875, y: 0.125 }
+        );
+        assert_eq!(
+            centroid(Geometry::MultiPoint(ring(&[(0.0, 0.0), (2.0, 4.0)]))),
+            Coord { x: 1.0, y: 2.0 }
+        );
+    }
+}
@@ -1,11 +1,13 @@
 //! Contains Edigeo file [`Header`] definition & logic..
 use crate::error::*;
+use std::fmt;
 use std::str::FromStr;
 
 /// Represents a parsed header in an Edigeo `Line`.
 ///
 /// Stores metadata about a header, including code, data type, format, and size.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Header {
     /// Identifier code for the header.
     pub code: Code,
@@ -26,9 +28,14 @@ impl Header {
     /// Extracts the header code, value type, format, and size from specified
     /// byte positions in the line.
     pub fn parse_header(line: &str) -> EdigeoResult<Self> {
-        let code = parse_code(line);
-        let value_type = parse_value_type(line);
-        let value_format = parse_value_format(line);
+        // Every header field is a single ASCII byte wide or more, so a
+        // multi-byte character would split them.
+        if line.len() < 7 || !line.as_bytes()[..7].is_ascii() {
+            return Err(EdigeoError::InvalidLine(line.to_string()));
+        }
+        let code = parse_code(line)?;
+        let value_type = parse_value_type(line)?;
+        let value_format = parse_value_format(line)?;
         let value_size = parse_value_size(line)?;
         Ok(Self {
             code,
@@ -41,57 +48,52 @@ impl Header {
 
 /// Parses the first 3 bytes of a header line to extract the header code.
 ///
-/// Example:  `RTYSA03:GTS` -> `RIY`.
+/// Example:  `RTYSA03:GTS` -> `RTY`.
 ///
-/// # Panics
-/// Panics if the input does not contain a colon (`:`).
-pub fn parse_code(line: &str) -> Code {
-    // assert!(line.contains(":"), "Input str not of valid form");
-    line[0..3]
-        .parse::<Code>()
-        .expect("Invalid Code value in Header String")
+/// Returns [`EdigeoError::UnknownCode`] if the code is not defined by the
+/// standard.
+pub fn parse_code(line: &str) -> EdigeoResult<Code> {
+    field(line, 0..3)?.parse::<Code>()
+}
+
+/// Returns the bytes of a header line in `range`, or
+/// [`EdigeoError::InvalidLine`] when the line is too short or the range
+/// splits a character.
+fn field(line: &str, range: std::ops::Range<usize>) -> EdigeoResult<&str> {
+    line.get(range)
+        .ok_or_else(|| EdigeoError::InvalidLine(line.to_string()))
 }
 
 /// Parses the value type from the 4th byte of the header line.
 ///
 /// Example:  `RTYSA03:GTS` -> `S`.
 ///
-/// # Panics
-/// Panics if parsing `ValueType` fails.
-pub fn parse_value_type(line: &str) -> ValueType {
-    // assert!(line.contains(":"), "Input str not of valid form");
-
-    line[3..4]
-        .parse::<ValueType>()
-        .expect("Invalid Value Type Field.")
+/// Returns [`EdigeoError::InvalidFormat`] if the value type is unknown.
+pub fn parse_value_type(line: &str) -> EdigeoResult<ValueType> {
+    field(line, 3..4)?.parse::<ValueType>()
 }
 
 /// Parses the value format from the 5th byte of the header line.
 ///
 /// Example:  `RTYSA03:GTS` -> `A`.
 ///
-/// # Panics
-/// Panics if parsing `ValueFormat` fails.
-pub fn parse_value_format(line: &str) -> ValueFormat {
-    // assert!(line.contains(":"), "Input str not of valid form");
-    line[4..5]
-        .parse::<ValueFormat>()
-        .expect("Invalid Value Format Field.")
+/// Returns [`EdigeoError::InvalidFormat`] if the value format is unknown.
+pub fn parse_value_format(line: &str) -> EdigeoResult<ValueFormat> {
+    field(line, 4..5)?.parse::<ValueFormat>()
 }
 
 /// Parses the value size from the 6th and 7th bytes of the header line.
 ///
 /// Example:  `RTYSA03:GTS` -> `3`.
 ///
-/// # Panics
-/// Panics if parsing `usize` fails.
+/// Returns [`EdigeoError::ParseIntError`] if the size is not a number.
 pub fn parse_value_size(line: &str) -> EdigeoResult<usize> {
-    // assert!(line.contains(":"), "Input str not of valid form");
-    Ok(line[5..7].parse::<usize>()?)
+    Ok(field(line, 5..7)?.parse::<usize>()?)
 }
 
 /// Specifies the format of a value in an Edigeo header.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueFormat {
     /// String of Characters
     A,
@@ -136,6 +138,7 @@ impl FromStr for ValueFormat {
 
 /// Specifies the type of a value in an Edigeo header.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueType {
     /// reserved logical record
     T,
@@ -159,6 +162,7 @@ impl FromStr for ValueType {
 
 /// Specifies the Special Codes used to indicate File Position Metadata.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyWordCode {
     /// Indicates the logical start of the file.
     BOM,
@@ -170,6 +174,7 @@ pub enum KeyWordCode {
 
 /// Type of descriptor
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeCode {
     /// Type of descriptor
     RTY,
@@ -177,6 +182,7 @@ pub enum TypeCode {
 
 /// Descriptor Identifier
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum ZoneName {
     RID,
@@ -257,10 +263,14 @@ pub enum ZoneName {
     FTP,
     SNS,
     TEX,
+    CAD,
+    NEX,
 }
 
 /// Specifies the type of a code in an Edigeo header.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
 #[allow(missing_docs)]
 pub enum Code {
     /// Special File Keywords
@@ -271,6 +281,30 @@ pub enum Code {
     ZoneCode(ZoneName),
 }
 
+impl fmt::Display for Code {
+    /// Writes the three letter code as it appears in an EDIGéO file, e.g. `RID`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Code::KWCode(code) => write!(f, "{code:?}"),
+            Code::TypeCode(code) => write!(f, "{code:?}"),
+            Code::ZoneCode(code) => write!(f, "{code:?}"),
+        }
+    }
+}
+
+impl From<Code> for String {
+    fn from(code: Code) -> Self {
+        code.to_string()
+    }
+}
+
+impl TryFrom<String> for Code {
+    type Error = EdigeoError;
+    fn try_from(code: String) -> EdigeoResult<Self> {
+        code.parse()
+    }
+}
+
 impl FromStr for Code {
     type Err = EdigeoError;
     fn from_str(input: &str) -> EdigeoResult<Self> {
@@ -357,35 +391,9 @@ impl FromStr for Code {
             "FTP" => Ok(Code::ZoneCode(ZoneName::FTP)),
             "SNS" => Ok(Code::ZoneCode(ZoneName::SNS)),
             "TEX" => Ok(Code::ZoneCode(ZoneName::TEX)),
-            "RTY" => Ok(Code::RTY),
-            "RID" => Ok(Code::RID),
-            "AUT" => Ok(Code::AUT),
-            "ADR" => Ok(Code::ADR),
-            "LOC" => Ok(Code::LOC),
-            "VOC" => Ok(Code::VOC),
-            "SEC" => Ok(Code::SEC),
-            "RDI" => Ok(Code::RDI),
-            "VER" => Ok(Code::VER),
-            "VDA" => Ok(Code::VDA),
-            "TRL" => Ok(Code::TRL),
-            "EDN" => Ok(Code::EDN),
-            "TDA" => Ok(Code::TDA),
-            "INF" => Ok(Code::INF),
-            "LON" => Ok(Code::LON),
-            "GNN" => Ok(Code::GNN),
-            "GNI" => Ok(Code::GNI),
-            "GON" => Ok(Code::GON),
-            "GOI" => Ok(Code::GOI),
-            "QAN" => Ok(Code::QAN),
-            "QAI" => Ok(Code::QAI),
-            "DIN" => Ok(Code::DIN),
-            "DII" => Ok(Code::DII),
-            "SCN" => Ok(Code::SCN),
-            "SCI" => Ok(Code::SCI),
-            "GDC" => Ok(Code::GDC),
-            "GDN" => Ok(Code::GDN),
-            "GDI" => Ok(Code::GDI),
-            _ => Err(EdigeoError::InvalidFormat(input.to_string())),
+            "CAD" => Ok(Code::ZoneCode(ZoneName::CAD)),
+            "NEX" => Ok(Code::ZoneCode(ZoneName::NEX)),
+            _ => Err(EdigeoError::UnknownCode(input.to_string())),
         }
     }
 }
@@ -394,6 +402,21 @@ impl FromStr for Code {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_header_non_ascii() {
+        for line in ["ABé45:xxxx", "RTéA03:GTS", "RTYSé3", "RTYSAé:GTS", "RTY"] {
+            assert!(
+                matches!(Header::parse_header(line), Err(EdigeoError::InvalidLine(_))),
+                "{line}"
+            );
+        }
+        assert!(matches!(
+            parse_value_size("RTYSé"),
+            Err(EdigeoError::InvalidLine(_))
+        ));
+        assert!(Header::parse_header("RTYSA03").is_ok());
+    }
+
     #[test]
     fn test_from_str_value_format() {
         let test_cases = [
@@ -0,0 +1,224 @@
+//! Contains the national parcel identifiers ([`Idu`]) and the sheet
+//! identifiers ([`SheetId`]) they are derived from.
+//!
+//! The `IDU_id` of a parcel only holds its commune, prefix, section and
+//! number, e.g. `0240000A0033`. The department is not part of the lot: it is
+//! taken from the name of the sheet, e.g. `edigeo-740240000A01.tar.bz2`,
+//! giving the 14-character identifier `740240000A0033` used by the DGFiP
+//! MAJIC files.
+//!
+//! ```no_run
+//! use edigeo::*;
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle()).unwrap();
+//! for (id, idu) in lot.parcel_idus().unwrap() {
+//!     println!("{id}\t{idu}");
+//! }
+//! ```
+use crate::error::{EdigeoError, EdigeoResult};
+use crate::lot::Lot;
+use std::fmt;
+
+/// Identifier of a cadastral sheet, as found in the name of its archive or
+/// directory: `740240000A01` is sheet `01` of section `0A`, prefix `000`, in
+/// commune `024` of department `74`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SheetId {
+    /// Department code, e.g. `74` or `2A`
+    pub departement: String,
+    /// Commune code within the department, e.g. `024`
+    pub commune: String,
+    /// Prefix of the section, `000` unless the commune was merged
+    pub prefixe: String,
+    /// Section code, padded with `0` to two characters, e.g. `0A`
+    pub section: String,
+    /// Sheet number within the section, e.g. `01`
+    pub feuille: String,
+}
+
+impl SheetId {
+    /// Parses a 12-character sheet identifier, e.g. `740240000A01`.
+    pub fn parse(id: &str) -> EdigeoResult<Self> {
+        let invalid = || EdigeoError::InvalidIdentifier(id.to_string());
+        if id.len() != 12 || !id.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+        let departement = &id[..2];
+        let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        let valid_departement = digits(departement) || departement == "2A" || departement == "2B";
+        if !valid_departement || !digits(&id[2..8]) || !digits(&id[10..]) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            departement: departement.to_string(),
+            commune: id[2..5].to_string(),
+            prefixe: id[5..8].to_string(),
+            section: id[8..10].to_string(),
+            feuille: id[10..].to_string(),
+        })
+    }
+
+    /// Finds the sheet identifier in a file name or path, looking at the
+    /// innermost component first: `dep74.zip/edigeo-740240000A01.tar` gives
+    /// `740240000A01`.
+    pub fn find(name: &str) -> Option<Self> {
+        name.rsplit(['/', '\\']).find_map(|component| {
+            let stem = component.split('.').next().unwrap_or(component);
+            let id = stem
+                .get(..7)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("edigeo-"))
+                .map_or(stem, |_| &stem[7..]);
+            Self::parse(&id.to_ascii_uppercase()).ok()
+        })
+    }
+
+    /// Returns the INSEE code of the commune, e.g. `74024`.
+    pub fn insee(&self) -> String {
+        format!("{}{}", self.departement, self.commune)
+    }
+}
+
+impl fmt::Display for SheetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}{}",
+            self.departement, self.commune, self.prefixe, self.section, self.feuille
+        )
+    }
+}
+
+/// National identifier of a parcel, 14 characters long:
+/// `departement + commune + prefixe + section + numero`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Idu {
+    /// Department code, e.g. `74` or `2A`
+    pub departement: String,
+    /// Commune code within the department, e.g. `024`
+    pub commune: String,
+    /// Prefix of the section, e.g. `000`
+    pub prefixe: String,
+    /// Section code, e.g. `0A`
+    pub section: String,
+    /// Parcel number within the section, e.g. `0033`
+    pub numero: String,
+}
+
+impl Idu {
+    /// Builds the identifier of a parcel from its department and the
+    /// 12-character `IDU_id` value found in the lot, e.g. `0240000A0033`.
+    pub fn from_parcel(departement: &str, idu: &str) -> EdigeoResult<Self> {
+        Self::parse(&format!("{departement}{idu}"))
+    }
+
+    /// Parses a 14-character identifier, e.g. `740240000A0033`.
+    pub fn parse(idu: &str) -> EdigeoResult<Self> {
+        // A parcel identifier reads as a sheet identifier with a 4-digit
+        // number in place of the 2-digit sheet.
+        let sheet = idu
+            .get(..12)
+            .filter(|_| idu.len() == 14 && idu[12..].bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|sheet| SheetId::parse(sheet).ok())
+            .ok_or_else(|| EdigeoError::InvalidIdentifier(idu.to_string()))?;
+        Ok(Self {
+            departement: sheet.departement,
+            commune: sheet.commune,
+            prefixe: sheet.prefixe,
+            section: sheet.section,
+            numero: idu[10..].to_string(),
+        })
+    }
+
+    /// Returns the INSEE code of the commune, e.g. `74024`.
+    pub fn insee(&self) -> String {
+        format!("{}{}", self.departement, self.commune)
+    }
+}
+
+impl fmt::Display for Idu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}{}",
+            self.departement, self.commune, self.prefixe, self.section, self.numero
+        )
+    }
+}
+
+impl Lot {
+    /// Returns the record identifier and national [`Idu`] of every
+    /// `PARCELLE_id` object, in the department of the lot's
+    /// [`sheet`](Lot::sheet).
+    ///
+    /// Fails with [`EdigeoError::InvalidIdentifier`] when the sheet is not
+    /// known, e.g. for a bundle built from files, or when an `IDU_id` value
+    /// is malformed. See [`Lot::parcel_idus_in`] to give the department.
+    pub fn parcel_idus(&self) -> EdigeoResult<Vec<(String, Idu)>> {
+        let sheet = self.sheet.as_ref().ok_or_else(|| {
+            EdigeoError::InvalidIdentifier(format!("no sheet identifier for lot {}", self.name))
+        })?;
+        self.parcel_idus_in(&sheet.departement)
+    }
+
+    /// Returns the record identifier and national [`Idu`] of every
+    /// `PARCELLE_id` object in the given department.
+    pub fn parcel_idus_in(&self, departement: &str) -> EdigeoResult<Vec<(String, Idu)>> {
+        self.subsets
+            .values()
+            .flat_map(|vec| &vec.objects)
+            .filter(|object| {
+                object
+                    .definition
+                    .as_ref()
+                    .is_some_and(|definition| definition.id == "PARCELLE_id")
+            })
+            .map(|object| {
+                let idu = object
+                    .attributes
+                    .iter()
+                    .find(|attribute| attribute.id == "IDU_id")
+                    .and_then(|attribute| attribute.value.as_ref())
+                    .map(|value| value.to_string())
+                    .unwrap_or_default();
+                Ok((object.id.clone(), Idu::from_parcel(departement, &idu)?))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheet_id() {
+        let sheet = SheetId::find("dep74.zip/edigeo-740240000A01.tar").unwrap();
+        assert_eq!(sheet.departement, "74");
+        assert_eq!(sheet.commune, "024");
+        assert_eq!(sheet.section, "0A");
+        assert_eq!(sheet.feuille, "01");
+        assert_eq!(sheet.insee(), "74024");
+        assert_eq!(sheet.to_string(), "740240000A01");
+
+        assert_eq!(
+            SheetId::find("/data/edigeo-2a004000ab02.tar.bz2").map(|s| s.departement),
+            Some("2A".to_string())
+        );
+        assert_eq!(SheetId::find("data/E0000A01.THF"), None);
+        assert!(SheetId::parse("7A0240000A01").is_err());
+    }
+
+    #[test]
+    fn test_idu() {
+        let idu = Idu::from_parcel("74", "0240000A0033").unwrap();
+        assert_eq!(idu.to_string(), "740240000A0033");
+        assert_eq!(idu.numero, "0033");
+        assert_eq!(Idu::parse("740240000A0033").unwrap(), idu);
+        assert!(matches!(
+            Idu::from_parcel("74", "0240000A33"),
+            Err(EdigeoError::InvalidIdentifier(_))
+        ));
+    }
+}
@@ -0,0 +1,413 @@
+//! Contains the [`SpatialIndex`], an R-tree over the feature geometries and
+//! arcs of a lot, enabled by the `rstar` feature.
+//!
+//! The index of a [`Lot`] is built on its first query and kept in the lot,
+//! so it is serialized with it when the `serde` feature is enabled too. It is
+//! not cloned with the lot nor compared, see [`IndexCache`].
+//!
+//! ```no_run
+//! use edigeo::*;
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo.tar.bz2").read_bundle()).unwrap();
+//! let point = Coord { x: 965008.83, y: 6560831.71 };
+//! for entry in lot.query_point(&point) {
+//!     println!("{} {:?} contains the point", entry.id, entry.object_type);
+//! }
+//! ```
+use crate::geometry::{ring_contains, BoundingBox, Coord, Geometry};
+use crate::lot::Lot;
+use rstar::{PointDistance, RTree, RTreeObject, AABB};
+use std::sync::OnceLock;
+
+/// A feature or arc stored in a [`SpatialIndex`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndexEntry {
+    /// Record identifier of the object or arc, e.g. `Objet_243368`
+    pub id: String,
+    /// Identifier of the subset holding it
+    pub subset: String,
+    /// Descriptor type: `FEA` for features, `PAR` for arcs
+    pub kind: String,
+    /// Object type of a feature in the SCD, e.g. `PARCELLE_id`
+    pub object_type: Option<String>,
+    /// Assembled geometry of the feature or line of the arc
+    pub geometry: Geometry,
+    /// Bounding box of the geometry
+    pub bbox: BoundingBox,
+}
+
+impl RTreeObject for IndexEntry {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(
+            [self.bbox.min.x, self.bbox.min.y],
+            [self.bbox.max.x, self.bbox.max.y],
+        )
+    }
+}
+
+impl PointDistance for IndexEntry {
+    fn distance_2(&self, point: &[f64; 2]) -> f64 {
+        let point = Coord {
+            x: point[0],
+            y: point[1],
+        };
+        if contains(&self.geometry, &point) {
+            return 0.0;
+        }
+        segments(&self.geometry)
+            .map(|(a, b)| segment_distance_2(a, b, &point))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// Segments of the lines and rings of a geometry, points being degenerate
+/// segments.
+fn segments(geometry: &Geometry) -> Box<dyn Iterator<Item = (&Coord, &Coord)> + '_> {
+    fn pairs(line: &[Coord]) -> impl Iterator<Item = (&Coord, &Coord)> {
+        line.windows(2).map(|w| (&w[0], &w[1]))
+    }
+    match geometry {
+        Geometry::Point(c) => Box::new(std::iter::once((c, c))),
+        Geometry::MultiPoint(points) => Box::new(points.iter().map(|c| (c, c))),
+        Geometry::LineString(line) => Box::new(pairs(line)),
+        Geometry::MultiLineString(lines) | Geometry::Polygon(lines) => {
+            Box::new(lines.iter().flat_map(|line| pairs(line)))
+        }
+        Geometry::MultiPolygon(polygons) => {
+            Box::new(polygons.iter().flatten().flat_map(|line| pairs(line)))
+        }
+    }
+}
+
+/// Whether a polygonal geometry contains the point, in its exterior ring and
+/// out of its holes.
+fn contains(geometry: &Geometry, point: &Coord) -> bool {
+    let polygon_contains = |rings: &[Vec<Coord>]| match rings {
+        [exterior, holes @ ..] => {
+            ring_contains(exterior, point) && !holes.iter().any(|hole| ring_contains(hole, point))
+        }
+        [] => false,
+    };
+    match geometry {
+        Geometry::Polygon(rings) => polygon_contains(rings),
+        Geometry::MultiPolygon(polygons) => polygons.iter().any(|rings| polygon_contains(rings)),
+        _ => false,
+    }
+}
+
+fn segment_distance_2(a: &Coord, b: &Coord, p: &Coord) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_2 = dx * dx + dy * dy;
+    let t = match length_2 {
+        0.0 => 0.0,
+        _ => (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_2).clamp(0.0, 1.0),
+    };
+    let (x, y) = (a.x + t * dx - p.x, a.y + t * dy - p.y);
+    x * x + y * y
+}
+
+/// Whether segments `ab` and `cd` touch or cross.
+fn segments_intersect(a: &Coord, b: &Coord, c: &Coord, d: &Coord) -> bool {
+    let orientation = |p: &Coord, q: &Coord, r: &Coord| {
+        ((q.x - p.x) * (r.y - p.y) - (q.y - p.y) * (r.x - p.x)).signum()
+    };
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    (o1 != o2 || o1 == 0.0) && (o3 != o4 || o3 == 0.0) && {
+        // Collinear segments only touch when their boxes overlap.
+        let overlap = |p: f64, q: f64, r: f64, s: f64| p.min(q) <= r.max(s) && r.min(s) <= p.max(q);
+        overlap(a.x, b.x, c.x, d.x) && overlap(a.y, b.y, c.y, d.y)
+    }
+}
+
+fn bbox_contains(bbox: &BoundingBox, point: &Coord) -> bool {
+    (bbox.min.x..=bbox.max.x).contains(&point.x) && (bbox.min.y..=bbox.max.y).contains(&point.y)
+}
+
+/// Whether a geometry touches or overlaps a bounding box.
+fn intersects(geometry: &Geometry, bbox: &BoundingBox) -> bool {
+    let corners = [
+        bbox.min,
+        Coord {
+            x: bbox.max.x,
+            y: bbox.min.y,
+        },
+        bbox.max,
+        Coord {
+            x: bbox.min.x,
+            y: bbox.max.y,
+        },
+    ];
+    geometry
+        .coords()
+        .into_iter()
+        .any(|c| bbox_contains(bbox, c))
+        || contains(geometry, &bbox.min)
+        || segments(geometry).any(|(a, b)| {
+            (0..4).any(|i| segments_intersect(a, b, &corners[i], &corners[(i + 1) % 4]))
+        })
+}
+
+/// An R-tree over the feature geometries and arcs of a lot.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpatialIndex {
+    tree: RTree<IndexEntry>,
+}
+
+impl PartialEq for SpatialIndex {
+    fn eq(&self, other: &Self) -> bool {
+        self.tree.size() == other.tree.size() && self.tree.iter().eq(other.tree.iter())
+    }
+}
+
+impl SpatialIndex {
+    /// Indexes the features having a geometry and the arcs of a lot.
+    pub fn new(lot: &Lot) -> Self {
+        let mut entries = Vec::new();
+        for feature in lot.features() {
+            let Some(geometry) = feature.geometry else {
+                continue;
+            };
+            let Some(bbox) = geometry.bbox() else {
+                continue;
+            };
+            entries.push(IndexEntry {
+                id: feature.id,
+                subset: feature.subset,
+                kind: "FEA".to_string(),
+                object_type: Some(feature.object_type),
+                geometry,
+                bbox,
+            });
+        }
+        for (subset, vec) in &lot.subsets {
+            for arc in &vec.arcs {
+                let Some(bbox) = BoundingBox::from_coords(&arc.points) else {
+                    continue;
+                };
+                entries.push(IndexEntry {
+                    id: arc.id.clone(),
+                    subset: subset.clone(),
+                    kind: "PAR".to_string(),
+                    object_type: None,
+                    geometry: Geometry::LineString(arc.points.clone()),
+                    bbox,
+                });
+            }
+        }
+        Self {
+            tree: RTree::bulk_load(entries),
+        }
+    }
+
+    /// Returns the number of indexed entries.
+    pub fn len(&self) -> usize {
+        self.tree.size()
+    }
+
+    /// Returns `true` when nothing is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.tree.size() == 0
+    }
+
+    /// Returns the entries whose geometry touches or overlaps the bounding box.
+    pub fn query_bbox(&self, bbox: &BoundingBox) -> Vec<&IndexEntry> {
+        let envelope = AABB::from_corners([bbox.min.x, bbox.min.y], [bbox.max.x, bbox.max.y]);
+        self.tree
+            .locate_in_envelope_intersecting(&envelope)
+            .filter(|entry| intersects(&entry.geometry, bbox))
+            .collect()
+    }
+
+    /// Returns the features whose polygon contains the point.
+    pub fn query_point(&self, point: &Coord) -> Vec<&IndexEntry> {
+        let envelope = AABB::from_point([point.x, point.y]);
+        self.tree
+            .locate_in_envelope_intersecting(&envelope)
+            .filter(|entry| contains(&entry.geometry, point))
+            .collect()
+    }
+
+    /// Returns the entries by increasing distance of their geometry to the
+    /// point, `0` for polygons containing it.
+    pub fn nearest<'a>(&'a self, point: &Coord) -> impl Iterator<Item = (&'a IndexEntry, f64)> {
+        self.tree
+            .nearest_neighbor_iter_with_distance_2(&[point.x, point.y])
+            .map(|(entry, distance_2)| (entry, distance_2.sqrt()))
+    }
+}
+
+/// The [`SpatialIndex`] of a [`Lot`], built on its first query.
+///
+/// The cache is not part of the value of the lot: it compares equal to any
+/// other cache, and a clone of the lot starts without it, as clones are
+/// usually edited before being queried.
+#[derive(Debug, Default)]
+pub struct IndexCache(OnceLock<SpatialIndex>);
+
+impl IndexCache {
+    /// Returns the index if it was built.
+    pub fn get(&self) -> Option<&SpatialIndex> {
+        self.0.get()
+    }
+
+    /// Drops the index, to be built again on the next query.
+    pub fn reset(&mut self) {
+        self.0 = OnceLock::new();
+    }
+
+    /// Returns `true` when the index was not built.
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_unset(&self) -> bool {
+        self.0.get().is_none()
+    }
+}
+
+impl Clone for IndexCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for IndexCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl From<SpatialIndex> for IndexCache {
+    fn from(index: SpatialIndex) -> Self {
+        Self(OnceLock::from(index))
+    }
+}
+
+/// Serializes the cache as its optional index.
+#[cfg(feature = "serde")]
+impl serde::Serialize for IndexCache {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for IndexCache {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<SpatialIndex>::deserialize(deserializer)?
+            .map_or_else(Self::default, Self::from))
+    }
+}
+
+impl Lot {
+    /// Returns the spatial index of the lot, built on first use.
+    ///
+    /// The index is not updated when the lot changes, see
+    /// [`Lot::build_index`].
+    pub fn index(&self) -> &SpatialIndex {
+        self.index.0.get_or_init(|| SpatialIndex::new(self))
+    }
+
+    /// Builds the spatial index of the lot again, e.g. after editing its
+    /// subsets.
+    pub fn build_index(&mut self) -> &SpatialIndex {
+        self.index = SpatialIndex::new(self).into();
+        self.index()
+    }
+
+    /// Returns the features and arcs whose geometry touches or overlaps the
+    /// bounding box, see [`SpatialIndex::query_bbox`].
+    pub fn query_bbox(&self, bbox: &BoundingBox) -> Vec<&IndexEntry> {
+        self.index().query_bbox(bbox)
+    }
+
+    /// Returns the features whose polygon contains the point, see
+    /// [`SpatialIndex::query_point`].
+    pub fn query_point(&self, point: &Coord) -> Vec<&IndexEntry> {
+        self.index().query_point(point)
+    }
+
+    /// Returns the features and arcs by increasing distance to the point, see
+    /// [`SpatialIndex::nearest`].
+    pub fn nearest(&self, point: &Coord) -> impl Iterator<Item = (&IndexEntry, f64)> {
+        self.index().nearest(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::coord;
+
+    fn square(x: f64, y: f64, size: f64) -> Geometry {
+        Geometry::Polygon(vec![vec![
+            coord(x, y),
+            coord(x + size, y),
+            coord(x + size, y + size),
+            coord(x, y + size),
+            coord(x, y),
+        ]])
+    }
+
+    fn index(geometries: Vec<Geometry>) -> SpatialIndex {
+        let entries = geometries
+            .into_iter()
+            .enumerate()
+            .map(|(i, geometry)| IndexEntry {
+                id: format!("Objet_{i}"),
+                subset: "SeTOP_1".to_string(),
+                kind: "FEA".to_string(),
+                object_type: None,
+                bbox: geometry.bbox().unwrap(),
+                geometry,
+            })
+            .collect();
+        SpatialIndex {
+            tree: RTree::bulk_load(entries),
+        }
+    }
+
+    #[test]
+    fn test_queries() {
+        let index = index(vec![
+            square(0.0, 0.0, 10.0),
+            Geometry::LineString(vec![coord(20.0, 0.0), coord(30.0, 10.0)]),
+            Geometry::Point(coord(5.0, 20.0)),
+        ]);
+        let ids = |entries: Vec<&IndexEntry>| {
+            let mut ids: Vec<_> = entries.iter().map(|e| e.id.clone()).collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(ids(index.query_point(&coord(5.0, 5.0))), ["Objet_0"]);
+        assert!(index.query_point(&coord(15.0, 5.0)).is_empty());
+
+        // The box holds none of the vertices but crosses the line.
+        let bbox = BoundingBox {
+            min: coord(24.0, 0.0),
+            max: coord(26.0, 8.0),
+        };
+        assert_eq!(ids(index.query_bbox(&bbox)), ["Objet_1"]);
+        // Inside the square, in the envelope of the line but off it.
+        let bbox = BoundingBox {
+            min: coord(2.0, 2.0),
+            max: coord(3.0, 3.0),
+        };
+        assert_eq!(ids(index.query_bbox(&bbox)), ["Objet_0"]);
+        let bbox = BoundingBox {
+            min: coord(28.0, 1.0),
+            max: coord(29.0, 2.0),
+        };
+        assert!(index.query_bbox(&bbox).is_empty());
+
+        let nearest: Vec<_> = index
+            .nearest(&coord(5.0, 16.0))
+            .map(|(entry, distance)| (entry.id.as_str(), distance))
+            .collect();
+        assert_eq!(nearest[0], ("Objet_2", 4.0));
+        assert_eq!(nearest[1], ("Objet_0", 6.0));
+    }
+}
@@ -0,0 +1,532 @@
+//! Contains the INSPIRE Cadastral Parcels (CP) GML writer, enabled by the
+//! `pci` feature.
+//!
+//! A lot is resolved into its PCI objects and written as a GML 3.2 WFS
+//! feature collection of the CP 4.0 application schema:
+//!
+//! - `PARCELLE` objects become `cp:CadastralParcel`s identified by their
+//!   14-character [`Idu`], with their `SUPF` as `areaValue`;
+//! - `COMMUNE`, `SECTION` and `SUBDSECT` objects become the first, second
+//!   and third order `cp:CadastralZoning`s, each linked to its upper level;
+//! - the arcs bounding parcels become `cp:CadastralBoundary`s linked to the
+//!   parcels on their sides.
+//!
+//! Geometries keep the coordinates of the lot, the `srsName` giving the EPSG
+//! code of its projection.
+//!
+//! ```no_run
+//! use edigeo::{inspire::InspireWriter, *};
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle()).unwrap();
+//! InspireWriter::default().write_lot(&lot, "740240000A01.gml").unwrap();
+//! ```
+use crate::error::{EdigeoError, EdigeoResult};
+use crate::geometry::{ring_contains, Coord, Geometry};
+use crate::idu::Idu;
+use crate::lot::Lot;
+use crate::merge::side;
+use crate::pci::{Cadastre, Subdivision};
+use crate::svg::escape;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+/// Subset and record identifier of a descriptor.
+type Key<'a> = (&'a str, &'a str);
+
+/// Zoning levels of the `CadastralZoningLevelValue` code list.
+const LEVELS: &str = "http://inspire.ec.europa.eu/codelist/CadastralZoningLevelValue";
+
+/// Writes the parcels, zonings and boundaries of a [`Lot`] as INSPIRE
+/// Cadastral Parcels GML.
+/// ```ignore
+///     let lot = Lot::from_bundle(&EdigeoReader::new(file).read_bundle()).unwrap();
+///     let writer = InspireWriter { boundaries: false, ..InspireWriter::new("FR.DGFIP.CP") };
+///     writer.write(&lot, &mut std::io::stdout())?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InspireWriter {
+    /// Namespace of the `inspireId`s, also prefixing the `gml:id`s
+    pub namespace: String,
+    /// Department of the parcel identifiers, by default the one of the lot's
+    /// [`sheet`](Lot::sheet)
+    pub departement: Option<String>,
+    /// Writes the `cp:CadastralBoundary` features
+    pub boundaries: bool,
+}
+
+impl Default for InspireWriter {
+    fn default() -> Self {
+        Self::new("FR.PCI")
+    }
+}
+
+/// A `cp:CadastralZoning` being written.
+struct Zoning<'a> {
+    local_id: String,
+    level: &'static str,
+    level_name: &'static str,
+    label: String,
+    scale: Option<u32>,
+    upper: Option<String>,
+    geometry: &'a Option<Geometry>,
+}
+
+impl InspireWriter {
+    /// Constructor method for creating a new [`InspireWriter`] identifying
+    /// the features in `namespace`, boundaries included.
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+            departement: None,
+            boundaries: true,
+        }
+    }
+
+    /// `gml:id` of a feature: the namespace, its type and local identifier.
+    fn gml_id(&self, kind: &str, local_id: &str) -> String {
+        escape(&format!("{}.{kind}.{local_id}", self.namespace))
+    }
+
+    /// Writes the lot into a GML file.
+    pub fn write_lot<P: AsRef<Path>>(&self, lot: &Lot, path: P) -> EdigeoResult<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(lot, &mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Writes the lot as a `wfs:FeatureCollection` of CP features.
+    ///
+    /// Fails with [`EdigeoError::UnknownProjection`] when the lot has no known
+    /// projection, and with [`EdigeoError::InvalidIdentifier`] when the
+    /// department or a parcel `IDU` is missing.
+    pub fn write<W: Write>(&self, lot: &Lot, writer: &mut W) -> EdigeoResult<()> {
+        let projection = lot.projection().ok_or_else(|| {
+            EdigeoError::UnknownProjection(lot.geo.reference.code.clone().unwrap_or_default())
+        })?;
+        let srs = format!("http://www.opengis.net/def/crs/EPSG/0/{}", projection.epsg);
+        let departement = self
+            .departement
+            .clone()
+            .or_else(|| lot.sheet.as_ref().map(|sheet| sheet.departement.clone()))
+            .ok_or_else(|| {
+                EdigeoError::InvalidIdentifier(format!("no department for lot {}", lot.name))
+            })?;
+        let cadastre = Cadastre::from_lot(lot);
+        let sheets = &cadastre.subdivisions;
+        // The version of the objects is the last update of their sheet.
+        let version = sheets.iter().find_map(|s| s.dred.or(s.dedi));
+
+        let mut members = Vec::new();
+        let mut parcels = HashMap::new();
+        for parcelle in &cadastre.parcelles {
+            let idu = Idu::from_parcel(&departement, &parcelle.idu)?.to_string();
+            let gml_id = self.gml_id("CadastralParcel", &idu);
+            let reference_point = parcelle.geometry.as_ref().and_then(|g| g.centroid());
+            // The sheet holding the parcel, else its section.
+            let section = parcelle.idu.get(..8).unwrap_or(&parcelle.idu);
+            let zoning =
+                sheets
+                    .iter()
+                    .filter(|s| s.idu.starts_with(section))
+                    .find(|s| contains(s, reference_point))
+                    .map(|s| self.gml_id("CadastralZoning", &format!("{departement}{}", s.idu)))
+                    .or_else(|| {
+                        cadastre.sections.iter().any(|s| s.idu == section).then(|| {
+                            self.gml_id("CadastralZoning", &format!("{departement}{section}"))
+                        })
+                    });
+
+            let mut xml = format!(r#"<cp:CadastralParcel gml:id="{gml_id}">"#);
+            match parcelle.supf {
+                Some(area) => {
+                    xml.push_str(&format!(r#"<cp:areaValue uom="m2">{area}</cp:areaValue>"#))
+                }
+                None => xml.push_str(&nil("areaValue")),
+            }
+            xml.push_str(&lifespan(version));
+            xml.push_str(&geometry_property(
+                "geometry",
+                &gml_id,
+                &srs,
+                parcelle.geometry.as_ref(),
+                false,
+            ));
+            xml.push_str(&self.inspire_id(&idu));
+            xml.push_str(&format!(
+                "<cp:label>{}</cp:label>",
+                escape(idu[10..].trim_start_matches('0'))
+            ));
+            xml.push_str(&format!(
+                "<cp:nationalCadastralReference>{idu}</cp:nationalCadastralReference>"
+            ));
+            xml.push_str(&reference_point_property(&gml_id, &srs, reference_point));
+            match zoning {
+                Some(zoning) => xml.push_str(&format!(r##"<cp:zoning xlink:href="#{zoning}"/>"##)),
+                None => xml.push_str(&nil("zoning")),
+            }
+            xml.push_str("</cp:CadastralParcel>");
+            members.push(xml);
+            parcels.insert(parcelle.id.as_str(), gml_id);
+        }
+
+        let mut zonings = Vec::new();
+        for commune in &cadastre.communes {
+            zonings.push(Zoning {
+                local_id: format!("{departement}{}", commune.idu),
+                level: "1stOrder",
+                level_name: "Commune",
+                label: commune
+                    .tex2
+                    .clone()
+                    .unwrap_or_else(|| format!("{departement}{}", commune.idu)),
+                scale: None,
+                upper: None,
+                geometry: &commune.geometry,
+            });
+        }
+        for section in &cadastre.sections {
+            let commune = section.idu.get(..3).unwrap_or_default();
+            zonings.push(Zoning {
+                local_id: format!("{departement}{}", section.idu),
+                level: "2ndOrder",
+                level_name: "Section",
+                label: section.tex.clone().unwrap_or_else(|| section.idu.clone()),
+                scale: None,
+                upper: cadastre
+                    .communes
+                    .iter()
+                    .any(|c| c.idu == commune)
+                    .then(|| format!("{departement}{commune}")),
+                geometry: &section.geometry,
+            });
+        }
+        for sheet in sheets {
+            let section = sheet.idu.get(..8).unwrap_or_default();
+            zonings.push(Zoning {
+                local_id: format!("{departement}{}", sheet.idu),
+                level: "3rdOrder",
+                level_name: "Feuille",
+                label: sheet.idu.get(6..).unwrap_or_default().to_string(),
+                scale: sheet.eor,
+                upper: cadastre
+                    .sections
+                    .iter()
+                    .any(|s| s.idu == section)
+                    .then(|| format!("{departement}{section}")),
+                geometry: &sheet.geometry,
+            });
+        }
+        for zoning in &zonings {
+            members.push(self.zoning(zoning, &srs, version));
+        }
+
+        if self.boundaries {
+            members.extend(self.boundaries(lot, &parcels, &srs, version));
+        }
+
+        write!(
+            writer,
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                "\n",
+                r#"<wfs:FeatureCollection xmlns:wfs="http://www.opengis.net/wfs/2.0" "#,
+                r#"xmlns:cp="http://inspire.ec.europa.eu/schemas/cp/4.0" "#,
+                r#"xmlns:base="http://inspire.ec.europa.eu/schemas/base/3.3" "#,
+                r#"xmlns:gml="http://www.opengis.net/gml/3.2" "#,
+                r#"xmlns:gmd="http://www.isotc211.org/2005/gmd" "#,
+                r#"xmlns:xlink="http://www.w3.org/1999/xlink" "#,
+                r#"xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" "#,
+                r#"xsi:schemaLocation="http://www.opengis.net/wfs/2.0 http://schemas.opengis.net/wfs/2.0/wfs.xsd "#,
+                r#"http://inspire.ec.europa.eu/schemas/cp/4.0 https://inspire.ec.europa.eu/schemas/cp/4.0/CadastralParcels.xsd" "#,
+                r#"timeStamp="{}" numberMatched="{}" numberReturned="{}">"#,
+                "\n"
+            ),
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            members.len(),
+            members.len()
+        )?;
+        for member in members {
+            writeln!(writer, "<wfs:member>{member}</wfs:member>")?;
+        }
+        writeln!(writer, "</wfs:FeatureCollection>")?;
+        Ok(())
+    }
+
+    fn inspire_id(&self, local_id: &str) -> String {
+        format!(
+            "<cp:inspireId><base:Identifier><base:localId>{}</base:localId><base:namespace>{}</base:namespace></base:Identifier></cp:inspireId>",
+            escape(local_id),
+            escape(&self.namespace)
+        )
+    }
+
+    fn zoning(&self, zoning: &Zoning, srs: &str, version: Option<NaiveDate>) -> String {
+        let gml_id = self.gml_id("CadastralZoning", &zoning.local_id);
+        let mut xml = format!(r#"<cp:CadastralZoning gml:id="{gml_id}">"#);
+        xml.push_str(&lifespan(version));
+        xml.push_str(&nil("estimatedAccuracy"));
+        xml.push_str(&geometry_property(
+            "geometry",
+            &gml_id,
+            srs,
+            zoning.geometry.as_ref(),
+            true,
+        ));
+        xml.push_str(&self.inspire_id(&zoning.local_id));
+        xml.push_str(&format!("<cp:label>{}</cp:label>", escape(&zoning.label)));
+        xml.push_str(&format!(
+            r#"<cp:level xlink:href="{LEVELS}/{}"/>"#,
+            zoning.level
+        ));
+        xml.push_str(&format!(
+            "<cp:levelName><gmd:LocalisedCharacterString>{}</gmd:LocalisedCharacterString></cp:levelName>",
+            zoning.level_name
+        ));
+        xml.push_str(&nil("name"));
+        // The element is misspelt in the CP schema.
+        xml.push_str(&format!(
+            "<cp:nationalCadastalZoningReference>{}</cp:nationalCadastalZoningReference>",
+            escape(&zoning.local_id)
+        ));
+        match zoning.scale {
+            Some(scale) => xml.push_str(&format!(
+                "<cp:originalMapScaleDenominator>{scale}</cp:originalMapScaleDenominator>"
+            )),
+            None => xml.push_str(&nil("originalMapScaleDenominator")),
+        }
+        let centroid = zoning.geometry.as_ref().and_then(|g| g.centroid());
+        xml.push_str(&reference_point_property(&gml_id, srs, centroid));
+        if let Some(upper) = &zoning.upper {
+            xml.push_str(&format!(
+                r##"<cp:upperLevelUnit xlink:href="#{}"/>"##,
+                self.gml_id("CadastralZoning", upper)
+            ));
+        }
+        xml.push_str("</cp:CadastralZoning>");
+        xml
+    }
+
+    /// One boundary per arc between two parcels, or between a parcel and the
+    /// outside of the parcels.
+    fn boundaries(
+        &self,
+        lot: &Lot,
+        parcels: &HashMap<&str, String>,
+        srs: &str,
+        version: Option<NaiveDate>,
+    ) -> Vec<String> {
+        let mut face_parcel: HashMap<Key, &str> = HashMap::new();
+        let mut arc_sides: HashMap<Key, [Option<Key>; 2]> = HashMap::new();
+        for vec in lot.subsets.values() {
+            for link in &vec.links {
+                if let Some((arc, face, left)) = side(link) {
+                    let sides = arc_sides.entry((&arc.subset, &arc.id)).or_default();
+                    sides[usize::from(!left)] = Some((&face.subset, &face.id));
+                    continue;
+                }
+                let Some(parcel) = link
+                    .members
+                    .iter()
+                    .find(|m| m.kind == "FEA")
+                    .and_then(|object| parcels.get(object.id.as_str()))
+                else {
+                    continue;
+                };
+                for face in link.members.iter().filter(|m| m.kind == "PFE") {
+                    face_parcel.insert((&face.subset, &face.id), parcel);
+                }
+            }
+        }
+
+        let mut boundaries = Vec::new();
+        for (subset, vec) in &lot.subsets {
+            for arc in &vec.arcs {
+                let Some(sides) = arc_sides.get(&(subset.as_str(), arc.id.as_str())) else {
+                    continue;
+                };
+                let linked =
+                    sides.map(|face| face.and_then(|face| face_parcel.get(&face).copied()));
+                match linked {
+                    [None, None] => continue,
+                    // Arcs inside a parcel of several faces are no boundary.
+                    [Some(left), Some(right)] if left == right => continue,
+                    _ => {}
+                }
+
+                let local_id = format!("{}.{subset}.{}", lot.name, arc.id);
+                let gml_id = self.gml_id("CadastralBoundary", &local_id);
+                let mut xml = format!(r#"<cp:CadastralBoundary gml:id="{gml_id}">"#);
+                xml.push_str(&lifespan(version));
+                xml.push_str(&nil("estimatedAccuracy"));
+                xml.push_str(&format!(
+                    r#"<cp:geometry><gml:LineString gml:id="{gml_id}.geometry" srsName="{srs}" srsDimension="2"><gml:posList>{}</gml:posList></gml:LineString></cp:geometry>"#,
+                    pos_list(&arc.points)
+                ));
+                xml.push_str(&self.inspire_id(&local_id));
+                for parcel in linked.into_iter().flatten() {
+                    xml.push_str(&format!(r##"<cp:parcel xlink:href="#{parcel}"/>"##));
+                }
+                xml.push_str("</cp:CadastralBoundary>");
+                boundaries.push(xml);
+            }
+        }
+        boundaries
+    }
+}
+
+/// Whether the polygon of a sheet contains a point.
+fn contains(sheet: &Subdivision, point: Option<Coord>) -> bool {
+    let Some(point) = point else {
+        return false;
+    };
+    match &sheet.geometry {
+        Some(Geometry::Polygon(rings)) => rings.first().is_some_and(|r| ring_contains(r, &point)),
+        Some(Geometry::MultiPolygon(polygons)) => polygons
+            .iter()
+            .filter_map(|rings| rings.first())
+            .any(|r| ring_contains(r, &point)),
+        _ => false,
+    }
+}
+
+/// A voidable property without value.
+fn nil(name: &str) -> String {
+    format!(r#"<cp:{name} xsi:nil="true" nilReason="unknown"/>"#)
+}
+
+fn lifespan(version: Option<NaiveDate>) -> String {
+    match version {
+        Some(date) => format!(
+            "<cp:beginLifespanVersion>{}</cp:beginLifespanVersion>",
+            date.format("%Y-%m-%dT00:00:00Z")
+        ),
+        None => nil("beginLifespanVersion"),
+    }
+}
+
+fn reference_point_property(gml_id: &str, srs: &str, point: Option<Coord>) -> String {
+    match point {
+        Some(c) => format!(
+            r#"<cp:referencePoint><gml:Point gml:id="{gml_id}.referencePoint" srsName="{srs}" srsDimension="2"><gml:pos>{} {}</gml:pos></gml:Point></cp:referencePoint>"#,
+            c.x, c.y
+        ),
+        None => nil("referencePoint"),
+    }
+}
+
+fn pos_list(coords: &[Coord]) -> String {
+    coords
+        .iter()
+        .map(|c| format!("{} {}", c.x, c.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A `gml:Polygon`, its rings with their exterior first.
+fn polygon(gml_id: &str, srs: Option<&str>, rings: &[Vec<Coord>]) -> String {
+    let srs = srs
+        .map(|srs| format!(r#" srsName="{srs}" srsDimension="2""#))
+        .unwrap_or_default();
+    let mut xml = format!(r#"<gml:Polygon gml:id="{gml_id}"{srs}>"#);
+    for (i, ring) in rings.iter().enumerate() {
+        let boundary = if i == 0 { "exterior" } else { "interior" };
+        xml.push_str(&format!(
+            "<gml:{boundary}><gml:LinearRing><gml:posList>{}</gml:posList></gml:LinearRing></gml:{boundary}>",
+            pos_list(ring)
+        ));
+    }
+    xml.push_str("</gml:Polygon>");
+    xml
+}
+
+/// A surface property: a `gml:Polygon`, or a `gml:MultiSurface` for several
+/// polygons or when `multi` is required.
+fn geometry_property(
+    name: &str,
+    gml_id: &str,
+    srs: &str,
+    geometry: Option<&Geometry>,
+    multi: bool,
+) -> String {
+    let polygons = match geometry {
+        Some(Geometry::Polygon(rings)) => vec![rings.clone()],
+        Some(Geometry::MultiPolygon(polygons)) => polygons.clone(),
+        _ => return nil(name),
+    };
+    let id = format!("{gml_id}.{name}");
+    let surface = match (multi, polygons.as_slice()) {
+        (false, [rings]) => polygon(&id, Some(srs), rings),
+        _ => {
+            let mut xml =
+                format!(r#"<gml:MultiSurface gml:id="{id}" srsName="{srs}" srsDimension="2">"#);
+            for (i, rings) in polygons.iter().enumerate() {
+                xml.push_str(&format!(
+                    "<gml:surfaceMember>{}</gml:surfaceMember>",
+                    polygon(&format!("{id}.{}", i + 1), None, rings)
+                ));
+            }
+            xml.push_str("</gml:MultiSurface>");
+            xml
+        }
+    };
+    format!("<cp:{name}>{surface}</cp:{name}>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Geometry {
+        Geometry::Polygon(vec![[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+            (0.0, 0.0),
+        ]
+        .iter()
+        .map(|&(x, y)| Coord { x, y })
+        .collect()])
+    }
+
+    #[test]
+    fn test_geometry_property() {
+        let srs = "http://www.opengis.net/def/crs/EPSG/0/2154";
+        let xml = geometry_property("geometry", "FR.PCI.CP.1", srs, Some(&square()), false);
+        assert_eq!(
+            xml,
+            format!(
+                concat!(
+                    r#"<cp:geometry><gml:Polygon gml:id="FR.PCI.CP.1.geometry" srsName="{}" srsDimension="2">"#,
+                    "<gml:exterior><gml:LinearRing><gml:posList>0 0 2 0 2 2 0 2 0 0</gml:posList></gml:LinearRing></gml:exterior>",
+                    "</gml:Polygon></cp:geometry>"
+                ),
+                srs
+            )
+        );
+        let xml = geometry_property("geometry", "FR.PCI.CZ.1", srs, Some(&square()), true);
+        assert!(xml.starts_with(r#"<cp:geometry><gml:MultiSurface gml:id="FR.PCI.CZ.1.geometry""#));
+        assert!(xml.contains(r#"<gml:surfaceMember><gml:Polygon gml:id="FR.PCI.CZ.1.geometry.1">"#));
+        assert_eq!(
+            geometry_property("geometry", "FR.PCI.CP.1", srs, None, false),
+            r#"<cp:geometry xsi:nil="true" nilReason="unknown"/>"#
+        );
+    }
+
+    #[test]
+    fn test_lifespan() {
+        let date = NaiveDate::from_ymd_opt(1939, 1, 1);
+        assert_eq!(
+            lifespan(date),
+            "<cp:beginLifespanVersion>1939-01-01T00:00:00Z</cp:beginLifespanVersion>"
+        );
+        assert_eq!(
+            InspireWriter::default().gml_id("CadastralParcel", "740240000A0033"),
+            "FR.PCI.CadastralParcel.740240000A0033"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Contains the [`Label`]s of a lot: the write-attribute objects
+//! (`ID_S_OBJ_Z_1_2_2`) positioning the text of another object on the sheet.
+//!
+//! Each label is linked to its anchor node by a construction relation and to
+//! the object it annotates by the `IWW` ("has for toponym") association. Its
+//! `ATR` attribute names the attribute of that object holding the text, e.g.
+//! `TEX_id` or `TEX2_id` for the second word of a place name.
+use crate::geometry::Coord;
+use crate::lot::{self, find_attribute, Lot};
+use crate::records::{Attribute, Object, Reference};
+use std::collections::HashMap;
+
+/// SCD identifier of the write-attribute object type.
+pub const LABEL_OBJECT: &str = "ID_S_OBJ_Z_1_2_2";
+
+/// A text label positioned on the sheet, with its lettering.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Label {
+    /// Record identifier of the write-attribute object, e.g.
+    /// `Attribut_TEX_id_Objet_243368`
+    pub id: String,
+    /// Identifier of the subset holding the label
+    pub subset: String,
+    /// Reference node the text is aligned on
+    pub anchor: Option<Coord>,
+    /// Text displayed, the value of the written attribute of the annotated
+    /// object
+    pub text: Option<String>,
+    /// SCD identifier of the written attribute (`ATR`), e.g. `TEX_id`
+    pub attribute: Option<String>,
+    /// Annotated object, e.g. `ED0A01;SeTOP_1;FEA;Objet_243368`
+    pub object: Option<Reference>,
+    /// Object type of the annotated object in the SCD, e.g. `PARCELLE_id`
+    pub object_type: Option<String>,
+    /// Typeface name (`FON`), e.g. `Times New Roman`
+    pub font: Option<String>,
+    /// Character height (`HEI`)
+    pub height: Option<f64>,
+    /// Code of the unit of the character height (`TYU`)
+    pub height_unit: Option<String>,
+    /// Width to height ratio of the characters (`CEF`)
+    pub width_factor: Option<f64>,
+    /// Spacing between characters (`CSP`)
+    pub spacing: Option<f64>,
+    /// Angle of the base line in degrees, counterclockwise from the X axis,
+    /// from the base vector (`DI3`, `DI4`)
+    pub rotation: f64,
+    /// Writing direction code (`TPA`): `1` right, `2` left, `3` up, `4` down
+    pub direction: Option<String>,
+    /// Horizontal alignment code (`HTA`) on the anchor: `1` normal, `2`
+    /// left, `3` middle, `4` right
+    pub horizontal_alignment: Option<String>,
+    /// Vertical alignment code (`VTA`) on the anchor: `1` normal, `2` top,
+    /// `3` cap, `4` half, `5` base, `6` bottom
+    pub vertical_alignment: Option<String>,
+}
+
+fn text(attributes: &[Attribute], id: &str) -> Option<String> {
+    find_attribute(attributes, id).and_then(lot::text)
+}
+
+fn float(attributes: &[Attribute], id: &str) -> Option<f64> {
+    find_attribute(attributes, id).and_then(lot::float)
+}
+
+/// Angle in degrees of the base vector, or of the height vector turned a
+/// quarter clockwise when the base vector is missing.
+fn rotation(attributes: &[Attribute]) -> f64 {
+    let vector = |x: &str, y: &str| Some((float(attributes, x)?, float(attributes, y)?));
+    let angle = match (
+        vector("ID_S_ATT_DI3", "ID_S_ATT_DI4"),
+        vector("ID_S_ATT_DI1", "ID_S_ATT_DI2"),
+    ) {
+        (Some((x, y)), _) if x != 0.0 || y != 0.0 => y.atan2(x).to_degrees(),
+        (_, Some((x, y))) if x != 0.0 || y != 0.0 => y.atan2(x).to_degrees() - 90.0,
+        _ => 0.0,
+    };
+    // Normalizes -0 and angles below -180.
+    match angle.rem_euclid(360.0) {
+        angle if angle > 180.0 => angle - 360.0,
+        angle => angle + 0.0,
+    }
+}
+
+fn is_label(object: &Object) -> bool {
+    object
+        .definition
+        .as_ref()
+        .is_some_and(|definition| definition.id == LABEL_OBJECT)
+}
+
+impl Lot {
+    /// Returns the write-attribute objects of the lot as [`Label`]s, with
+    /// their anchor, the text they display and the object they annotate.
+    pub fn labels(&self) -> Vec<Label> {
+        let mut nodes = HashMap::new();
+        let mut objects = HashMap::new();
+        for (subset, vec) in &self.subsets {
+            for node in &vec.nodes {
+                nodes.insert((subset.as_str(), node.id.as_str()), node);
+            }
+            for object in &vec.objects {
+                objects.insert((subset.as_str(), object.id.as_str()), object);
+            }
+        }
+
+        // Anchor node and annotated object of each label, from the links
+        // having the label as first object member.
+        let mut anchors = HashMap::new();
+        let mut annotated = HashMap::new();
+        for vec in self.subsets.values() {
+            for link in &vec.links {
+                let mut features = link.members.iter().filter(|m| m.kind == "FEA");
+                let Some(label) = features.next() else {
+                    continue;
+                };
+                let label = (label.subset.as_str(), label.id.as_str());
+                if !objects.get(&label).is_some_and(|object| is_label(object)) {
+                    continue;
+                }
+                if let Some(object) = features.next() {
+                    annotated.insert(label, object);
+                }
+                if let Some(node) = link.members.iter().find(|m| m.kind == "PNO") {
+                    anchors.insert(label, (node.subset.as_str(), node.id.as_str()));
+                }
+            }
+        }
+
+        let mut labels = Vec::new();
+        for (subset, vec) in &self.subsets {
+            for object in vec.objects.iter().filter(|object| is_label(object)) {
+                let key = (subset.as_str(), object.id.as_str());
+                let attributes = &object.attributes;
+                let attribute = find_attribute(attributes, "ID_S_ATT_ATR")
+                    .and_then(Reference::from_format)
+                    .map(|reference| reference.id);
+                let target = annotated.get(&key).copied();
+                let target_object = target
+                    .and_then(|target| objects.get(&(target.subset.as_str(), target.id.as_str())));
+
+                labels.push(Label {
+                    id: object.id.clone(),
+                    subset: subset.clone(),
+                    anchor: anchors.get(&key).and_then(|node| nodes.get(node)?.coord),
+                    text: attribute
+                        .as_deref()
+                        .zip(target_object)
+                        .and_then(|(attribute, target)| text(&target.attributes, attribute)),
+                    attribute,
+                    object: target.cloned(),
+                    object_type: target_object
+                        .and_then(|target| target.definition.as_ref())
+                        .map(|definition| definition.id.clone()),
+                    font: text(attributes, "ID_S_ATT_FON"),
+                    height: float(attributes, "ID_S_ATT_HEI"),
+                    height_unit: text(attributes, "ID_S_ATT_TYU"),
+                    width_factor: float(attributes, "ID_S_ATT_CEF"),
+                    spacing: float(attributes, "ID_S_ATT_CSP"),
+                    rotation: rotation(attributes),
+                    direction: text(attributes, "ID_S_ATT_TPA"),
+                    horizontal_alignment: text(attributes, "ID_S_ATT_HTA"),
+                    vertical_alignment: text(attributes, "ID_S_ATT_VTA"),
+                });
+            }
+        }
+        labels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockParse, FormatResult, VECFile};
+
+    const VEC: &str = "RTYSA03:PNO\nRIDSA07:Noeud_1\nSCPCP28:ED0A01;SeSD;PGE;ID_S_PRI_NOD\n\
+        TYPSN01:2\nCORCC23:+965008.83;+6560831.71;\nATCSN01:0\nQACSN01:0\n\
+        RTYSA03:FEA\nRIDSA07:Objet_1\nSCPCP27:ED0A01;SeSD;OBJ;PARCELLE_id\nATCSN01:1\n\
+        ATPCP22:ED0A01;SeSD;ATT;TEX_id\nATVST02:33\nQACSN01:0\n\
+        RTYSA03:FEA\nRIDSA09:Libelle_1\nSCPCP32:ED0A01;SeSD;OBJ;ID_S_OBJ_Z_1_2_2\nATCSN01:6\n\
+        ATPCP28:ED0A01;SeSD;ATT;ID_S_ATT_FON\nATVST15:Times New Roman\n\
+        ATPCP28:ED0A01;SeSD;ATT;ID_S_ATT_HEI\nATVSR09:+2.000000\n\
+        ATPCP28:ED0A01;SeSD;ATT;ID_S_ATT_DI3\nATVSR09:+0.000000\n\
+        ATPCP28:ED0A01;SeSD;ATT;ID_S_ATT_DI4\nATVSR09:+1.000000\n\
+        ATPCP28:ED0A01;SeSD;ATT;ID_S_ATT_HTA\nATVSA01:1\n\
+        ATPCP28:ED0A01;SeSD;ATT;ID_S_ATT_ATR\nATVCP22:ED0A01;SeSD;ATT;TEX_id\nQACSN01:0\n\
+        RTYSA03:LNK\nRIDSA05:Compo\nSCPCP32:ED0A01;SeSD;REL;ID_S_RCO_Z_1_2_2\nFTCSN01:2\n\
+        FTPCP28:ED0A01;SeTOP_1;FEA;Libelle_1\nFTPCP26:ED0A01;SeTOP_1;PNO;Noeud_1\n\
+        ATCSN01:0\nQACSN01:0\n\
+        RTYSA03:LNK\nRIDSA03:IWW\nSCPCP28:ED0A01;SeSD;ASS;IS_S_REL_IWW\nFTCSN01:2\n\
+        FTPCP28:ED0A01;SeTOP_1;FEA;Libelle_1\nFTPCP26:ED0A01;SeTOP_1;FEA;Objet_1\n\
+        ATCSN01:0\nQACSN01:0\n";
+
+    #[test]
+    fn test_labels() {
+        let mut lot = Lot::default();
+        lot.subsets
+            .insert("SeTOP_1".to_string(), VECFile::parse(VEC).unwrap());
+
+        let labels = lot.labels();
+        assert_eq!(labels.len(), 1);
+        let label = &labels[0];
+        assert_eq!(label.id, "Libelle_1");
+        assert_eq!(
+            label.anchor,
+            Some(Coord {
+                x: 965008.83,
+                y: 6560831.71
+            })
+        );
+        assert_eq!(label.text.as_deref(), Some("33"));
+        assert_eq!(label.attribute.as_deref(), Some("TEX_id"));
+        assert_eq!(label.object.as_ref().unwrap().id, "Objet_1");
+        assert_eq!(label.object_type.as_deref(), Some("PARCELLE_id"));
+        assert_eq!(label.font.as_deref(), Some("Times New Roman"));
+        assert_eq!(label.height, Some(2.0));
+        assert_eq!(label.width_factor, None);
+        assert_eq!(label.rotation, 90.0);
+        assert_eq!(label.horizontal_alignment.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_rotation() {
+        let attribute = |id: &str, value: f64| Attribute {
+            id: id.to_string(),
+            value: Some(FormatResult::Float(value)),
+            raw: String::new(),
+        };
+        assert_eq!(rotation(&[]), 0.0);
+        assert_eq!(
+            rotation(&[
+                attribute("ID_S_ATT_DI3", 1.0),
+                attribute("ID_S_ATT_DI4", -0.0)
+            ]),
+            0.0
+        );
+        assert_eq!(
+            rotation(&[
+                attribute("ID_S_ATT_DI3", -1.0),
+                attribute("ID_S_ATT_DI4", -1.0)
+            ]),
+            -135.0
+        );
+        // Without base vector, the height vector pointing left gives a base
+        // line pointing up.
+        assert_eq!(
+            rotation(&[
+                attribute("ID_S_ATT_DI1", -1.0),
+                attribute("ID_S_ATT_DI2", 0.0)
+            ]),
+            90.0
+        );
+    }
+}
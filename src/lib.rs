@@ -13,16 +13,54 @@
 //!
 //! An EDIGéO lot is described in several plain text files. These files are listed below:
 //! `.GEN` - General Information
+pub mod archive;
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod blocks;
 pub mod bundle;
+pub mod crs;
+pub mod csv;
+#[cfg(feature = "geozero")]
+pub mod datasource;
+pub mod diagnostics;
+pub mod diff;
+pub mod dxf;
 pub mod error;
 pub mod format;
+#[cfg(feature = "geo-types")]
+pub mod geo;
+pub mod geometry;
 pub mod header;
+pub mod idu;
+#[cfg(feature = "rstar")]
+pub mod index;
+#[cfg(feature = "pci")]
+pub mod inspire;
+pub mod label;
 pub mod line;
+pub mod lot;
+pub mod merge;
+pub mod mvt;
+#[cfg(feature = "pci")]
+pub mod pci;
 pub mod reader;
+pub mod records;
+pub mod shapefile;
+pub mod svg;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod topology;
+pub mod validation;
+pub mod wkb;
 
 pub use blocks::*;
 pub use format::*;
+pub use geometry::*;
 pub use header::*;
+pub use idu::*;
 pub use line::*;
+pub use lot::*;
 pub use reader::*;
+pub use records::*;
+pub use topology::*;
+pub use validation::*;
@@ -1,164 +1,153 @@
 //! Contains Edigeo [`Line`] parsing logic.
+use crate::error::*;
 use crate::format::*;
 use crate::header::*;
+use crate::reader::decode_file;
 
 /// Represents a line with its header, raw value, and parsed result.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Line {
     /// The header associated with the line.
     pub header: Header,
 
     /// The parsed result of the raw value, if available.
     pub parsed_value: Option<FormatResult>,
+
+    /// The value as written in the file, decoded, e.g. `+37054.`.
+    pub raw_value: String,
+}
+
+/// How the size declared in the header of a line is enforced on its value.
+///
+/// The standard counts sizes in bytes of the file's character set, so they
+/// are checked on the raw bytes before decoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValueSize {
+    /// Fail with [`EdigeoError::ValueSizeMismatch`] when the value does not
+    /// have exactly the declared number of bytes
+    #[default]
+    Check,
+    /// Trust the declared size and keep only that many bytes of the value,
+    /// as for fixed-width records
+    Trust,
+    /// Keep the whole value whatever the declared size
+    Ignore,
 }
 
 impl Line {
-    /// Parses a line of text into a `Line` struct.
+    /// Parses a line of decoded text into a `Line` struct.
     ///
     /// The line is expected to be in the format `header:raw_value`.
     /// The header is parsed into a `Header` struct, and the raw value
     /// is processed to obtain a parsed value.
-    pub fn parse_line(line: &str) -> Self {
-        let (head, raw_value) = line.split_once(":").unwrap();
-        let header = Header::parse_header(head).unwrap();
-        Self {
-            header: header.clone(),
-            parsed_value: parse_value(&header, raw_value),
-        }
+    pub fn parse_line(line: &str) -> EdigeoResult<Self> {
+        let (head, raw_value) = line
+            .split_once(':')
+            .ok_or_else(|| EdigeoError::InvalidLine(line.to_string()))?;
+        let header = Header::parse_header(head)?;
+        Ok(Self {
+            parsed_value: parse_value(&header, raw_value)?,
+            raw_value: raw_value.to_string(),
+            header,
+        })
     }
 
-    /// Checks if [`Line`] is empty or Newline and returns True
-    #[allow(unconditional_recursion)]
-    pub fn is_empty(&self) -> bool {
-        self.is_empty()
+    /// Parses a raw line of a file, checking the size of its value on the
+    /// bytes as [`ValueSize`] requires before decoding it from `WINDOWS_1252`.
+    pub fn parse_bytes(line: &[u8], value_size: ValueSize) -> EdigeoResult<Self> {
+        let invalid = || EdigeoError::InvalidLine(String::from_utf8_lossy(line).into_owned());
+        let colon = line
+            .iter()
+            .position(|byte| *byte == b':')
+            .ok_or_else(invalid)?;
+        let header = Header::parse_header(std::str::from_utf8(&line[..colon])?)?;
+
+        let mut raw_value = &line[colon + 1..];
+        match value_size {
+            ValueSize::Check if raw_value.len() != header.value_size => {
+                return Err(EdigeoError::ValueSizeMismatch {
+                    code: header.code.to_string(),
+                    declared: header.value_size,
+                    found: raw_value.len(),
+                });
+            }
+            ValueSize::Trust => raw_value = &raw_value[..raw_value.len().min(header.value_size)],
+            _ => {}
+        }
+
+        let raw_value = decode_file(raw_value).into_owned();
+        Ok(Self {
+            parsed_value: get_parser(&header).parse(&raw_value),
+            raw_value,
+            header,
+        })
     }
 }
 
 /// Parses a raw value according to the specifications in the header.
 ///
-/// Returns an `Option<FormatResult>`. If the raw value size does not match
-/// the expected size from the header, it panics. Otherwise, it uses a parser
-/// specific to the header to parse the value.
-pub fn parse_value(header: &Header, raw_value: &str) -> Option<FormatResult> {
-    if header.value_size != raw_value.chars().count() {
-        panic!("value size mismatch!");
+/// The value is expected to be decoded from `WINDOWS_1252`, where every
+/// character stands for one byte of the file. Returns
+/// [`EdigeoError::ValueSizeMismatch`] if its size does not match the size
+/// declared by the header, otherwise parses it with the parser specific to
+/// the header.
+pub fn parse_value(header: &Header, raw_value: &str) -> EdigeoResult<Option<FormatResult>> {
+    let found = raw_value.chars().count();
+    if header.value_size != found {
+        return Err(EdigeoError::ValueSizeMismatch {
+            code: header.code.to_string(),
+            declared: header.value_size,
+            found,
+        });
     }
 
     let parser = get_parser(header);
-    parser.parse(raw_value)
+    Ok(parser.parse(raw_value))
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-
-//     #[test]
-//     fn test_value_parse_passes() {
-//         let test_cases = [("BOMT 12:E0000A01.THF", "E0000A01.THF", None)];
-//         for (line, raw_value, result) in test_cases {
-//             let header = Header::parse_header(line).unwrap();
-//             let parsed_value = parse_value(&header, raw_value);
-//             assert_eq!(result, parsed_value);
-//         }
-//     }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-//     #[test]
-//     #[should_panic]
-//     fn test_value_parse_incorrect_passes() {
-//         let test_cases = [(
-//             "BOMT 12:E0000A01.THF",
-//             "E0000A01.THF",
-//             Some(FormatResult::Text("E0000A01.THF".to_string())),
-//         )];
-//         for (line, raw_value, result) in test_cases {
-//             let header = Header::parse_header(line).unwrap();
-//             let parsed_value = parse_value(&header, raw_value);
-//             assert_eq!(result, parsed_value);
-//         }
-//     }
+    #[test]
+    fn test_line_parse_bytes_counts_bytes() {
+        // `é` is a single byte in WINDOWS_1252.
+        let line = Line::parse_bytes(b"ATVST06:Cr\xe9ole", ValueSize::Check).unwrap();
+        assert_eq!(
+            line.parsed_value,
+            Some(FormatResult::Text("Créole".to_string()))
+        );
 
-// #[test]
-// fn test_line_parse_passes() {
-//     let test_cases = [(
-//         "BOMT 12:E0000A01.THF",
-//         Line {
-//             header: Header {
-//                 code: "BOM".to_string(),
-//                 value_type: ValueType::T,
-//                 value_format: ValueFormat::WhiteSpace,
-//                 value_size: 12,
-//             },
-//             raw_value: "E0000A01.THF".to_string(),
-//             parsed_value: None,
-//         },
-//     )];
-//     for (line, result) in test_cases {
-//         let line = Line::parse_line(line);
-//         assert_eq!(result, line);
-//     }
-// }
+        // The same value re-saved as UTF-8 is one byte too long.
+        let utf8 = "ATVST06:Créole".as_bytes();
+        assert!(matches!(
+            Line::parse_bytes(utf8, ValueSize::Check),
+            Err(EdigeoError::ValueSizeMismatch {
+                declared: 6,
+                found: 7,
+                ..
+            })
+        ));
+        assert!(Line::parse_bytes(utf8, ValueSize::Ignore).is_ok());
 
-// #[test]
-// #[should_panic]
-// fn test_line_parse_incorrect_passes() {
-//     let test_cases = [(
-//         "BOMT 12:E0000A01.THF",
-//         Line {
-//             header: Header {
-//                 code: "BOM".to_string(),
-//                 value_type: ValueType::T,
-//                 value_format: ValueFormat::WhiteSpace,
-//                 value_size: 7,
-//             },
-//             raw_value: "E0000A01.THF".to_string(),
-//             parsed_value: None,
-//         },
-//     )];
-//     for (line, result) in test_cases {
-//         let line = Line::parse_line(line);
-//         assert_eq!(result, line);
-//     }
-// }
-//     #[test]
-//     fn test_line_parse_passes() {
-//         let test_cases = [(
-//             "BOMT 12:E0000A01.THF",
-//             Line {
-//                 header: Header {
-//                     code: Code::KWCode(KeyWordCode::BOM),
-//                     value_type: ValueType::T,
-//                     value_format: ValueFormat::WhiteSpace,
-//                     value_size: 12,
-//                 },
-//                 raw_value: "E0000A01.THF".to_string(),
-//                 parsed_value: None,
-//             },
-//         )];
-//         for (line, result) in test_cases {
-//             let line = Line::parse_line(line);
-//             assert_eq!(result, line);
-//         }
-//     }
+        let line = Line::parse_bytes(b"RIDSA06:Arc_12 ", ValueSize::Trust).unwrap();
+        assert_eq!(
+            line.parsed_value,
+            Some(FormatResult::Text("Arc_12".to_string()))
+        );
+    }
 
-//     #[test]
-//     #[should_panic]
-//     fn test_line_parse_incorrect_passes() {
-//         let test_cases = [(
-//             "BOMT 12:E0000A01.THF",
-//             Line {
-//                 header: Header {
-//                     code: Code::KWCode(KeyWordCode::BOM),
-//                     value_type: ValueType::T,
-//                     value_format: ValueFormat::WhiteSpace,
-//                     value_size: 7,
-//                 },
-//                 raw_value: "E0000A01.THF".to_string(),
-//                 parsed_value: None,
-//             },
-//         )];
-//         for (line, result) in test_cases {
-//             let line = Line::parse_line(line);
-//             assert_eq!(result, line);
-//         }
-//     }
-// }
+    #[test]
+    fn test_line_parse_invalid() {
+        assert!(matches!(
+            Line::parse_line("RIDSA06"),
+            Err(EdigeoError::InvalidLine(_))
+        ));
+        assert!(matches!(
+            Line::parse_line("RIDSA07:Arc_12"),
+            Err(EdigeoError::ValueSizeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,623 @@
+//! Contains the [`Lot`], a fully parsed EDIGéO lot, and the assembly of its
+//! [`Feature`]s from the vector topology.
+use crate::bundle::EdigeoBundle;
+use crate::crs::Projection;
+use crate::diagnostics::DiagnosticSink;
+use crate::error::EdigeoResult;
+use crate::geometry::{build_polygons, merge_lines, signed_area, Geometry};
+use crate::idu::SheetId;
+use crate::records::*;
+use crate::{BlockParse, FormatResult, THFFile, ValueSize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A parsed EDIGéO lot with all of its descriptor files.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lot {
+    /// Name of the lot (`LON`), e.g. `ED0A01`
+    pub name: String,
+    /// General information
+    pub gen: GENFile,
+    /// Geographic reference
+    pub geo: GEOFile,
+    /// Nomenclature
+    pub dic: DICFile,
+    /// Conceptual data model
+    pub scd: SCDFile,
+    /// Quality descriptors
+    pub qal: QALFile,
+    /// Vector data, keyed by subset identifier (e.g. `SeTOP_1`)
+    pub subsets: BTreeMap<String, VECFile>,
+    /// Identifier of the sheet, found in the name of the bundle
+    pub sheet: Option<SheetId>,
+    /// Spatial index, built on the first query
+    #[cfg(feature = "rstar")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "crate::index::IndexCache::is_unset")
+    )]
+    pub index: crate::index::IndexCache,
+}
+
+/// A geographic object of a lot with its attributes and assembled geometry.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Feature {
+    /// Record identifier of the `FEA` descriptor, e.g. `Objet_243907`
+    pub id: String,
+    /// Identifier of the subset holding the object
+    pub subset: String,
+    /// Object type in the SCD, e.g. `PARCELLE_id`
+    pub object_type: String,
+    /// Attribute values
+    pub attributes: Vec<Attribute>,
+    /// Reference to the quality descriptor
+    pub quality: Option<Reference>,
+    /// Geometry assembled from the nodes, arcs or faces composing the object
+    pub geometry: Option<Geometry>,
+}
+
+impl Feature {
+    /// Returns the value of the attribute with the given SCD identifier.
+    pub fn attribute(&self, id: &str) -> Option<&FormatResult> {
+        find_attribute(&self.attributes, id)
+    }
+
+    /// Returns the trimmed text of an attribute, `None` when it is blank.
+    pub fn text(&self, id: &str) -> Option<String> {
+        self.attribute(id).and_then(text)
+    }
+
+    /// Returns the number held by an attribute, see [`float`].
+    pub fn float(&self, id: &str) -> Option<f64> {
+        self.attribute(id).and_then(float)
+    }
+}
+
+/// Returns the value of the attribute with the given SCD identifier.
+pub(crate) fn find_attribute<'a>(
+    attributes: &'a [Attribute],
+    id: &str,
+) -> Option<&'a FormatResult> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.id == id)
+        .and_then(|attribute| attribute.value.as_ref())
+}
+
+/// Returns the trimmed text of a value, `None` when it is blank.
+pub(crate) fn text(value: &FormatResult) -> Option<String> {
+    Some(value.to_string().trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// Returns the number held by a value, parsing a text with a decimal point
+/// or comma, e.g. ` 1,5`.
+pub(crate) fn float(value: &FormatResult) -> Option<f64> {
+    match value {
+        FormatResult::Float(value) => Some(*value),
+        FormatResult::Int(value) => Some(*value as f64),
+        value => value.to_string().trim().replace(',', ".").parse().ok(),
+    }
+}
+
+/// Strips the `_id` suffix of an SCD identifier: `PARCELLE_id` -> `PARCELLE`.
+pub fn layer_name(object_type: &str) -> &str {
+    object_type.strip_suffix("_id").unwrap_or(object_type)
+}
+
+/// Names of the columns of a table, each one taken at most once.
+#[derive(Debug, Default)]
+pub(crate) struct ColumnNames {
+    names: HashSet<String>,
+    /// Maximum length of a name, for the fields of a `.dbf` file
+    max_len: Option<usize>,
+}
+
+impl ColumnNames {
+    /// Names of `.dbf` fields: ASCII, at most 10 characters long and unique
+    /// regardless of case.
+    pub(crate) fn dbf() -> Self {
+        Self {
+            names: HashSet::new(),
+            max_len: Some(10),
+        }
+    }
+
+    /// Returns the label, suffixed when already taken: `TEX_2`, `TEX_3`...,
+    /// or `TEX1`, `TEX2`... truncated to the maximum length.
+    pub(crate) fn unique(&mut self, label: &str) -> String {
+        let Some(max_len) = self.max_len else {
+            let mut name = label.to_string();
+            let mut i = 1;
+            while !self.names.insert(name.clone()) {
+                i += 1;
+                name = format!("{label}_{i}");
+            }
+            return name;
+        };
+        let base: String = label.chars().filter(char::is_ascii).take(max_len).collect();
+        let mut name = base.clone();
+        let mut i = 1;
+        while !self.names.insert(name.to_ascii_uppercase()) {
+            let suffix = i.to_string();
+            name = format!(
+                "{}{suffix}",
+                &base[..base.len().min(max_len - suffix.len())]
+            );
+            i += 1;
+        }
+        name
+    }
+}
+
+/// An attribute column of the table of an object type, see
+/// [`Lot::attribute_columns`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AttributeColumn {
+    /// SCD identifier of the attribute, e.g. `SUPF_id`
+    pub id: String,
+    /// Name of the column, from the dictionary label
+    pub name: String,
+    /// Type of the values in the dictionary (`TYP`), e.g. `R`
+    pub value_type: Option<String>,
+    /// Number of decimals of the values (`CAD`)
+    pub decimals: Option<i32>,
+}
+
+type Key<'a> = (&'a str, &'a str);
+
+fn key(reference: &Reference) -> Key<'_> {
+    (&reference.subset, &reference.id)
+}
+
+/// Relations between the descriptors of a lot, indexed for assembly.
+struct Topology<'a> {
+    nodes: HashMap<Key<'a>, &'a Node>,
+    arcs: HashMap<Key<'a>, &'a Arc>,
+    /// Left and right faces of each arc
+    arc_faces: HashMap<Key<'a>, (Option<Key<'a>>, Option<Key<'a>>)>,
+    /// Arcs bounding each face
+    face_arcs: HashMap<Key<'a>, Vec<Key<'a>>>,
+    /// Primitives composing each object
+    compositions: HashMap<Key<'a>, Vec<&'a Reference>>,
+}
+
+impl<'a> Topology<'a> {
+    fn new(lot: &'a Lot) -> Self {
+        let mut topology = Self {
+            nodes: HashMap::new(),
+            arcs: HashMap::new(),
+            arc_faces: HashMap::new(),
+            face_arcs: HashMap::new(),
+            compositions: HashMap::new(),
+        };
+
+        for (subset, vec) in &lot.subsets {
+            for node in &vec.nodes {
+                topology.nodes.insert((subset, &node.id), node);
+            }
+            for arc in &vec.arcs {
+                topology.arcs.insert((subset, &arc.id), arc);
+            }
+            for link in &vec.links {
+                topology.add_link(link);
+            }
+        }
+        topology
+    }
+
+    fn add_link(&mut self, link: &'a Link) {
+        let find = |kind: &str| link.members.iter().find(|m| m.kind == kind);
+        let relation = link.definition.as_ref().map_or("", |d| d.id.as_str());
+
+        if let Some(object) = find("FEA") {
+            for member in link.members.iter().filter(|m| m.kind != "FEA") {
+                self.compositions
+                    .entry(key(object))
+                    .or_default()
+                    .push(member);
+            }
+        } else if let (Some(arc), Some(face)) = (find("PAR"), find("PFE")) {
+            let sides = self.arc_faces.entry(key(arc)).or_default();
+            if relation.ends_with("DRTE") {
+                sides.1 = Some(key(face));
+            } else {
+                sides.0 = Some(key(face));
+            }
+            self.face_arcs.entry(key(face)).or_default().push(key(arc));
+        }
+    }
+
+    fn geometry(&self, object: Key<'a>) -> Option<Geometry> {
+        let members = self.compositions.get(&object)?;
+        let of_kind = |kind: &str| {
+            members
+                .iter()
+                .filter(|m| m.kind == kind)
+                .map(|m| key(m))
+                .collect::<Vec<_>>()
+        };
+
+        let faces: HashSet<Key> = of_kind("PFE").into_iter().collect();
+        if !faces.is_empty() {
+            return self.faces_geometry(&faces);
+        }
+
+        let arcs = of_kind("PAR");
+        if !arcs.is_empty() {
+            let mut lines = merge_lines(
+                arcs.iter()
+                    .filter_map(|arc| self.arcs.get(arc))
+                    .map(|arc| arc.points.clone())
+                    .collect(),
+            );
+            return match lines.len() {
+                0 => None,
+                1 => lines.pop().map(Geometry::LineString),
+                _ => Some(Geometry::MultiLineString(lines)),
+            };
+        }
+
+        let mut points: Vec<_> = of_kind("PNO")
+            .iter()
+            .filter_map(|node| self.nodes.get(node)?.coord)
+            .collect();
+        match points.len() {
+            0 => None,
+            1 => points.pop().map(Geometry::Point),
+            _ => Some(Geometry::MultiPoint(points)),
+        }
+    }
+
+    /// Dissolves a set of faces into a polygon from the arcs having exactly
+    /// one of their sides in the set.
+    fn faces_geometry(&self, faces: &HashSet<Key<'a>>) -> Option<Geometry> {
+        let mut boundary: Vec<Key> = faces
+            .iter()
+            .flat_map(|face| self.face_arcs.get(face).into_iter().flatten())
+            .filter(|arc| {
+                let (left, right) = self.arc_faces.get(*arc).cloned().unwrap_or_default();
+                left.is_some_and(|f| faces.contains(&f))
+                    != right.is_some_and(|f| faces.contains(&f))
+            })
+            .copied()
+            .collect();
+        boundary.sort();
+        boundary.dedup();
+        let rings = boundary
+            .iter()
+            .filter_map(|arc| self.arcs.get(arc))
+            .map(|arc| arc.points.clone())
+            .collect();
+        let mut polygons = build_polygons(rings);
+        match polygons.len() {
+            0 => None,
+            1 => polygons.pop().map(Geometry::Polygon),
+            _ => Some(Geometry::MultiPolygon(polygons)),
+        }
+    }
+
+    /// Signed area of a face from its arcs, oriented so that the face lies on
+    /// their left. It is negative for the unbounded face surrounding the lot.
+    fn face_area(&self, face: Key<'a>) -> f64 {
+        self.face_arcs
+            .get(&face)
+            .into_iter()
+            .flatten()
+            .filter_map(|arc| Some((self.arcs.get(arc)?, self.arc_faces.get(arc)?)))
+            .map(|(arc, (left, right))| {
+                let area = signed_area(&arc.points);
+                match (*left == Some(face), *right == Some(face)) {
+                    (true, false) => area,
+                    (false, true) => -area,
+                    _ => 0.0,
+                }
+            })
+            .sum()
+    }
+}
+
+/// A vector primitive of a lot with its geometry.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Primitive {
+    /// Record identifier of the descriptor, e.g. `Arc_1243`
+    pub id: String,
+    /// Identifier of the subset holding the primitive
+    pub subset: String,
+    /// Kind of primitive: `PNO`, `PAR` or `PFE`
+    pub kind: String,
+    /// Point of a node, line of an arc or polygon of a face
+    pub geometry: Option<Geometry>,
+}
+
+impl Lot {
+    /// Decodes and parses every file of an [`EdigeoBundle`] into a [`Lot`].
+    ///
+    /// Each `.VEC` file is stored under the subset identifier given to it by
+    /// the `GDN`/`GDI` pairs of the `.THF` file. Values whose byte length
+    /// differs from the size declared by their header are rejected, see
+    /// [`Lot::from_bundle_with`] to accept them.
+    ///
+    /// A count zone (`ATC`, `PTC`...) that differs from the number of zones
+    /// it counts, as in a truncated transfer, fails with
+    /// [`EdigeoError::CountMismatch`](crate::error::EdigeoError::CountMismatch).
+    /// [`Lot::from_bundle_lenient`] reports them as warnings instead.
+    pub fn from_bundle(bundle: &EdigeoBundle) -> EdigeoResult<Self> {
+        Self::from_bundle_with(bundle, ValueSize::default())
+    }
+
+    /// Decodes and parses every file of an [`EdigeoBundle`] into a [`Lot`],
+    /// handling the declared value sizes as [`ValueSize`] requires and
+    /// checking the declared counts like [`Lot::from_bundle`].
+    pub fn from_bundle_with(bundle: &EdigeoBundle, value_size: ValueSize) -> EdigeoResult<Self> {
+        fn parse_optional<T: BlockParse + Default>(
+            data: Option<&[u8]>,
+            value_size: ValueSize,
+        ) -> EdigeoResult<T> {
+            data.map_or(Ok(T::default()), |data| {
+                T::parse_bytes_checked(data, value_size)
+            })
+        }
+
+        let thf = THFFile::parse_bytes_checked(&bundle.thf, value_size)?;
+
+        let mut subsets = BTreeMap::new();
+        for (name, subset) in thf.subsets() {
+            let data = match name.as_str() {
+                "T1" => &bundle.t1,
+                "T2" => &bundle.t2,
+                "T3" => &bundle.t3,
+                "S1" => &bundle.s1,
+                _ => continue,
+            };
+            subsets.insert(subset, VECFile::parse_bytes_checked(data, value_size)?);
+        }
+
+        Ok(Self {
+            name: thf.lot_name().unwrap_or_default(),
+            gen: parse_optional(bundle.gen.as_deref(), value_size)?,
+            geo: GEOFile::parse_bytes_checked(&bundle.geo, value_size)?,
+            dic: parse_optional(bundle.dic.as_deref(), value_size)?,
+            scd: parse_optional(bundle.scd.as_deref(), value_size)?,
+            qal: QALFile::parse_bytes_checked(&bundle.qal, value_size)?,
+            subsets,
+            sheet: bundle.sheet(),
+            #[cfg(feature = "rstar")]
+            index: Default::default(),
+        })
+    }
+
+    /// Parses every file of an [`EdigeoBundle`] into a [`Lot`] without
+    /// failing, reporting problems to the sink instead, see
+    /// [`parse_blocks_lenient`](crate::parse_blocks_lenient).
+    pub fn from_bundle_lenient(
+        bundle: &EdigeoBundle,
+        value_size: ValueSize,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Self {
+        fn parse_optional<T: BlockParse + Default>(
+            data: Option<&[u8]>,
+            value_size: ValueSize,
+            file: &str,
+            diagnostics: &mut dyn DiagnosticSink,
+        ) -> T {
+            data.map_or_else(T::default, |data| {
+                T::parse_lenient(data, value_size, file, diagnostics)
+            })
+        }
+
+        let thf = THFFile::parse_lenient(&bundle.thf, value_size, "THF", diagnostics);
+
+        let mut subsets = BTreeMap::new();
+        for (name, subset) in thf.subsets() {
+            let data = match name.as_str() {
+                "T1" => &bundle.t1,
+                "T2" => &bundle.t2,
+                "T3" => &bundle.t3,
+                "S1" => &bundle.s1,
+                _ => continue,
+            };
+            let vec = VECFile::parse_lenient(data, value_size, &name, diagnostics);
+            subsets.insert(subset, vec);
+        }
+
+        Self {
+            name: thf.lot_name().unwrap_or_default(),
+            gen: parse_optional(bundle.gen.as_deref(), value_size, "GEN", diagnostics),
+            geo: GEOFile::parse_lenient(&bundle.geo, value_size, "GEO", diagnostics),
+            dic: parse_optional(bundle.dic.as_deref(), value_size, "DIC", diagnostics),
+            scd: parse_optional(bundle.scd.as_deref(), value_size, "SCD", diagnostics),
+            qal: QALFile::parse_lenient(&bundle.qal, value_size, "QAL", diagnostics),
+            subsets,
+            sheet: bundle.sheet(),
+            #[cfg(feature = "rstar")]
+            index: Default::default(),
+        }
+    }
+
+    /// Returns the projection named by the geographic reference of the lot.
+    pub fn projection(&self) -> Option<Projection> {
+        self.geo
+            .reference
+            .code
+            .as_deref()
+            .and_then(Projection::from_code)
+    }
+
+    /// Returns the EPSG identifier of the lot's projection, to be used as SRID.
+    pub fn srid(&self) -> Option<u32> {
+        self.projection().map(|projection| projection.epsg)
+    }
+
+    /// Returns the dictionary label of an SCD attribute type, falling back to
+    /// its identifier without the `_id` suffix: `SUPF_id` -> `SUPF`.
+    pub fn attribute_label<'a>(&'a self, id: &'a str) -> &'a str {
+        self.scd
+            .attribute(id)
+            .and_then(|attribute| attribute.definition.as_ref())
+            .and_then(|definition| self.dic.definition(&definition.id))
+            .map(|definition| definition.label.as_str())
+            .filter(|label| !label.is_empty())
+            .unwrap_or_else(|| layer_name(id))
+    }
+
+    /// Returns the attribute columns of the table of an object type: its SCD
+    /// attributes followed by those found on its features only, named after
+    /// their dictionary labels.
+    pub(crate) fn attribute_columns(
+        &self,
+        object_type: &str,
+        features: &[Feature],
+        names: &mut ColumnNames,
+    ) -> Vec<AttributeColumn> {
+        let mut attributes: Vec<&str> = match self.scd.object(object_type) {
+            Some(object) => object.attributes.iter().map(|a| a.id.as_str()).collect(),
+            None => Vec::new(),
+        };
+        for attribute in features.iter().flat_map(|f| &f.attributes) {
+            if !attributes.contains(&attribute.id.as_str()) {
+                attributes.push(&attribute.id);
+            }
+        }
+
+        attributes
+            .into_iter()
+            .map(|id| {
+                let scd = self.scd.attribute(id);
+                let value_type = scd
+                    .and_then(|a| a.definition.as_ref())
+                    .and_then(|d| self.dic.definition(&d.id))
+                    .and_then(|d| d.value_type.clone());
+                AttributeColumn {
+                    id: id.to_string(),
+                    name: names.unique(self.attribute_label(id)),
+                    value_type,
+                    decimals: scd.and_then(|a| a.decimals),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the dictionary description (`AVD`) of a coded value of an SCD
+    /// attribute type, e.g. `01` of `DUR_id` -> `Bâti dur`.
+    pub fn value_description(&self, id: &str, code: &str) -> Option<&str> {
+        self.scd
+            .attribute(id)
+            .and_then(|attribute| attribute.definition.as_ref())
+            .and_then(|definition| self.dic.definition(&definition.id))?
+            .values
+            .iter()
+            .find(|value| value.code.trim() == code.trim())
+            .map(|value| value.description.as_str())
+    }
+
+    /// Assembles every `FEA` object of the lot into a [`Feature`].
+    pub fn features(&self) -> Vec<Feature> {
+        let topology = Topology::new(self);
+
+        self.subsets
+            .iter()
+            .flat_map(|(subset, vec)| vec.objects.iter().map(move |object| (subset, object)))
+            .map(|(subset, object)| Feature {
+                id: object.id.clone(),
+                subset: subset.clone(),
+                object_type: object
+                    .definition
+                    .as_ref()
+                    .map(|definition| definition.id.clone())
+                    .unwrap_or_default(),
+                attributes: object.attributes.clone(),
+                quality: object.quality.clone(),
+                geometry: topology.geometry((subset, &object.id)),
+            })
+            .collect()
+    }
+
+    /// Returns the nodes, arcs and faces of every subset with their geometry.
+    ///
+    /// Faces are assembled from their bounding arcs. The unbounded face
+    /// surrounding the lot has no geometry.
+    pub fn primitives(&self) -> Vec<Primitive> {
+        let topology = Topology::new(self);
+        let primitive = |subset: &str, id: &str, kind: &str, geometry| Primitive {
+            id: id.to_string(),
+            subset: subset.to_string(),
+            kind: kind.to_string(),
+            geometry,
+        };
+
+        let mut primitives = Vec::new();
+        for (subset, vec) in &self.subsets {
+            for node in &vec.nodes {
+                let geometry = node.coord.map(Geometry::Point);
+                primitives.push(primitive(subset, &node.id, "PNO", geometry));
+            }
+            for arc in &vec.arcs {
+                let geometry =
+                    Some(Geometry::LineString(arc.points.clone())).filter(|_| arc.points.len() > 1);
+                primitives.push(primitive(subset, &arc.id, "PAR", geometry));
+            }
+            for face in &vec.faces {
+                let key = (subset.as_str(), face.id.as_str());
+                let geometry = Some(key)
+                    .filter(|key| topology.face_area(*key) > 0.0)
+                    .and_then(|key| topology.faces_geometry(&HashSet::from([key])));
+                primitives.push(primitive(subset, &face.id, "PFE", geometry));
+            }
+        }
+        primitives
+    }
+
+    /// Assembles the features of the lot grouped by object type.
+    pub fn layers(&self) -> BTreeMap<String, Vec<Feature>> {
+        let mut layers: BTreeMap<String, Vec<Feature>> = BTreeMap::new();
+        for feature in self.features() {
+            layers
+                .entry(feature.object_type.clone())
+                .or_default()
+                .push(feature);
+        }
+        layers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{feature, text};
+
+    #[test]
+    fn test_unique_names() {
+        let mut names = ColumnNames::default();
+        assert_eq!(names.unique("TEX"), "TEX");
+        assert_eq!(names.unique("TEX"), "TEX_2");
+        assert_eq!(names.unique("TEX"), "TEX_3");
+
+        let mut names = ColumnNames::dbf();
+        assert_eq!(names.unique("Numero_voie"), "Numero_voi");
+        assert_eq!(names.unique("NUMERO_VOIE"), "NUMERO_VO1");
+        assert_eq!(names.unique("Dénomination"), "Dnominatio");
+    }
+
+    #[test]
+    fn test_feature_values() {
+        let feature = feature(
+            "Objet_1",
+            "PARCELLE_id",
+            &[
+                ("TEX_id", text(" 33 ")),
+                ("INDP_id", text("  ")),
+                ("SUPF_id", text("1,5")),
+                ("ORI_id", FormatResult::Int(90)),
+            ],
+            None,
+        );
+        assert_eq!(feature.text("TEX_id").as_deref(), Some("33"));
+        assert_eq!(feature.text("INDP_id"), None);
+        assert_eq!(feature.text("COAR_id"), None);
+        assert_eq!(feature.float("SUPF_id"), Some(1.5));
+        assert_eq!(feature.float("ORI_id"), Some(90.0));
+        assert_eq!(feature.float("TEX_id"), Some(33.0));
+    }
+}
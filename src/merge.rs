@@ -0,0 +1,713 @@
+//! Contains the merge of the lots of adjacent cadastral sheets into a single
+//! seamless [`Lot`], see [`Lot::merge`].
+//!
+//! ```no_run
+//! use edigeo::*;
+//!
+//! let lots: Vec<Lot> = ["edigeo-740240000A01.tar.bz2", "edigeo-740240000A02.tar.bz2"]
+//!     .iter()
+//!     .map(|path| Lot::from_bundle(&EdigeoReader::new(path).read_bundle()).unwrap())
+//!     .collect();
+//! let commune = Lot::merge(&lots, 0.01).unwrap();
+//! let sections = &commune.layers()["SECTION_id"];
+//! ```
+use crate::error::{EdigeoError, EdigeoResult};
+use crate::geometry::{BoundingBox, Coord, Geometry};
+use crate::lot::Lot;
+use crate::records::{Link, Reference};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Descriptor types whose record identifiers are qualified by the lot name.
+const RECORD_KINDS: [&str; 6] = ["PNO", "PAR", "PFE", "FEA", "LNK", "QUP"];
+
+/// Relation suffixes exchanged when an arc is reversed.
+const REVERSED_SIDES: [(&str, &str); 2] = [("_DRTE", "_GCHE"), ("_NOD_INI", "_NOD_FIN")];
+
+type Key = (String, String);
+
+fn key(reference: &Reference) -> Key {
+    (reference.subset.clone(), reference.id.clone())
+}
+
+/// Bit pattern of a coordinate, to look up identical positions.
+type Position = (u64, u64);
+
+/// Returns the [`Position`] of a coordinate.
+fn bits(coord: &Coord) -> Position {
+    (coord.x.to_bits(), coord.y.to_bits())
+}
+
+/// Returns the segments between the vertices of an arc, regardless of its
+/// direction.
+fn segments(points: &[Coord]) -> impl Iterator<Item = (Position, Position)> + '_ {
+    points.windows(2).map(|pair| {
+        let (a, b) = (bits(&pair[0]), bits(&pair[1]));
+        (a.min(b), a.max(b))
+    })
+}
+
+/// Positions, nodes and arcs of the lots merged so far.
+struct Seen {
+    tolerance: f64,
+    cells: HashMap<(i64, i64), Vec<Coord>>,
+    nodes: HashMap<(String, Position), Vec<String>>,
+    arcs: HashMap<(String, Vec<Position>), Vec<String>>,
+    /// Face on the left (`true`) or right side of each arc.
+    sides: HashMap<(String, String, bool), String>,
+}
+
+impl Seen {
+    fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            cells: HashMap::new(),
+            nodes: HashMap::new(),
+            arcs: HashMap::new(),
+            sides: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, coord: &Coord) -> (i64, i64) {
+        let size = match self.tolerance {
+            tolerance if tolerance > 0.0 => tolerance,
+            _ => 1.0,
+        };
+        (
+            (coord.x / size).floor() as i64,
+            (coord.y / size).floor() as i64,
+        )
+    }
+
+    /// Moves a coordinate onto the closest one seen within the tolerance.
+    fn snap(&self, coord: Coord) -> Coord {
+        let (x, y) = self.cell(&coord);
+        let distance = |c: &Coord| (c.x - coord.x).hypot(c.y - coord.y);
+        (x - 1..=x + 1)
+            .flat_map(|x| (y - 1..=y + 1).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(|c| distance(c) <= self.tolerance)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .copied()
+            .unwrap_or(coord)
+    }
+
+    fn insert(&mut self, coord: Coord) {
+        self.cells.entry(self.cell(&coord)).or_default().push(coord);
+    }
+
+    fn add(&mut self, lot: &Lot) {
+        for (subset, vec) in &lot.subsets {
+            for node in &vec.nodes {
+                if let Some(coord) = node.coord {
+                    self.insert(coord);
+                    self.nodes
+                        .entry((subset.clone(), bits(&coord)))
+                        .or_default()
+                        .push(node.id.clone());
+                }
+            }
+            for arc in &vec.arcs {
+                arc.points.iter().for_each(|c| self.insert(*c));
+                let points = arc.points.iter().map(bits).collect();
+                self.arcs
+                    .entry((subset.clone(), points))
+                    .or_default()
+                    .push(arc.id.clone());
+            }
+            for (arc, face, left) in vec.links.iter().filter_map(side) {
+                self.sides
+                    .entry((subset.clone(), arc.id.clone(), left))
+                    .or_insert(face.id.clone());
+            }
+        }
+    }
+
+    /// Returns a node already seen at the position of a node and not yet
+    /// `claimed` by another node of the lot.
+    fn node(&self, subset: &str, coord: &Coord, claimed: &mut HashSet<String>) -> Option<String> {
+        let nodes = self.nodes.get(&(subset.to_string(), bits(coord)))?;
+        let node = nodes.iter().find(|id| !claimed.contains(*id))?;
+        claimed.insert(node.clone());
+        Some(node.clone())
+    }
+
+    /// Returns an arc already seen through the vertices of an arc and not yet
+    /// `claimed` by another arc of the lot, and whether it runs the other way.
+    fn arc(
+        &self,
+        subset: &str,
+        points: &[Coord],
+        claimed: &mut HashSet<String>,
+    ) -> Option<(String, bool)> {
+        let forward = points.iter().map(bits).collect();
+        let backward = points.iter().rev().map(bits).collect();
+        [(forward, false), (backward, true)]
+            .into_iter()
+            .filter_map(|(points, reversed)| {
+                let arcs = self.arcs.get(&(subset.to_string(), points))?;
+                Some((arcs.iter().find(|id| !claimed.contains(*id))?, reversed))
+            })
+            .next()
+            .map(|(arc, reversed)| {
+                claimed.insert(arc.clone());
+                (arc.clone(), reversed)
+            })
+    }
+}
+
+/// Returns the arc, the face and whether the face is on the left of the arc
+/// for the links between an arc and the faces on its sides.
+pub(crate) fn side(link: &Link) -> Option<(&Reference, &Reference, bool)> {
+    let relation = &link.definition.as_ref()?.id;
+    let left = relation.ends_with("_GCHE");
+    if !left && !relation.ends_with("_DRTE") {
+        return None;
+    }
+    let arc = link.members.iter().find(|m| m.kind == "PAR")?;
+    let face = link.members.iter().find(|m| m.kind == "PFE")?;
+    Some((arc, face, left))
+}
+
+/// Returns the lot with every record identifier prefixed by `prefix`, in
+/// its descriptors and in the references to them.
+fn qualify(lot: &Lot, prefix: &str) -> Lot {
+    let name = |id: &mut String| *id = format!("{prefix}_{id}");
+    let reference = |reference: &mut Reference| {
+        if RECORD_KINDS.contains(&reference.kind.as_str()) {
+            reference.id = format!("{prefix}_{}", reference.id);
+        }
+    };
+
+    let mut lot = lot.clone();
+    for vec in lot.subsets.values_mut() {
+        for node in &mut vec.nodes {
+            name(&mut node.id);
+            node.quality.iter_mut().for_each(reference);
+        }
+        for arc in &mut vec.arcs {
+            name(&mut arc.id);
+            arc.quality.iter_mut().for_each(reference);
+        }
+        for face in &mut vec.faces {
+            name(&mut face.id);
+            face.quality.iter_mut().for_each(reference);
+        }
+        for object in &mut vec.objects {
+            name(&mut object.id);
+            object.quality.iter_mut().for_each(reference);
+        }
+        for link in &mut vec.links {
+            name(&mut link.id);
+            link.members.iter_mut().for_each(reference);
+        }
+    }
+    for quality in &mut lot.qal.qualities {
+        name(&mut quality.id);
+        quality.objects.iter_mut().for_each(reference);
+    }
+    lot
+}
+
+/// Rewrites a link whose arc member was reversed: left and right faces,
+/// initial and final nodes and the direction of travel are exchanged.
+fn reverse_link(link: &mut Link) {
+    if let Some(definition) = &mut link.definition {
+        for (a, b) in REVERSED_SIDES {
+            if let Some(stem) = definition.id.strip_suffix(a) {
+                definition.id = format!("{stem}{b}");
+                break;
+            } else if let Some(stem) = definition.id.strip_suffix(b) {
+                definition.id = format!("{stem}{a}");
+                break;
+            }
+        }
+    }
+    link.direction = match link.direction.as_deref() {
+        Some("+") => Some("-".to_string()),
+        Some("-") => Some("+".to_string()),
+        _ => link.direction.take(),
+    };
+}
+
+/// Finds the root of a set in a union-find forest.
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Object type and attribute values, identical for the parts of an object.
+type Signature = (String, Vec<(String, String)>);
+
+/// An object of one of the merged lots, as a candidate for dissolution.
+struct Candidate {
+    lot: usize,
+    key: Key,
+    signature: Signature,
+    /// Arcs of the sheet boundary along which the object runs.
+    edges: Vec<Key>,
+}
+
+impl Lot {
+    /// Merges the lots of adjacent sheets of a commune into one lot.
+    ///
+    /// - Record identifiers are qualified by the name of their lot, e.g.
+    ///   `ED0A02_Arc_12`, so that the `Noeud_N`/`Arc_N` numbering of each
+    ///   sheet does not collide.
+    /// - Nodes and arc vertices are snapped onto those of the previous lots
+    ///   lying within `tolerance`. Nodes then found at the same position are
+    ///   merged, and so are arcs going through the same vertices, joining the
+    ///   faces on both sides of the sheet boundaries. Where sheets overlap,
+    ///   faces on the same side of a merged arc are merged too.
+    /// - Lines and polygons of the same type and attributes running along the
+    ///   same merged arc of the sheet boundaries, such as the parts of a
+    ///   section drawn on several sheets, are dissolved into the object of
+    ///   the first lot. Similar objects that merely lie close to each other,
+    ///   such as adjacent buildings, are kept apart.
+    ///
+    /// The descriptive files (`GEO`, `SCD`, `DIC`...) and the sheet of the
+    /// first lot are kept. Fails with [`EdigeoError::IncompatibleLots`] when
+    /// the lots have different projections or come from different communes.
+    pub fn merge(lots: &[Lot], tolerance: f64) -> EdigeoResult<Lot> {
+        let Some(first) = lots.first() else {
+            return Ok(Lot::default());
+        };
+        for lot in &lots[1..] {
+            if lot.srid() != first.srid() {
+                return Err(EdigeoError::IncompatibleLots(format!(
+                    "{} and {} have different projections",
+                    first.name, lot.name
+                )));
+            }
+            if let (Some(a), Some(b)) = (&first.sheet, &lot.sheet) {
+                if a.insee() != b.insee() {
+                    return Err(EdigeoError::IncompatibleLots(format!(
+                        "sheets {a} and {b} belong to different communes"
+                    )));
+                }
+            }
+        }
+
+        let mut prefixes: Vec<String> = Vec::new();
+        for (i, lot) in lots.iter().enumerate() {
+            let prefix = match lot.name.as_str() {
+                "" => format!("LOT{i}"),
+                name if prefixes.iter().any(|p| p == name) => format!("{name}_{i}"),
+                name => name.to_string(),
+            };
+            prefixes.push(prefix);
+        }
+
+        // Faces surrounding each sheet and objects that may be dissolved,
+        // from the lots as parsed.
+        let mut outer_faces = HashSet::new();
+        let mut candidates = Vec::new();
+        for (i, (lot, prefix)) in lots.iter().zip(&prefixes).enumerate() {
+            let qualified = |r: &Reference| (r.subset.clone(), format!("{prefix}_{}", r.id));
+            for primitive in lot.primitives() {
+                if primitive.kind == "PFE" && primitive.geometry.is_none() {
+                    outer_faces.insert((primitive.subset, format!("{prefix}_{}", primitive.id)));
+                }
+            }
+            // Arcs bordering a face surrounding the sheet, by segment, and
+            // those each arc of the lot runs along, in any subset.
+            let links = || lot.subsets.values().flat_map(|vec| &vec.links);
+            let arcs: HashMap<Key, &[Coord]> = lot
+                .subsets
+                .iter()
+                .flat_map(|(subset, vec)| vec.arcs.iter().map(move |arc| (subset, arc)))
+                .map(|(subset, arc)| {
+                    let id = format!("{prefix}_{}", arc.id);
+                    ((subset.clone(), id), arc.points.as_slice())
+                })
+                .collect();
+            let mut seams = HashMap::new();
+            for (arc, face, _) in links().filter_map(side) {
+                if let (true, Some(points)) = (
+                    outer_faces.contains(&qualified(face)),
+                    arcs.get(&qualified(arc)),
+                ) {
+                    seams.extend(segments(points).map(|segment| (segment, qualified(arc))));
+                }
+            }
+            let along = |arc: &Key| -> Vec<Key> {
+                let points = arcs.get(arc).copied().unwrap_or_default();
+                let seams = segments(points).filter_map(|segment| seams.get(&segment));
+                seams.cloned().collect()
+            };
+            let mut face_edges: HashMap<Key, Vec<Key>> = HashMap::new();
+            for (arc, face, _) in links().filter_map(side) {
+                let edges = face_edges.entry(qualified(face)).or_default();
+                edges.extend(along(&qualified(arc)));
+            }
+            // Arcs of the sheet boundary along which each object runs.
+            let mut edges: HashMap<Key, Vec<Key>> = HashMap::new();
+            for link in links() {
+                let Some(object) = link.members.iter().find(|m| m.kind == "FEA") else {
+                    continue;
+                };
+                let object = edges.entry(qualified(object)).or_default();
+                for member in link.members.iter().map(qualified) {
+                    match face_edges.get(&member) {
+                        Some(arcs) => object.extend(arcs.iter().cloned()),
+                        None => object.extend(along(&member)),
+                    }
+                }
+            }
+            for feature in lot.features() {
+                // Points cannot be split by the boundary of a sheet.
+                if matches!(
+                    feature.geometry,
+                    Some(Geometry::Point(_) | Geometry::MultiPoint(_)) | None
+                ) {
+                    continue;
+                }
+                let key = (feature.subset, format!("{prefix}_{}", feature.id));
+                let mut edges = edges.remove(&key).unwrap_or_default();
+                edges.sort();
+                edges.dedup();
+                if edges.is_empty() {
+                    continue;
+                }
+                let mut attributes: Vec<_> = feature
+                    .attributes
+                    .iter()
+                    .map(|attribute| {
+                        let value = attribute.value.as_ref().map(|value| value.to_string());
+                        (attribute.id.clone(), value.unwrap_or_default())
+                    })
+                    .collect();
+                attributes.sort();
+                candidates.push(Candidate {
+                    lot: i,
+                    key,
+                    signature: (feature.object_type, attributes),
+                    edges,
+                });
+            }
+        }
+
+        let mut merged = qualify(first, &prefixes[0]);
+        let mut seen = Seen::new(tolerance);
+        seen.add(&merged);
+        // Dropped nodes, arcs and objects: the identifier of the descriptor
+        // replacing them and whether it runs the other way.
+        let mut replaced: HashMap<Key, (String, bool)> = HashMap::new();
+        let mut fused = HashSet::new();
+
+        for (lot, prefix) in lots[1..].iter().zip(&prefixes[1..]) {
+            let mut lot = qualify(lot, prefix);
+            for (subset, vec) in &mut lot.subsets {
+                for node in &mut vec.nodes {
+                    node.coord = node.coord.map(|c| seen.snap(c));
+                }
+                for arc in &mut vec.arcs {
+                    arc.points.iter_mut().for_each(|c| *c = seen.snap(*c));
+                }
+
+                // Each node or arc of the previous lots replaces at most one
+                // of the lot, keeping the duplicates of a sheet apart.
+                let mut claimed = HashSet::new();
+                vec.nodes.retain(|node| {
+                    let existing = node.coord.and_then(|c| seen.node(subset, &c, &mut claimed));
+                    let Some(existing) = existing else {
+                        return true;
+                    };
+                    replaced.insert((subset.clone(), node.id.clone()), (existing, false));
+                    false
+                });
+                vec.arcs.retain(|arc| {
+                    let Some(existing) = seen.arc(subset, &arc.points, &mut claimed) else {
+                        return true;
+                    };
+                    fused.insert((subset.clone(), existing.0.clone()));
+                    replaced.insert((subset.clone(), arc.id.clone()), existing);
+                    false
+                });
+
+                // A face on the same side of a merged arc as a face of the
+                // previous lots is that face, when sheets overlap.
+                let mut faces = HashMap::new();
+                for (arc, face, left) in vec.links.iter().filter_map(side) {
+                    let Some((existing, reversed)) = replaced.get(&key(arc)) else {
+                        continue;
+                    };
+                    let side = (subset.clone(), existing.clone(), left != *reversed);
+                    if let Some(existing) = seen.sides.get(&side) {
+                        let existing = (subset.clone(), existing.clone());
+                        if !outer_faces.contains(&key(face)) && !outer_faces.contains(&existing) {
+                            faces.entry(key(face)).or_insert(existing.1);
+                        }
+                    }
+                }
+                vec.faces
+                    .retain(|face| !faces.contains_key(&(subset.clone(), face.id.clone())));
+                replaced.extend(faces.into_iter().map(|(face, id)| (face, (id, false))));
+            }
+            seen.add(&lot);
+
+            for (subset, vec) in lot.subsets {
+                let target = merged.subsets.entry(subset).or_default();
+                target.nodes.extend(vec.nodes);
+                target.arcs.extend(vec.arcs);
+                target.faces.extend(vec.faces);
+                target.objects.extend(vec.objects);
+                target.links.extend(vec.links);
+            }
+            merged.qal.qualities.extend(lot.qal.qualities);
+            merged.gen.extent = match (merged.gen.extent, lot.gen.extent) {
+                (Some((min, max)), Some((other_min, other_max))) => {
+                    let other = BoundingBox {
+                        min: other_min,
+                        max: other_max,
+                    };
+                    let extent = BoundingBox { min, max }.expand(&other);
+                    Some((extent.min, extent.max))
+                }
+                (extent, other) => extent.or(other),
+            };
+        }
+
+        // Groups the parts of the same object drawn on several sheets: those
+        // bounded by the same arc merged on the sheet boundaries.
+        let mut parts: BTreeMap<(&Signature, Key), Vec<usize>> = BTreeMap::new();
+        for (i, candidate) in candidates.iter().enumerate() {
+            for edge in &candidate.edges {
+                let edge = match replaced.get(edge) {
+                    Some((id, _)) => (edge.0.clone(), id.clone()),
+                    None => edge.clone(),
+                };
+                if fused.contains(&edge) {
+                    parts
+                        .entry((&candidate.signature, edge))
+                        .or_default()
+                        .push(i);
+                }
+            }
+        }
+        // A group holds at most one object of each lot, so that the similar
+        // objects of a sheet are never dissolved together.
+        let mut parents: Vec<usize> = (0..candidates.len()).collect();
+        let mut group_lots: Vec<HashSet<usize>> =
+            candidates.iter().map(|c| HashSet::from([c.lot])).collect();
+        for members in parts.values() {
+            for (n, &i) in members.iter().enumerate() {
+                for &j in &members[n + 1..] {
+                    let (ri, rj) = (root(&mut parents, i), root(&mut parents, j));
+                    if ri != rj && group_lots[ri].is_disjoint(&group_lots[rj]) {
+                        let (kept, joined) = (ri.min(rj), ri.max(rj));
+                        parents[joined] = kept;
+                        let lots = std::mem::take(&mut group_lots[joined]);
+                        group_lots[kept].extend(lots);
+                    }
+                }
+            }
+        }
+        let mut dissolved = HashSet::new();
+        for i in 0..candidates.len() {
+            let r = root(&mut parents, i);
+            if r != i {
+                let kept = candidates[r].key.1.clone();
+                replaced.insert(candidates[i].key.clone(), (kept, false));
+                dissolved.insert(candidates[i].key.clone());
+            }
+        }
+
+        let mut links = HashSet::new();
+        for (subset, vec) in &mut merged.subsets {
+            vec.objects
+                .retain(|object| !dissolved.contains(&(subset.clone(), object.id.clone())));
+            vec.links.retain_mut(|link| {
+                let mut reversed = false;
+                for member in &mut link.members {
+                    if let Some((id, flip)) = replaced.get(&key(member)) {
+                        member.id = id.clone();
+                        reversed |= *flip;
+                    }
+                }
+                if reversed {
+                    reverse_link(link);
+                }
+                // The faces surrounding the sheets no longer border the arcs
+                // shared by two sheets.
+                if let Some((arc, face, _)) = side(link) {
+                    if fused.contains(&key(arc)) && outer_faces.contains(&key(face)) {
+                        return false;
+                    }
+                }
+                let definition = link.definition.as_ref().map(|d| d.id.clone());
+                let mut members: Vec<_> = link.members.iter().map(key).collect();
+                members.sort();
+                members.dedup();
+                members.len() > 1 && links.insert((definition, members))
+            });
+        }
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::{Attribute, Object, VECFile};
+    use crate::test_support::{arc, coord, face, line, link, node, reference};
+    use crate::FormatResult;
+
+    /// A sheet holding the square `[0, 10] x [0, 10]` as the face of section
+    /// `A`, its boundary split into the right side going up and the rest of
+    /// the square. The corners of the right side are moved by `offset`.
+    fn sheet(name: &str, offset: f64) -> Lot {
+        let (bottom, top) = (coord(10.0 + offset, 0.0), coord(10.0, 10.0 + offset));
+        let object = format!("Objet_{name}");
+        let vec = VECFile {
+            nodes: vec![node("Noeud_1", bottom), node("Noeud_2", top)],
+            arcs: vec![
+                arc("Arc_1", vec![bottom, top]),
+                arc(
+                    "Arc_2",
+                    vec![top, coord(0.0, 10.0), coord(0.0, 0.0), bottom],
+                ),
+            ],
+            faces: vec![face("Face_0"), face("Face_1")],
+            objects: vec![Object {
+                id: object.clone(),
+                definition: Some(reference("OBJ", "SECTION_id")),
+                attribute_count: Some(1),
+                attributes: vec![Attribute {
+                    id: "TEX_id".to_string(),
+                    value: Some(FormatResult::Text("A".to_string())),
+                    raw: String::new(),
+                }],
+                quality: None,
+            }],
+            links: vec![
+                link("ID_S_RCO_FAC_GCHE", &[("PAR", "Arc_1"), ("PFE", "Face_1")]),
+                link("ID_S_RCO_FAC_DRTE", &[("PAR", "Arc_1"), ("PFE", "Face_0")]),
+                link("ID_S_RCO_FAC_GCHE", &[("PAR", "Arc_2"), ("PFE", "Face_1")]),
+                link("ID_S_RCO_FAC_DRTE", &[("PAR", "Arc_2"), ("PFE", "Face_0")]),
+                link(
+                    "ID_S_RCO_SECTION_id",
+                    &[("FEA", &object), ("PFE", "Face_1")],
+                ),
+            ],
+        };
+        Lot {
+            name: name.to_string(),
+            subsets: BTreeMap::from([("SeTOP_1".to_string(), vec)]),
+            ..Lot::default()
+        }
+    }
+
+    /// Mirrors a sheet on the line `x = 10 + dx / 2`, reversing its arcs to
+    /// keep its faces on the same side.
+    fn mirrored(mut lot: Lot, dx: f64) -> Lot {
+        let mirror = |c: Coord| coord(20.0 + dx - c.x, c.y);
+        for vec in lot.subsets.values_mut() {
+            for node in &mut vec.nodes {
+                node.coord = node.coord.map(mirror);
+            }
+            for arc in &mut vec.arcs {
+                arc.points = arc.points.iter().rev().copied().map(mirror).collect();
+            }
+        }
+        lot
+    }
+
+    /// Adds to a sheet the building `[8, 9.997] x [2, 4]` of section `A`,
+    /// with the given `DUR`.
+    fn with_building(mut lot: Lot, dur: &str) -> Lot {
+        let vec = lot.subsets.get_mut("SeTOP_1").unwrap();
+        let ring = line(&[
+            (8.0, 2.0),
+            (9.997, 2.0),
+            (9.997, 4.0),
+            (8.0, 4.0),
+            (8.0, 2.0),
+        ]);
+        vec.arcs.push(arc("Arc_3", ring));
+        vec.faces.push(face("Face_2"));
+        vec.objects.push(Object {
+            id: "Objet_BAT".to_string(),
+            definition: Some(reference("OBJ", "BATIMENT_id")),
+            attribute_count: Some(1),
+            attributes: vec![Attribute {
+                id: "DUR_id".to_string(),
+                value: Some(FormatResult::Text(dur.to_string())),
+                raw: dur.to_string(),
+            }],
+            quality: None,
+        });
+        vec.links.extend([
+            link("ID_S_RCO_FAC_GCHE", &[("PAR", "Arc_3"), ("PFE", "Face_2")]),
+            link("ID_S_RCO_FAC_DRTE", &[("PAR", "Arc_3"), ("PFE", "Face_1")]),
+            link(
+                "ID_S_RCO_BATIMENT_id",
+                &[("FEA", "Objet_BAT"), ("PFE", "Face_2")],
+            ),
+        ]);
+        lot
+    }
+
+    #[test]
+    fn test_merge_sheets() {
+        // The right sheet shares the boundary x = 10, drawn 5 mm away.
+        let left = sheet("ED0A01", 0.0);
+        let right = mirrored(sheet("ED0A02", 0.005), 0.0);
+
+        let merged = Lot::merge(&[left, right], 0.01).unwrap();
+        let vec = &merged.subsets["SeTOP_1"];
+        assert_eq!(vec.nodes.len(), 2);
+        assert_eq!(vec.arcs.len(), 3);
+        assert!(vec.faces.iter().any(|face| face.id == "ED0A02_Face_1"));
+
+        let features = merged.features();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].id, "ED0A01_Objet_ED0A01");
+        let Some(Geometry::Polygon(rings)) = &features[0].geometry else {
+            panic!("unexpected geometry {:?}", features[0].geometry);
+        };
+        assert_eq!(rings.len(), 1);
+        let bbox = BoundingBox::from_coords(&rings[0]).unwrap();
+        assert_eq!((bbox.min, bbox.max), (coord(0.0, 0.0), coord(20.0, 10.0)));
+    }
+
+    #[test]
+    fn test_merge_keeps_distant_objects() {
+        let left = sheet("ED0A01", 0.0);
+        let far = mirrored(sheet("ED0A02", 0.0), 100.0);
+
+        let merged = Lot::merge(&[left.clone(), far], 0.01).unwrap();
+        assert_eq!(merged.features().len(), 2);
+        assert_eq!(merged.subsets["SeTOP_1"].arcs.len(), 4);
+
+        let mut other = left.clone();
+        other.geo.reference.code = Some("LAMB93".to_string());
+        assert!(matches!(
+            Lot::merge(&[left, other], 0.01),
+            Err(EdigeoError::IncompatibleLots(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge_keeps_adjacent_buildings() {
+        // Two buildings with the same attributes, 6 mm apart on both sides of
+        // the sheet boundary, are not parts of one building.
+        let left = with_building(sheet("ED0A01", 0.0), "01");
+        let right = mirrored(with_building(sheet("ED0A02", 0.0), "01"), 0.0);
+
+        let merged = Lot::merge(&[left, right], 0.01).unwrap();
+        let mut ids: Vec<_> = merged.features().into_iter().map(|f| f.id).collect();
+        ids.sort();
+        assert_eq!(
+            ids,
+            [
+                "ED0A01_Objet_BAT",
+                "ED0A01_Objet_ED0A01",
+                "ED0A02_Objet_BAT"
+            ]
+        );
+    }
+}
@@ -0,0 +1,1057 @@
+//! Contains the Mapbox Vector Tile (MVT) tiler of [`Lot`]s.
+//!
+//! Features are reprojected to Web Mercator, then for every zoom level
+//! simplified with the Douglas-Peucker algorithm, clipped to each tile they
+//! cross (with a small buffer) and encoded as protobuf, with one layer per
+//! object type named as the `.shp` files (`PARCELLE`, `BATIMENT`...). Each
+//! feature carries its record identifier as `ID` and its attributes under
+//! their dictionary labels, and is numbered in its layer as MVT identifier.
+//!
+//! The resulting [`Tileset`] is written to a `z/x/y.pbf` directory tree or,
+//! with the `mbtiles` feature, to an MBTiles SQLite file:
+//!
+//! ```no_run
+//! use edigeo::{mvt::*, *};
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle()).unwrap();
+//! let tileset = MvtTiler::new(13, 18).tile_lots(&[lot]).unwrap();
+//! tileset.write_dir("tiles/").unwrap();
+//! ```
+use crate::error::{EdigeoError, EdigeoResult};
+use crate::geometry::{BoundingBox, Coord, Geometry, LineString, Polygon};
+use crate::lot::{layer_name, Feature, Lot};
+use crate::FormatResult;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Highest zoom level that can be tiled, whose tile rows and columns still
+/// fit in the integers of the tiles.
+pub const MAX_ZOOM: u8 = 24;
+
+/// Latitude limit of the Web Mercator projection, in degrees.
+const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// Coordinates of a tile in the XYZ scheme, rows growing southwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileId {
+    /// Zoom level
+    pub z: u8,
+    /// Column, from the antimeridian eastwards
+    pub x: u32,
+    /// Row, from the north
+    pub y: u32,
+}
+
+/// Cuts lots into vector tiles.
+/// ```ignore
+///     let tiler = MvtTiler { buffer: 128, ..MvtTiler::new(12, 18) };
+///     let tileset = tiler.tile_lots(&lots)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MvtTiler {
+    /// Lowest zoom level generated
+    pub min_zoom: u8,
+    /// Highest zoom level generated
+    pub max_zoom: u8,
+    /// Size of a tile in its integer coordinates, 4096 by default
+    pub extent: u32,
+    /// Margin kept around the tiles when clipping, in tile coordinates
+    pub buffer: u32,
+    /// Distance under which vertices are simplified away, in tile
+    /// coordinates
+    pub tolerance: f64,
+}
+
+impl Default for MvtTiler {
+    fn default() -> Self {
+        Self::new(12, 18)
+    }
+}
+
+/// The vector tiles of a set of lots with their TileJSON metadata.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tileset {
+    /// Name of the tileset: the names of the lots tiled
+    pub name: String,
+    /// Lowest zoom level
+    pub min_zoom: u8,
+    /// Highest zoom level
+    pub max_zoom: u8,
+    /// Extent of the features in WGS 84 longitudes and latitudes
+    pub bounds: Option<BoundingBox>,
+    /// Fields of each layer with their type, `String` or `Number`
+    pub layers: BTreeMap<String, BTreeMap<String, &'static str>>,
+    /// Uncompressed MVT protobuf of each tile
+    pub tiles: BTreeMap<TileId, Vec<u8>>,
+}
+
+impl MvtTiler {
+    /// Constructor method for creating a new [`MvtTiler`] generating the
+    /// zoom levels `min_zoom` to `max_zoom`, with tiles of 4096 units, a
+    /// buffer of 64 and a simplification tolerance of 1.
+    pub fn new(min_zoom: u8, max_zoom: u8) -> Self {
+        Self {
+            min_zoom,
+            max_zoom,
+            extent: 4096,
+            buffer: 64,
+            tolerance: 1.0,
+        }
+    }
+
+    /// Cuts the features of the lots into tiles. Lots of different
+    /// projections can be mixed, but each must have a known one.
+    ///
+    /// The features of each layer are numbered from 1 in the order of the
+    /// lots, as MVT identifiers unique in the layer. Fails with
+    /// [`EdigeoError::InvalidZoom`] when `max_zoom` is above [`MAX_ZOOM`] or
+    /// `min_zoom` above `max_zoom`.
+    pub fn tile_lots(&self, lots: &[Lot]) -> EdigeoResult<Tileset> {
+        if self.max_zoom > MAX_ZOOM {
+            return Err(EdigeoError::InvalidZoom(format!(
+                "max_zoom {} is above {MAX_ZOOM}",
+                self.max_zoom
+            )));
+        }
+        if self.min_zoom > self.max_zoom {
+            return Err(EdigeoError::InvalidZoom(format!(
+                "min_zoom {} is above max_zoom {}",
+                self.min_zoom, self.max_zoom
+            )));
+        }
+        let mut tileset = Tileset {
+            name: lots
+                .iter()
+                .map(|lot| lot.name.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            min_zoom: self.min_zoom,
+            max_zoom: self.max_zoom,
+            ..Tileset::default()
+        };
+        let mut tiles: BTreeMap<TileId, BTreeMap<String, LayerBuilder>> = BTreeMap::new();
+        // Last identifier given in each layer.
+        let mut ids: HashMap<String, u64> = HashMap::new();
+
+        for lot in lots {
+            let projection = lot.projection().ok_or_else(|| {
+                EdigeoError::UnknownProjection(lot.geo.reference.code.clone().unwrap_or_default())
+            })?;
+            for (object_type, features) in lot.layers() {
+                let name = layer_name(&object_type).to_string();
+                let fields = tileset.layers.entry(name.clone()).or_default();
+                for feature in features {
+                    let Some(geometry) = &feature.geometry else {
+                        continue;
+                    };
+                    let wgs84 = Shape::new(geometry).map(|c| projection.to_wgs84(c));
+                    if let Some(bbox) = wgs84.bbox() {
+                        tileset.bounds = Some(match tileset.bounds {
+                            Some(bounds) => bounds.expand(&bbox),
+                            None => bbox,
+                        });
+                    }
+                    let world = wgs84.map(mercator);
+                    let properties = properties(lot, &feature);
+                    for (key, value) in &properties {
+                        fields.insert(key.clone(), value.kind());
+                    }
+                    let id = ids.entry(name.clone()).or_default();
+                    *id += 1;
+                    let id = *id;
+
+                    for z in self.min_zoom..=self.max_zoom {
+                        for (tile, commands) in self.cut(&world, z) {
+                            tiles
+                                .entry(tile)
+                                .or_default()
+                                .entry(name.clone())
+                                .or_default()
+                                .add(id, &properties, commands);
+                        }
+                    }
+                }
+            }
+        }
+
+        tileset.tiles = tiles
+            .into_iter()
+            .map(|(tile, layers)| {
+                let mut data = Vec::new();
+                for (name, layer) in layers {
+                    write_bytes(&mut data, 3, &layer.encode(&name, self.extent));
+                }
+                (tile, data)
+            })
+            .collect();
+        Ok(tileset)
+    }
+
+    /// Simplifies and clips a shape in world coordinates for every tile it
+    /// crosses at zoom `z`, returning the encoded geometries.
+    fn cut(&self, world: &Shape, z: u8) -> Vec<(TileId, Commands)> {
+        let extent = self.extent as f64;
+        let tiles = 1u64 << z;
+        let scale = tiles as f64 * extent;
+        let shape = world
+            .map(|c| Coord {
+                x: c.x * scale,
+                y: c.y * scale,
+            })
+            .simplify(self.tolerance);
+        let Some(bbox) = shape.bbox() else {
+            return Vec::new();
+        };
+        let buffer = self.buffer as f64;
+        let range = |min: f64, max: f64| {
+            let last = tiles as f64 - 1.0;
+            let first = ((min - buffer) / extent).floor().clamp(0.0, last) as u32;
+            first..=((max + buffer) / extent).floor().clamp(0.0, last) as u32
+        };
+
+        let mut cut = Vec::new();
+        for x in range(bbox.min.x, bbox.max.x) {
+            for y in range(bbox.min.y, bbox.max.y) {
+                let origin = Coord {
+                    x: x as f64 * extent,
+                    y: y as f64 * extent,
+                };
+                let min = Coord {
+                    x: origin.x - buffer,
+                    y: origin.y - buffer,
+                };
+                let max = Coord {
+                    x: origin.x + extent + buffer,
+                    y: origin.y + extent + buffer,
+                };
+                if let Some(commands) = shape.clip(&min, &max).encode(&origin) {
+                    cut.push((TileId { z, x, y }, commands));
+                }
+            }
+        }
+        cut
+    }
+}
+
+impl Tileset {
+    /// TileJSON describing the tileset and its `vector_layers`, with tiles
+    /// addressed relatively as `{z}/{x}/{y}.pbf`.
+    pub fn tilejson(&self) -> String {
+        let layers: Vec<String> = self
+            .layers
+            .iter()
+            .map(|(name, fields)| {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, kind)| format!("{}:\"{kind}\"", json_string(field)))
+                    .collect();
+                format!(
+                    r#"{{"id":{},"fields":{{{}}},"minzoom":{},"maxzoom":{}}}"#,
+                    json_string(name),
+                    fields.join(","),
+                    self.min_zoom,
+                    self.max_zoom
+                )
+            })
+            .collect();
+        let mut json = format!(
+            r#"{{"tilejson":"3.0.0","name":{},"tiles":["{{z}}/{{x}}/{{y}}.pbf"],"minzoom":{},"maxzoom":{},"vector_layers":[{}]"#,
+            json_string(&self.name),
+            self.min_zoom,
+            self.max_zoom,
+            layers.join(",")
+        );
+        if let Some((bounds, center)) = self.bounds_and_center() {
+            json.push_str(&format!(r#","bounds":[{bounds}],"center":[{center}]"#));
+        }
+        json.push('}');
+        json
+    }
+
+    /// Bounds and center as comma separated values, the center at the lowest
+    /// zoom level.
+    fn bounds_and_center(&self) -> Option<(String, String)> {
+        let BoundingBox { min, max } = self.bounds?;
+        Some((
+            format!("{},{},{},{}", min.x, min.y, max.x, max.y),
+            format!(
+                "{},{},{}",
+                (min.x + max.x) / 2.0,
+                (min.y + max.y) / 2.0,
+                self.min_zoom
+            ),
+        ))
+    }
+
+    /// Writes the tiles into `dir` as `z/x/y.pbf` files with a `metadata.json`
+    /// TileJSON, and returns the number of tiles written.
+    pub fn write_dir<P: AsRef<Path>>(&self, dir: P) -> EdigeoResult<usize> {
+        let dir = dir.as_ref();
+        for (tile, data) in &self.tiles {
+            let path = dir.join(tile.z.to_string()).join(tile.x.to_string());
+            std::fs::create_dir_all(&path)?;
+            std::fs::write(path.join(format!("{}.pbf", tile.y)), data)?;
+        }
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join("metadata.json"), self.tilejson())?;
+        Ok(self.tiles.len())
+    }
+
+    /// Writes the tiles into an MBTiles file, gzip compressed, replacing the
+    /// tiles and metadata already stored under the same keys. Fails with
+    /// [`EdigeoError::InvalidZoom`] for tiles above [`MAX_ZOOM`].
+    #[cfg(feature = "mbtiles")]
+    pub fn write_mbtiles<P: AsRef<Path>>(&self, path: P) -> EdigeoResult<()> {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut connection = rusqlite::Connection::open(path)?;
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(
+            "CREATE TABLE IF NOT EXISTS metadata (name TEXT, value TEXT);
+             CREATE UNIQUE INDEX IF NOT EXISTS name ON metadata (name);
+             CREATE TABLE IF NOT EXISTS tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+             CREATE UNIQUE INDEX IF NOT EXISTS tile_index ON tiles (zoom_level, tile_column, tile_row);",
+        )?;
+
+        let mut metadata = vec![
+            ("name", self.name.clone()),
+            ("format", "pbf".to_string()),
+            ("type", "overlay".to_string()),
+            ("minzoom", self.min_zoom.to_string()),
+            ("maxzoom", self.max_zoom.to_string()),
+            ("json", self.tilejson()),
+        ];
+        if let Some((bounds, center)) = self.bounds_and_center() {
+            metadata.push(("bounds", bounds));
+            metadata.push(("center", center));
+        }
+        {
+            let mut insert = transaction
+                .prepare("INSERT OR REPLACE INTO metadata (name, value) VALUES (?1, ?2)")?;
+            for (name, value) in &metadata {
+                insert.execute((name, value))?;
+            }
+
+            let mut insert = transaction.prepare(
+                "INSERT OR REPLACE INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (tile, data) in &self.tiles {
+                if tile.z > MAX_ZOOM {
+                    return Err(EdigeoError::InvalidZoom(format!(
+                        "tile {}/{}/{} is above {MAX_ZOOM}",
+                        tile.z, tile.x, tile.y
+                    )));
+                }
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                // MBTiles rows follow the TMS scheme, from the south.
+                let row = (1u32 << tile.z) - 1 - tile.y;
+                insert.execute((tile.z, tile.x, row, encoder.finish()?))?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+/// Projects WGS 84 degrees to Web Mercator world coordinates, from 0 to 1
+/// eastwards and southwards.
+fn mercator(c: &Coord) -> Coord {
+    let lat = c.y.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    Coord {
+        x: (c.x + 180.0) / 360.0,
+        y: (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / std::f64::consts::PI) / 2.0,
+    }
+}
+
+/// A property value of a feature.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    String(String),
+    Int(i64),
+    Double(f64),
+}
+
+impl Value {
+    /// Type of the value in the TileJSON `fields`.
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::String(_) => "String",
+            Value::Int(_) | Value::Double(_) => "Number",
+        }
+    }
+
+    /// Key deduplicating the values of a layer.
+    fn key(&self) -> String {
+        match self {
+            Value::String(s) => format!("s{s}"),
+            Value::Int(i) => format!("i{i}"),
+            Value::Double(d) => format!("d{}", d.to_bits()),
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Value::String(s) => write_bytes(&mut buf, 1, s.as_bytes()),
+            Value::Double(d) => {
+                write_key(&mut buf, 3, 1);
+                buf.extend_from_slice(&d.to_le_bytes());
+            }
+            Value::Int(i) => {
+                write_key(&mut buf, 4, 0);
+                write_varint(&mut buf, *i as u64);
+            }
+        }
+        buf
+    }
+}
+
+/// The record identifier and the attributes of a feature under their
+/// dictionary labels, dates in ISO 8601.
+fn properties(lot: &Lot, feature: &Feature) -> Vec<(String, Value)> {
+    let mut properties = vec![("ID".to_string(), Value::String(feature.id.clone()))];
+    for attribute in &feature.attributes {
+        let value = match &attribute.value {
+            Some(FormatResult::Int(i)) => Value::Int(*i as i64),
+            Some(FormatResult::Float(f)) => Value::Double(*f),
+            Some(FormatResult::Date(date)) => Value::String(date.format("%Y-%m-%d").to_string()),
+            Some(value) => match value.to_string().trim() {
+                "" => continue,
+                text => Value::String(text.to_string()),
+            },
+            None => continue,
+        };
+        properties.push((lot.attribute_label(&attribute.id).to_string(), value));
+    }
+    properties
+}
+
+/// MVT geometry type and commands of a feature.
+type Commands = (u32, Vec<u32>);
+
+/// A layer of a tile being built, with its key and value tables.
+#[derive(Debug, Default)]
+struct LayerBuilder {
+    keys: Vec<String>,
+    key_index: HashMap<String, u32>,
+    values: Vec<Value>,
+    value_index: HashMap<String, u32>,
+    features: Vec<Vec<u8>>,
+}
+
+impl LayerBuilder {
+    fn add(&mut self, id: u64, properties: &[(String, Value)], (kind, commands): Commands) {
+        let mut tags = Vec::with_capacity(properties.len() * 2);
+        for (key, value) in properties {
+            let next = self.keys.len() as u32;
+            let key = *self.key_index.entry(key.clone()).or_insert_with(|| {
+                self.keys.push(key.clone());
+                next
+            });
+            let next = self.values.len() as u32;
+            let value = *self.value_index.entry(value.key()).or_insert_with(|| {
+                self.values.push(value.clone());
+                next
+            });
+            tags.extend([key, value]);
+        }
+
+        let mut feature = Vec::new();
+        write_key(&mut feature, 1, 0);
+        write_varint(&mut feature, id);
+        write_packed(&mut feature, 2, &tags);
+        write_key(&mut feature, 3, 0);
+        write_varint(&mut feature, kind as u64);
+        write_packed(&mut feature, 4, &commands);
+        self.features.push(feature);
+    }
+
+    fn encode(&self, name: &str, extent: u32) -> Vec<u8> {
+        let mut layer = Vec::new();
+        write_key(&mut layer, 15, 0);
+        write_varint(&mut layer, 2);
+        write_bytes(&mut layer, 1, name.as_bytes());
+        for feature in &self.features {
+            write_bytes(&mut layer, 2, feature);
+        }
+        for key in &self.keys {
+            write_bytes(&mut layer, 3, key.as_bytes());
+        }
+        for value in &self.values {
+            write_bytes(&mut layer, 4, &value.encode());
+        }
+        write_key(&mut layer, 5, 0);
+        write_varint(&mut layer, extent as u64);
+        layer
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_key(buf: &mut Vec<u8>, field: u32, wire_type: u32) {
+    write_varint(buf, (field << 3 | wire_type) as u64);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_key(buf, field, 2);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn write_packed(buf: &mut Vec<u8>, field: u32, values: &[u32]) {
+    let mut packed = Vec::new();
+    for value in values {
+        write_varint(&mut packed, *value as u64);
+    }
+    write_bytes(buf, field, &packed);
+}
+
+fn json_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A geometry being tiled, its coordinates in any plane.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Points(Vec<Coord>),
+    Lines(Vec<LineString>),
+    Polygons(Vec<Polygon>),
+}
+
+impl Shape {
+    fn new(geometry: &Geometry) -> Self {
+        match geometry {
+            Geometry::Point(c) => Shape::Points(vec![*c]),
+            Geometry::MultiPoint(points) => Shape::Points(points.clone()),
+            Geometry::LineString(line) => Shape::Lines(vec![line.clone()]),
+            Geometry::MultiLineString(lines) => Shape::Lines(lines.clone()),
+            Geometry::Polygon(polygon) => Shape::Polygons(vec![polygon.clone()]),
+            Geometry::MultiPolygon(polygons) => Shape::Polygons(polygons.clone()),
+        }
+    }
+
+    fn map<F: Fn(&Coord) -> Coord>(&self, f: F) -> Self {
+        let line = |line: &LineString| line.iter().map(&f).collect::<LineString>();
+        match self {
+            Shape::Points(points) => Shape::Points(line(points)),
+            Shape::Lines(lines) => Shape::Lines(lines.iter().map(line).collect()),
+            Shape::Polygons(polygons) => Shape::Polygons(
+                polygons
+                    .iter()
+                    .map(|rings| rings.iter().map(line).collect())
+                    .collect(),
+            ),
+        }
+    }
+
+    fn bbox(&self) -> Option<BoundingBox> {
+        match self {
+            Shape::Points(points) => BoundingBox::from_coords(points),
+            Shape::Lines(lines) => BoundingBox::from_coords(lines.iter().flatten()),
+            // The exterior rings hold the holes.
+            Shape::Polygons(polygons) => {
+                BoundingBox::from_coords(polygons.iter().filter_map(|p| p.first()).flatten())
+            }
+        }
+    }
+
+    /// Simplifies lines and rings, dropping the rings collapsing to less than
+    /// three vertices and the polygons losing their exterior ring.
+    fn simplify(&self, tolerance: f64) -> Self {
+        match self {
+            Shape::Points(_) => self.clone(),
+            Shape::Lines(lines) => {
+                Shape::Lines(lines.iter().map(|l| simplify(l, tolerance)).collect())
+            }
+            Shape::Polygons(polygons) => Shape::Polygons(
+                polygons
+                    .iter()
+                    .filter_map(|rings| {
+                        let mut rings = rings.iter().map(|r| simplify(r, tolerance));
+                        let exterior = rings.next().filter(|r| r.len() > 3)?;
+                        Some(
+                            std::iter::once(exterior)
+                                .chain(rings.filter(|r| r.len() > 3))
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Clips the shape to the rectangle from `min` to `max`.
+    fn clip(&self, min: &Coord, max: &Coord) -> Self {
+        let inside = |c: &Coord| c.x >= min.x && c.x <= max.x && c.y >= min.y && c.y <= max.y;
+        match self {
+            Shape::Points(points) => {
+                Shape::Points(points.iter().filter(|c| inside(c)).copied().collect())
+            }
+            Shape::Lines(lines) => Shape::Lines(
+                lines
+                    .iter()
+                    .flat_map(|line| clip_line(line, min, max))
+                    .collect(),
+            ),
+            Shape::Polygons(polygons) => Shape::Polygons(
+                polygons
+                    .iter()
+                    .filter_map(|rings| {
+                        let mut rings = rings.iter().map(|r| clip_ring(r, min, max));
+                        let exterior = rings.next().filter(|r| r.len() > 3)?;
+                        Some(
+                            std::iter::once(exterior)
+                                .chain(rings.filter(|r| r.len() > 3))
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Encodes the shape as MVT commands relative to the tile `origin`, or
+    /// `None` when nothing remains once rounded to integer coordinates.
+    fn encode(&self, origin: &Coord) -> Option<Commands> {
+        let quantize = |line: &[Coord]| {
+            let mut points: Vec<(i64, i64)> = Vec::with_capacity(line.len());
+            for c in line {
+                let point = (
+                    (c.x - origin.x).round() as i64,
+                    (c.y - origin.y).round() as i64,
+                );
+                if points.last() != Some(&point) {
+                    points.push(point);
+                }
+            }
+            points
+        };
+        let mut cursor = Cursor::default();
+        let kind = match self {
+            Shape::Points(points) => {
+                let points: Vec<(i64, i64)> = points.iter().flat_map(|c| quantize(&[*c])).collect();
+                if !points.is_empty() {
+                    cursor.command(1, points.len());
+                    points.into_iter().for_each(|p| cursor.point(p));
+                }
+                1
+            }
+            Shape::Lines(lines) => {
+                for line in lines.iter().map(|line| quantize(line)) {
+                    if line.len() > 1 {
+                        cursor.line(&line);
+                    }
+                }
+                2
+            }
+            Shape::Polygons(polygons) => {
+                for rings in polygons {
+                    for (i, ring) in rings.iter().enumerate() {
+                        let mut ring = quantize(ring);
+                        if ring.len() > 1 && ring.first() == ring.last() {
+                            ring.pop();
+                        }
+                        let area = ring_area(&ring);
+                        if ring.len() < 3 || area == 0 {
+                            match i {
+                                0 => break,
+                                _ => continue,
+                            }
+                        }
+                        // Exterior rings have a positive area with the Y axis
+                        // pointing down, holes a negative one.
+                        if (area > 0) != (i == 0) {
+                            ring.reverse();
+                        }
+                        cursor.line(&ring);
+                        cursor.command(7, 1);
+                    }
+                }
+                3
+            }
+        };
+        (!cursor.commands.is_empty()).then_some((kind, cursor.commands))
+    }
+}
+
+/// Twice the signed area of an open ring.
+fn ring_area(ring: &[(i64, i64)]) -> i64 {
+    (0..ring.len())
+        .map(|i| {
+            let ((x0, y0), (x1, y1)) = (ring[i], ring[(i + 1) % ring.len()]);
+            x0 * y1 - x1 * y0
+        })
+        .sum()
+}
+
+/// Writes geometry commands with parameters relative to the previous point.
+#[derive(Debug, Default)]
+struct Cursor {
+    x: i64,
+    y: i64,
+    commands: Vec<u32>,
+}
+
+impl Cursor {
+    fn command(&mut self, id: u32, count: usize) {
+        self.commands.push(id & 0x7 | (count as u32) << 3);
+    }
+
+    fn point(&mut self, (x, y): (i64, i64)) {
+        let zigzag = |n: i64| ((n << 1) ^ (n >> 63)) as u32;
+        self.commands.push(zigzag(x - self.x));
+        self.commands.push(zigzag(y - self.y));
+        (self.x, self.y) = (x, y);
+    }
+
+    fn line(&mut self, line: &[(i64, i64)]) {
+        self.command(1, 1);
+        self.point(line[0]);
+        self.command(2, line.len() - 1);
+        line[1..].iter().for_each(|p| self.point(*p));
+    }
+}
+
+/// Distance from `c` to the segment from `a` to `b`.
+fn segment_distance(c: &Coord, a: &Coord, b: &Coord) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length = dx * dx + dy * dy;
+    let t = match length {
+        0.0 => 0.0,
+        _ => (((c.x - a.x) * dx + (c.y - a.y) * dy) / length).clamp(0.0, 1.0),
+    };
+    (c.x - a.x - t * dx).hypot(c.y - a.y - t * dy)
+}
+
+/// Douglas-Peucker simplification keeping the end points.
+fn simplify(line: &[Coord], tolerance: f64) -> LineString {
+    if line.len() < 3 {
+        return line.to_vec();
+    }
+    let mut keep = vec![false; line.len()];
+    keep[0] = true;
+    keep[line.len() - 1] = true;
+    let mut stack = vec![(0, line.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let (mut index, mut distance) = (0, 0.0);
+        for i in first + 1..last {
+            let d = segment_distance(&line[i], &line[first], &line[last]);
+            if d > distance {
+                (index, distance) = (i, d);
+            }
+        }
+        if distance > tolerance {
+            keep[index] = true;
+            stack.push((first, index));
+            stack.push((index, last));
+        }
+    }
+    line.iter()
+        .zip(keep)
+        .filter_map(|(c, keep)| keep.then_some(*c))
+        .collect()
+}
+
+/// Liang-Barsky clipping of a segment, `None` when it lies outside.
+fn clip_segment(a: &Coord, b: &Coord, min: &Coord, max: &Coord) -> Option<(Coord, Coord)> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let (mut t0, mut t1) = (0.0, 1.0);
+    for (p, q) in [
+        (-dx, a.x - min.x),
+        (dx, max.x - a.x),
+        (-dy, a.y - min.y),
+        (dy, max.y - a.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let r = q / p;
+        if p < 0.0 {
+            if r > t1 {
+                return None;
+            }
+            t0 = f64::max(t0, r);
+        } else {
+            if r < t0 {
+                return None;
+            }
+            t1 = f64::min(t1, r);
+        }
+    }
+    let at = |t: f64| match t {
+        0.0 => *a,
+        1.0 => *b,
+        _ => Coord {
+            x: a.x + t * dx,
+            y: a.y + t * dy,
+        },
+    };
+    Some((at(t0), at(t1)))
+}
+
+/// Clips a line to a rectangle, splitting it where it goes out.
+fn clip_line(line: &[Coord], min: &Coord, max: &Coord) -> Vec<LineString> {
+    let mut lines = Vec::new();
+    let mut current: LineString = Vec::new();
+    for w in line.windows(2) {
+        match clip_segment(&w[0], &w[1], min, max) {
+            Some((a, b)) => {
+                if current.last() != Some(&a) {
+                    if current.len() > 1 {
+                        lines.push(std::mem::take(&mut current));
+                    }
+                    current = vec![a];
+                }
+                current.push(b);
+                if b != w[1] {
+                    lines.push(std::mem::take(&mut current));
+                }
+            }
+            None => {
+                if current.len() > 1 {
+                    lines.push(std::mem::take(&mut current));
+                }
+                current.clear();
+            }
+        }
+    }
+    if current.len() > 1 {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Sutherland-Hodgman clipping of a closed ring to a rectangle.
+fn clip_ring(ring: &[Coord], min: &Coord, max: &Coord) -> LineString {
+    let mut output: LineString = ring[..ring.len().saturating_sub(1)].to_vec();
+    for edge in 0..4 {
+        let input = std::mem::take(&mut output);
+        let Some(&last) = input.last() else {
+            break;
+        };
+        let inside = |c: &Coord| match edge {
+            0 => c.x >= min.x,
+            1 => c.x <= max.x,
+            2 => c.y >= min.y,
+            _ => c.y <= max.y,
+        };
+        let intersection = |a: &Coord, b: &Coord| match edge {
+            0 | 1 => {
+                let x = if edge == 0 { min.x } else { max.x };
+                Coord {
+                    x,
+                    y: a.y + (x - a.x) / (b.x - a.x) * (b.y - a.y),
+                }
+            }
+            _ => {
+                let y = if edge == 2 { min.y } else { max.y };
+                Coord {
+                    x: a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x),
+                    y,
+                }
+            }
+        };
+        let mut previous = last;
+        for c in &input {
+            match (inside(&previous), inside(c)) {
+                (true, true) => output.push(*c),
+                (false, true) => {
+                    output.push(intersection(&previous, c));
+                    output.push(*c);
+                }
+                (true, false) => output.push(intersection(&previous, c)),
+                (false, false) => {}
+            }
+            previous = *c;
+        }
+    }
+    if let Some(&first) = output.first() {
+        output.push(first);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::{Object, VECFile};
+    use crate::test_support::{coord, line, link, node, reference};
+
+    #[test]
+    fn test_simplify() {
+        let zigzag = line(&[(0.0, 0.0), (1.0, 0.2), (2.0, -0.2), (3.0, 5.0), (4.0, 0.0)]);
+        assert_eq!(
+            simplify(&zigzag, 0.5),
+            line(&[(0.0, 0.0), (2.0, -0.2), (3.0, 5.0), (4.0, 0.0)])
+        );
+        assert_eq!(simplify(&zigzag, 10.0), line(&[(0.0, 0.0), (4.0, 0.0)]));
+    }
+
+    #[test]
+    fn test_clip() {
+        let (min, max) = (Coord { x: 0.0, y: 0.0 }, Coord { x: 10.0, y: 10.0 });
+        let square = line(&[
+            (-5.0, -5.0),
+            (5.0, -5.0),
+            (5.0, 5.0),
+            (-5.0, 5.0),
+            (-5.0, -5.0),
+        ]);
+        let clipped = clip_ring(&square, &min, &max);
+        assert_eq!(clipped.len(), 5);
+        assert_eq!(
+            BoundingBox::from_coords(&clipped),
+            Some(BoundingBox {
+                min,
+                max: Coord { x: 5.0, y: 5.0 }
+            })
+        );
+
+        let through = line(&[
+            (-5.0, 5.0),
+            (5.0, 5.0),
+            (5.0, 15.0),
+            (6.0, 15.0),
+            (6.0, 5.0),
+        ]);
+        assert_eq!(
+            clip_line(&through, &min, &max),
+            vec![
+                line(&[(0.0, 5.0), (5.0, 5.0), (5.0, 10.0)]),
+                line(&[(6.0, 10.0), (6.0, 5.0)])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        // Counter-clockwise with Y pointing down: reversed into an exterior ring.
+        let square = line(&[(1.0, 1.0), (1.0, 3.0), (3.0, 3.0), (3.0, 1.0), (1.0, 1.0)]);
+        let shape = Shape::Polygons(vec![vec![square]]);
+        assert_eq!(
+            shape.encode(&Coord { x: 0.0, y: 0.0 }),
+            Some((3, vec![9, 6, 2, 26, 0, 4, 3, 0, 0, 3, 15]))
+        );
+
+        let points = Shape::Points(line(&[(25.0, 17.0)]));
+        assert_eq!(
+            points.encode(&Coord { x: 0.0, y: 0.0 }),
+            Some((1, vec![9, 50, 34]))
+        );
+        assert_eq!(Shape::Lines(vec![]).encode(&Coord { x: 0.0, y: 0.0 }), None);
+    }
+
+    #[test]
+    fn test_mercator_tiles() {
+        let world = mercator(&Coord { x: 0.0, y: 0.0 });
+        assert_eq!(world, Coord { x: 0.5, y: 0.5 });
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 300);
+        assert_eq!(buf, [0xac, 0x02]);
+    }
+
+    /// Reads a varint at the start of `data`, moving past it.
+    fn read_varint(data: &mut &[u8]) -> u64 {
+        let mut value = 0;
+        for (i, byte) in data.iter().enumerate() {
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                *data = &data[i + 1..];
+                break;
+            }
+        }
+        value
+    }
+
+    /// Returns the varint (`Err`) and length-delimited (`Ok`) fields of a
+    /// protobuf message with their numbers.
+    fn fields(mut data: &[u8]) -> Vec<(u64, Result<&[u8], u64>)> {
+        let mut fields = Vec::new();
+        while !data.is_empty() {
+            let key = read_varint(&mut data);
+            let value = match key & 0x7 {
+                0 => Err(read_varint(&mut data)),
+                _ => {
+                    let len = read_varint(&mut data) as usize;
+                    let (bytes, rest) = data.split_at(len);
+                    data = rest;
+                    Ok(bytes)
+                }
+            };
+            fields.push((key >> 3, value));
+        }
+        fields
+    }
+
+    #[test]
+    fn test_feature_ids() {
+        // Two sheets holding a boundary mark with the same record identifier.
+        let sheet = |name: &str| {
+            let vec = VECFile {
+                nodes: vec![node("Noeud_1", coord(700_000.0, 6_600_000.0))],
+                objects: vec![Object {
+                    id: "Objet_1".to_string(),
+                    definition: Some(reference("OBJ", "BORNE_id")),
+                    attribute_count: Some(0),
+                    attributes: vec![],
+                    quality: None,
+                }],
+                links: vec![link(
+                    "ID_S_RCO_BORNE_id",
+                    &[("FEA", "Objet_1"), ("PNO", "Noeud_1")],
+                )],
+                ..VECFile::default()
+            };
+            let mut lot = Lot {
+                name: name.to_string(),
+                subsets: BTreeMap::from([("SeTOP_1".to_string(), vec)]),
+                ..Lot::default()
+            };
+            lot.geo.reference.code = Some("LAMB93".to_string());
+            lot
+        };
+        let tileset = MvtTiler::new(10, 10)
+            .tile_lots(&[sheet("ED0A01"), sheet("ED0A02")])
+            .unwrap();
+        assert_eq!(tileset.tiles.len(), 1);
+
+        let tile = tileset.tiles.values().next().unwrap();
+        let [(3, Ok(layer))] = fields(tile)[..] else {
+            panic!("unexpected tile {tile:?}");
+        };
+        let ids: Vec<_> = fields(layer)
+            .into_iter()
+            .filter_map(|(number, value)| match (number, value) {
+                (2, Ok(feature)) => fields(feature)
+                    .into_iter()
+                    .find_map(|(number, value)| (number == 1).then_some(value)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, [Err(1), Err(2)]);
+    }
+
+    #[test]
+    fn test_invalid_zoom() {
+        for tiler in [MvtTiler::new(12, 25), MvtTiler::new(18, 12)] {
+            assert!(matches!(
+                tiler.tile_lots(&[]),
+                Err(EdigeoError::InvalidZoom(_))
+            ));
+        }
+        assert!(MvtTiler::new(0, MAX_ZOOM).tile_lots(&[]).is_ok());
+    }
+}
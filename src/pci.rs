@@ -0,0 +1,571 @@
+//! Contains the typed objects of the PCI (Plan Cadastral Informatisé)
+//! catalogue of the CNIG/DGFiP specification, enabled by the `pci` feature.
+//!
+//! A [`Cadastre`] sorts the features of a lot by object type into typed
+//! structs whose fields are named after the attribute codes: `PARCELLE_id`
+//! objects become [`Parcelle`]s with their `IDU_id`, `SUPF_id`, `INDP_id`...
+//! values. Pre-coded attributes keep their code, e.g. `"01"`.
+//!
+//! ```no_run
+//! use edigeo::{pci::Cadastre, *};
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo.tar.bz2").read_bundle()).unwrap();
+//! let cadastre = Cadastre::from_lot(&lot);
+//! for parcelle in &cadastre.parcelles {
+//!     println!("{} {:?} m²", parcelle.idu, parcelle.supf);
+//! }
+//! ```
+use crate::geometry::Geometry;
+use crate::lot::{Feature, Lot};
+use chrono::NaiveDate;
+
+/// Typed access to the integer, date and place name values of a feature.
+struct Values<'a>(&'a Feature);
+
+impl Values<'_> {
+    fn integer(&self, id: &str) -> Option<u32> {
+        self.0.text(id)?.trim_start_matches('+').parse().ok()
+    }
+
+    /// Parses a `dd/mm/yyyy` date.
+    fn date(&self, id: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.0.text(id)?, "%d/%m/%Y").ok()
+    }
+
+    /// Returns the words of a place name, from `TEX_id` then `TEX2_id` to
+    /// `TEX10_id`.
+    fn words(&self) -> Vec<String> {
+        std::iter::once("TEX_id".to_string())
+            .chain((2..=10).map(|n| format!("TEX{n}_id")))
+            .filter_map(|id| self.0.text(&id))
+            .collect()
+    }
+}
+
+/// Land parcel (`PARCELLE_id`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parcelle {
+    /// Record identifier of the object
+    pub id: String,
+    /// Parcel identifier (`IDU`), e.g. `6430000A0031`
+    pub idu: String,
+    /// Tax area in m² (`SUPF`)
+    pub supf: Option<f64>,
+    /// Plan indicator (`INDP`): `01` shown on the plan, `02` not shown
+    pub indp: Option<String>,
+    /// Survey code (`COAR`)
+    pub coar: Option<String>,
+    /// Parcel number (`TEX`)
+    pub tex: Option<String>,
+    /// Polygon of the parcel
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for Parcelle {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            idu: feature.text("IDU_id").unwrap_or_default(),
+            supf: feature.float("SUPF_id"),
+            indp: feature.text("INDP_id"),
+            coar: feature.text("COAR_id"),
+            tex: feature.text("TEX_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Building (`BATIMENT_id`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Batiment {
+    /// Record identifier of the object
+    pub id: String,
+    /// Kind of building (`DUR`): `01` solid, `02` light
+    pub dur: Option<String>,
+    /// Text written on the building (`TEX`)
+    pub tex: Option<String>,
+    /// Polygon of the building
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for Batiment {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            dur: feature.text("DUR_id"),
+            tex: feature.text("TEX_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Cadastral section (`SECTION_id`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Section {
+    /// Record identifier of the object
+    pub id: String,
+    /// Section identifier (`IDU`), e.g. `6430000A`
+    pub idu: String,
+    /// Section letters (`TEX`), e.g. `A`
+    pub tex: Option<String>,
+    /// Polygon of the section
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for Section {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            idu: feature.text("IDU_id").unwrap_or_default(),
+            tex: feature.text("TEX_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Municipality (`COMMUNE_id`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Commune {
+    /// Record identifier of the object
+    pub id: String,
+    /// INSEE code of the municipality (`IDU`), without the department
+    pub idu: String,
+    /// Name of the municipality (`TEX2`)
+    pub tex2: Option<String>,
+    /// Polygon of the municipality
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for Commune {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            idu: feature.text("IDU_id").unwrap_or_default(),
+            tex2: feature.text("TEX2_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Section subdivision (`SUBDSECT_id`), the cadastral sheet.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Subdivision {
+    /// Record identifier of the object
+    pub id: String,
+    /// Sheet identifier (`IDU`), e.g. `6430000A03`
+    pub idu: String,
+    /// Quality of the plan (`QUPL`)
+    pub qupl: Option<String>,
+    /// Mode of manufacture of the plan (`COPL`)
+    pub copl: Option<String>,
+    /// Denominator of the original scale (`EOR`), e.g. `2000`
+    pub eor: Option<u32>,
+    /// Date of the plan (`DEDI`)
+    pub dedi: Option<NaiveDate>,
+    /// Orientation angle of the sheet in grads (`ICL`)
+    pub icl: Option<f64>,
+    /// Date of the digitisation (`DIS`)
+    pub dis: Option<NaiveDate>,
+    /// Date of the last update of the plan (`DRED`)
+    pub dred: Option<NaiveDate>,
+    /// Digitisation process (`INP`)
+    pub inp: Option<String>,
+    /// Polygon of the sheet
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for Subdivision {
+    fn from(feature: &Feature) -> Self {
+        let values = Values(feature);
+        Self {
+            id: feature.id.clone(),
+            idu: feature.text("IDU_id").unwrap_or_default(),
+            qupl: feature.text("QUPL_id"),
+            copl: feature.text("COPL_id"),
+            eor: values.integer("EOR_id"),
+            dedi: values.date("DEDI_id"),
+            icl: feature.float("ICL_id"),
+            dis: values.date("DIS_id"),
+            dred: values.date("DRED_id"),
+            inp: feature.text("INP_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Tax subdivision of a parcel (`SUBDFISC_id`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubdivisionFiscale {
+    /// Record identifier of the object
+    pub id: String,
+    /// Letter of the subdivision (`TEX`)
+    pub tex: Option<String>,
+    /// Polygon of the subdivision
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for SubdivisionFiscale {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            tex: feature.text("TEX_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Place name of a catalogue object, written in up to ten words.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Toponyme {
+    /// Record identifier of the object
+    pub id: String,
+    /// Words of the name, from `TEX` to `TEX10`
+    pub tex: Vec<String>,
+    /// Geometry of the named object
+    pub geometry: Option<Geometry>,
+}
+
+impl Toponyme {
+    /// Returns the name, its words separated by spaces.
+    pub fn name(&self) -> String {
+        self.tex.join(" ")
+    }
+}
+
+impl From<&Feature> for Toponyme {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            tex: Values(feature).words(),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Place name (`LIEUDIT_id`).
+pub type LieuDit = Toponyme;
+
+/// River section (`TRONFLUV_id`).
+pub type TronconFluvial = Toponyme;
+
+/// Communication route, drawn as its name line (`ZONCOMMUNI_id`).
+pub type ZoneCommunication = Toponyme;
+
+/// Road section (`TRONROUTE_id`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TronconRoute {
+    /// Record identifier of the object
+    pub id: String,
+    /// Road category (`RCAD`)
+    pub rcad: Option<String>,
+    /// Words of the name, from `TEX` to `TEX10`
+    pub tex: Vec<String>,
+    /// Polygon of the road section
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for TronconRoute {
+    fn from(feature: &Feature) -> Self {
+        let values = Values(feature);
+        Self {
+            id: feature.id.clone(),
+            rcad: feature.text("RCAD_id"),
+            tex: values.words(),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Object holding a single text (`TEX`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Texte {
+    /// Record identifier of the object
+    pub id: String,
+    /// Text of the object (`TEX`)
+    pub tex: Option<String>,
+    /// Geometry of the object
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for Texte {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            tex: feature.text("TEX_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Street number (`NUMVOIE_id`).
+pub type Numvoie = Texte;
+
+/// Main street name anchor (`VOIEP_id`).
+pub type VoieP = Texte;
+
+/// Charge, e.g. an easement area (`CHARGE_id`).
+pub type Charge = Texte;
+
+/// Boundary marker (`BORNE_id`) or cross (`CROIX_id`), a bare point.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    /// Record identifier of the object
+    pub id: String,
+    /// Position of the object
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for Point {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Boundary marker (`BORNE_id`).
+pub type Borne = Point;
+
+/// Cross (`CROIX_id`).
+pub type Croix = Point;
+
+/// Symbol drawn on the plan, e.g. a wall or a limit sign.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Symbole {
+    /// Record identifier of the object
+    pub id: String,
+    /// Symbol code (`SYM`)
+    pub sym: Option<String>,
+    /// Orientation of the symbol in grads (`ORI`)
+    pub ori: Option<f64>,
+    /// Text written with the symbol (`TEX`)
+    pub tex: Option<String>,
+    /// Geometry of the symbol
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for Symbole {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            sym: feature.text("SYM_id"),
+            ori: feature.float("ORI_id"),
+            tex: feature.text("TEX_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// Surface symbol (`TSURF_id`).
+pub type Tsurf = Symbole;
+
+/// Line symbol (`TLINE_id`).
+pub type Tline = Symbole;
+
+/// Point symbol (`TPOINT_id`).
+pub type Tpoint = Symbole;
+
+/// Limit symbol (`SYMBLIM_id`).
+pub type SymboleLimite = Symbole;
+
+/// Bolt (`BOULON_id`).
+pub type Boulon = Symbole;
+
+/// Survey network point (`PTCANV_id`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointCanevas {
+    /// Record identifier of the object
+    pub id: String,
+    /// Point identifier (`IDU`)
+    pub idu: Option<String>,
+    /// Origin of the network (`CAN`)
+    pub can: Option<String>,
+    /// Materialisation of the point (`MAP`)
+    pub map: Option<String>,
+    /// Point used for altimetry (`PALT`)
+    pub palt: Option<String>,
+    /// Point used for planimetry (`PPLN`)
+    pub ppln: Option<String>,
+    /// Symbol code (`SYM`)
+    pub sym: Option<String>,
+    /// Orientation of the symbol in grads (`ORI`)
+    pub ori: Option<f64>,
+    /// Position of the point
+    pub geometry: Option<Geometry>,
+}
+
+impl From<&Feature> for PointCanevas {
+    fn from(feature: &Feature) -> Self {
+        Self {
+            id: feature.id.clone(),
+            idu: feature.text("IDU_id"),
+            can: feature.text("CAN_id"),
+            map: feature.text("MAP_id"),
+            palt: feature.text("PALT_id"),
+            ppln: feature.text("PPLN_id"),
+            sym: feature.text("SYM_id"),
+            ori: feature.float("ORI_id"),
+            geometry: feature.geometry.clone(),
+        }
+    }
+}
+
+/// The objects of a lot sorted by PCI object type.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cadastre {
+    /// `PARCELLE_id` objects
+    pub parcelles: Vec<Parcelle>,
+    /// `BATIMENT_id` objects
+    pub batiments: Vec<Batiment>,
+    /// `SECTION_id` objects
+    pub sections: Vec<Section>,
+    /// `COMMUNE_id` objects
+    pub communes: Vec<Commune>,
+    /// `SUBDSECT_id` objects
+    pub subdivisions: Vec<Subdivision>,
+    /// `SUBDFISC_id` objects
+    pub subdivisions_fiscales: Vec<SubdivisionFiscale>,
+    /// `LIEUDIT_id` objects
+    pub lieux_dits: Vec<LieuDit>,
+    /// `TRONFLUV_id` objects
+    pub troncons_fluviaux: Vec<TronconFluvial>,
+    /// `TRONROUTE_id` objects
+    pub troncons_routes: Vec<TronconRoute>,
+    /// `ZONCOMMUNI_id` objects
+    pub zones_communication: Vec<ZoneCommunication>,
+    /// `NUMVOIE_id` objects
+    pub numvoies: Vec<Numvoie>,
+    /// `VOIEP_id` objects
+    pub voies_principales: Vec<VoieP>,
+    /// `CHARGE_id` objects
+    pub charges: Vec<Charge>,
+    /// `BORNE_id` objects
+    pub bornes: Vec<Borne>,
+    /// `CROIX_id` objects
+    pub croix: Vec<Croix>,
+    /// `TSURF_id` objects
+    pub tsurfs: Vec<Tsurf>,
+    /// `TLINE_id` objects
+    pub tlines: Vec<Tline>,
+    /// `TPOINT_id` objects
+    pub tpoints: Vec<Tpoint>,
+    /// `SYMBLIM_id` objects
+    pub symboles_limites: Vec<SymboleLimite>,
+    /// `BOULON_id` objects
+    pub boulons: Vec<Boulon>,
+    /// `PTCANV_id` objects
+    pub points_canevas: Vec<PointCanevas>,
+    /// Objects of other types, such as the write-attribute objects of labels
+    pub others: Vec<Feature>,
+}
+
+impl Cadastre {
+    /// Sorts the assembled features of a lot by object type.
+    pub fn from_lot(lot: &Lot) -> Self {
+        Self::from_features(lot.features())
+    }
+
+    /// Sorts features by object type.
+    pub fn from_features(features: impl IntoIterator<Item = Feature>) -> Self {
+        let mut cadastre = Self::default();
+        for feature in features {
+            let f = &feature;
+            match feature.object_type.as_str() {
+                "PARCELLE_id" => cadastre.parcelles.push(f.into()),
+                "BATIMENT_id" => cadastre.batiments.push(f.into()),
+                "SECTION_id" => cadastre.sections.push(f.into()),
+                "COMMUNE_id" => cadastre.communes.push(f.into()),
+                "SUBDSECT_id" => cadastre.subdivisions.push(f.into()),
+                "SUBDFISC_id" => cadastre.subdivisions_fiscales.push(f.into()),
+                "LIEUDIT_id" => cadastre.lieux_dits.push(f.into()),
+                "TRONFLUV_id" => cadastre.troncons_fluviaux.push(f.into()),
+                "TRONROUTE_id" => cadastre.troncons_routes.push(f.into()),
+                "ZONCOMMUNI_id" => cadastre.zones_communication.push(f.into()),
+                "NUMVOIE_id" => cadastre.numvoies.push(f.into()),
+                "VOIEP_id" => cadastre.voies_principales.push(f.into()),
+                "CHARGE_id" => cadastre.charges.push(f.into()),
+                "BORNE_id" => cadastre.bornes.push(f.into()),
+                "CROIX_id" => cadastre.croix.push(f.into()),
+                "TSURF_id" => cadastre.tsurfs.push(f.into()),
+                "TLINE_id" => cadastre.tlines.push(f.into()),
+                "TPOINT_id" => cadastre.tpoints.push(f.into()),
+                "SYMBLIM_id" => cadastre.symboles_limites.push(f.into()),
+                "BOULON_id" => cadastre.boulons.push(f.into()),
+                "PTCANV_id" => cadastre.points_canevas.push(f.into()),
+                _ => cadastre.others.push(feature),
+            }
+        }
+        cadastre
+    }
+}
+
+impl Lot {
+    /// Sorts the features of the lot into a typed [`Cadastre`].
+    pub fn cadastre(&self) -> Cadastre {
+        Cadastre::from_lot(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{feature, text};
+    use crate::FormatResult;
+
+    #[test]
+    fn test_from_features() {
+        let cadastre = Cadastre::from_features([
+            feature(
+                "Objet_1",
+                "PARCELLE_id",
+                &[
+                    ("IDU_id", text("0240000A0033")),
+                    ("SUPF_id", FormatResult::Float(37054.0)),
+                    ("INDP_id", text("01")),
+                    ("TEX_id", text("33")),
+                ],
+                None,
+            ),
+            feature(
+                "Objet_1",
+                "SUBDSECT_id",
+                &[("EOR_id", text("2000")), ("DEDI_id", text("01/01/1869"))],
+                None,
+            ),
+            feature(
+                "Objet_1",
+                "LIEUDIT_id",
+                &[("TEX2_id", text("Grand")), ("TEX_id", text("Le"))],
+                None,
+            ),
+            feature("Objet_1", "ID_S_OBJ_Z_1_2_2", &[], None),
+        ]);
+
+        let parcelle = &cadastre.parcelles[0];
+        assert_eq!(parcelle.idu, "0240000A0033");
+        assert_eq!(parcelle.supf, Some(37054.0));
+        assert_eq!(parcelle.indp.as_deref(), Some("01"));
+        assert_eq!(parcelle.coar, None);
+        assert_eq!(parcelle.tex.as_deref(), Some("33"));
+
+        let subdivision = &cadastre.subdivisions[0];
+        assert_eq!(subdivision.eor, Some(2000));
+        assert_eq!(subdivision.dedi, NaiveDate::from_ymd_opt(1869, 1, 1));
+        assert_eq!(cadastre.lieux_dits[0].name(), "Le Grand");
+        assert_eq!(cadastre.others.len(), 1);
+    }
+}
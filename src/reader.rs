@@ -1,36 +1,80 @@
 //! Contains all logic for processing [`EDIGéO`] files from directories, such as [`EdigeoDir`].
+use crate::archive::{collect_bundles, for_each_bundle, read_bundles};
 use crate::bundle::EdigeoBundle;
-use bzip2::read::BzDecoder;
+use crate::diagnostics::{Diagnostic, DiagnosticKind, DiagnosticSink, Severity};
+use crate::error::{EdigeoError, EdigeoResult};
 use encoding_rs::WINDOWS_1252;
 use std::{
     borrow::Cow,
     fs::File,
-    io::Read,
+    io::{BufReader, Read},
     path::{Path, PathBuf},
 };
-use tar::Archive;
 
 /// Raw `Bytes` are encoded in `Latin1 (WINDOWS_1252)` and are decoded to
-/// `UTF-8` strings
+/// `UTF-8` strings, replacing the bytes that cannot be decoded.
 pub fn decode_file(data: &[u8]) -> Cow<'_, str> {
-    let (cow, _encoding_used, had_errors) = WINDOWS_1252.decode(data);
-    if had_errors {
-        eprintln!("Warning: Encoding errors occurred");
+    WINDOWS_1252.decode(data).0
+}
+
+/// Returns `true` when raw `Bytes` are not plain `WINDOWS_1252`: they hold
+/// bytes left undefined by it (`0x81`, `0x8D`, `0x8F`, `0x90`, `0x9D`), or
+/// they are valid `UTF-8` with non-ASCII characters, as in a file re-saved as
+/// `UTF-8`, which decodes to mojibake such as `Ã©`.
+pub fn is_misencoded(data: &[u8]) -> bool {
+    const UNDEFINED: [u8; 5] = [0x81, 0x8D, 0x8F, 0x90, 0x9D];
+    data.iter().any(|byte| UNDEFINED.contains(byte))
+        || (!data.is_ascii() && std::str::from_utf8(data).is_ok())
+}
+
+/// Reports a [`DiagnosticKind::Encoding`] warning for the given file when
+/// its raw `Bytes` are misencoded, see [`is_misencoded`].
+pub(crate) fn check_encoding(data: &[u8], file: &str, diagnostics: &mut dyn DiagnosticSink) {
+    if is_misencoded(data) {
+        diagnostics.report(Diagnostic::new(
+            Severity::Warning,
+            file,
+            None,
+            DiagnosticKind::Encoding,
+        ));
     }
-    cow
+}
+
+/// Decodes raw `Bytes` like [`decode_file`], reporting a
+/// [`DiagnosticKind::Encoding`] warning for the given file when they are
+/// misencoded, see [`is_misencoded`].
+pub fn decode_file_with<'a>(
+    data: &'a [u8],
+    file: &str,
+    diagnostics: &mut dyn DiagnosticSink,
+) -> Cow<'a, str> {
+    check_encoding(data, file, diagnostics);
+    decode_file(data)
 }
 
 /// The [`ExchangeReader`] Trait used for reading the [`EdigeoBundle`] from various sources
 /// namely:
 /// - `.thf` file
-/// - `.tar.bz2` compressed file
+/// - `.tar.bz2`, `.tar.gz`, `.tar` or `.zip` archive, possibly nested
 /// - `directory` where .thf file is located
 pub trait ExchangeReader {
     /// Reads each of the mandatory files and builds a [`EdigeoBundle`] struct.
-    fn read_bundle(&self) -> EdigeoBundle;
+    ///
+    /// Returns [`EdigeoError::IncompleteBundle`] when a mandatory file is
+    /// missing, or the error met while reading the source.
+    fn try_read_bundle(&self) -> EdigeoResult<EdigeoBundle>;
+
+    /// Reads the bundle like [`ExchangeReader::try_read_bundle`].
+    ///
+    /// # Panics
+    /// Panics if the bundle cannot be read.
+    fn read_bundle(&self) -> EdigeoBundle {
+        self.try_read_bundle().unwrap()
+    }
 }
 
-/// Tar file `.tar.bz2` is the most common exchange format for Edigeo
+/// Archive file: `.tar.bz2` is the most common exchange format for Edigeo,
+/// `.tar.gz`, `.tar` and `.zip` are detected from their magic bytes
 struct TarReader {
     path: PathBuf,
 }
@@ -72,84 +116,86 @@ impl THFReader {
     }
 }
 
+impl TarReader {
+    /// Returns the file name of the archive, which prefixes the bundle names.
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Reads every complete lot of the archive, reporting the incomplete
+    /// ones to the sink.
+    fn read_bundles(
+        &self,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> EdigeoResult<Vec<EdigeoBundle>> {
+        let file = BufReader::new(File::open(&self.path)?);
+        collect_bundles(&self.name(), file, diagnostics)
+    }
+}
+
 impl ExchangeReader for TarReader {
-    fn read_bundle(&self) -> EdigeoBundle {
-        let file = std::fs::File::open(&self.path).unwrap();
-        let bz2_decoder = BzDecoder::new(file);
-        let mut archive = Archive::new(bz2_decoder);
-        let mut bundle = EdigeoBundle::default();
+    fn try_read_bundle(&self) -> EdigeoResult<EdigeoBundle> {
+        single_bundle(self.read_bundles(&mut ())?)
+    }
+}
 
-        for entry in archive.entries().unwrap() {
-            let mut entry = entry.unwrap();
-            let path = entry.path().unwrap();
-            let path_str = path.to_string_lossy();
-
-            let target = match path_str {
-                p if p.ends_with(".THF") => &mut bundle.thf,
-                p if p.ends_with(".GEO") => &mut bundle.geo,
-                p if p.ends_with("T1.VEC") => &mut bundle.t1,
-                p if p.ends_with("T2.VEC") => &mut bundle.t2,
-                p if p.ends_with("T3.VEC") => &mut bundle.t3,
-                p if p.ends_with("S1.VEC") => &mut bundle.s1,
-                p if p.ends_with(".QAL") => &mut bundle.qal,
-                p if p.ends_with(".DIC") => &mut bundle.dic.get_or_insert(Vec::new()),
-                p if p.ends_with(".GEN") => &mut bundle.gen.get_or_insert(Vec::new()),
-                p if p.ends_with(".SCD") => &mut bundle.scd.get_or_insert(Vec::new()),
-                _ => continue,
-            };
-
-            entry.read_to_end(target).unwrap();
-        }
+/// Returns the only bundle of an archive.
+fn single_bundle(bundles: Vec<EdigeoBundle>) -> EdigeoResult<EdigeoBundle> {
+    let count = bundles.len();
+    match <[EdigeoBundle; 1]>::try_from(bundles) {
+        Ok([bundle]) => Ok(bundle),
+        Err(_) if count == 0 => Err(EdigeoError::IncompleteBundle),
+        Err(_) => Err(EdigeoError::InvalidArchive(format!(
+            "{count} lots found where one was expected"
+        ))),
+    }
+}
 
-        if !bundle.is_completed() {
-            panic!("All necesssary EIDGéO files not present.");
-        }
-        bundle
+impl EdigeoBundle {
+    /// Reads a bundle from an archive stream, e.g. the body of an upload,
+    /// without writing it to disk. See [`EdigeoBundle::from_bytes`].
+    pub fn from_reader<R: Read>(reader: R) -> EdigeoResult<Self> {
+        single_bundle(collect_bundles("", reader, &mut ())?)
+    }
+
+    /// Reads a bundle from the bytes of an archive held in memory.
+    ///
+    /// `.tar.bz2`, `.tar.gz`, `.tar` and `.zip` archives are detected from
+    /// their magic bytes and nested archives are unpacked. Fails when the
+    /// archive does not hold exactly one complete lot, see
+    /// [`read_bundles`] for archives holding several.
+    pub fn from_bytes(data: &[u8]) -> EdigeoResult<Self> {
+        single_bundle(read_bundles("", data.to_vec())?)
     }
 }
 
 impl ExchangeReader for DirReader {
-    fn read_bundle(&self) -> EdigeoBundle {
-        if self.path.is_file() {
-            panic!("Expected Dir Path");
-        }
-
+    fn try_read_bundle(&self) -> EdigeoResult<EdigeoBundle> {
         let mut bundle = EdigeoBundle::default();
 
-        for entry in self.path.read_dir().unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
-            let path_str = &path.to_string_lossy();
-            let mut file = File::open(&path).unwrap();
-
-            let target = match path_str {
-                p if p.ends_with(".THF") => &mut bundle.thf,
-                p if p.ends_with(".GEO") => &mut bundle.geo,
-                p if p.ends_with("T1.VEC") => &mut bundle.t1,
-                p if p.ends_with("T2.VEC") => &mut bundle.t2,
-                p if p.ends_with("T3.VEC") => &mut bundle.t3,
-                p if p.ends_with("S1.VEC") => &mut bundle.s1,
-                p if p.ends_with(".QAL") => &mut bundle.qal,
-                p if p.ends_with(".DIC") => &mut bundle.dic.get_or_insert(Vec::new()),
-                p if p.ends_with(".GEN") => &mut bundle.gen.get_or_insert(Vec::new()),
-                p if p.ends_with(".SCD") => &mut bundle.scd.get_or_insert(Vec::new()),
-                _ => continue,
-            };
-            file.read_to_end(target).unwrap();
+        for entry in self.path.read_dir()? {
+            let path = entry?.path();
+            if path.is_file() {
+                let data = std::fs::read(&path)?;
+                bundle.insert(&path.to_string_lossy(), data);
+            }
         }
 
-        if !bundle.is_completed() {
-            panic!("All necesssary EIDGéO files not present.");
-        }
-        bundle
+        bundle.name = Some(self.path.to_string_lossy().into_owned());
+        bundle.completed()
     }
 }
 
 impl ExchangeReader for THFReader {
-    fn read_bundle(&self) -> EdigeoBundle {
-        let dir = self.path.parent().unwrap();
-        let dir_reader = DirReader::new(dir);
-        dir_reader.read_bundle()
+    fn try_read_bundle(&self) -> EdigeoResult<EdigeoBundle> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        DirReader::new(dir).try_read_bundle()
     }
 }
 
@@ -189,25 +235,92 @@ impl EdigeoReader {
         let reader = match path.is_dir() {
             true => Reader::Dir(DirReader::new(path)),
             false => match path.extension().and_then(|ext| ext.to_str()) {
-                Some("bz2") => Reader::Tar(TarReader::new(path)),
-                Some("THF") => Reader::File(THFReader::new(path)),
-                None | Some(_) => panic!("Invalid file format!"),
+                Some(ext) if ext.eq_ignore_ascii_case("THF") => Reader::File(THFReader::new(path)),
+                _ => Reader::Tar(TarReader::new(path)),
             },
         };
 
         Self { reader }
     }
 
-    /// Consumes the [`EdigeoReader`] and returns the [`EdigeoBundle`]
+    /// Reads the [`EdigeoBundle`] of the source.
+    ///
+    /// Fails with [`EdigeoError::IncompleteBundle`] when a mandatory file is
+    /// missing, with [`EdigeoError::InvalidArchive`] when an archive is
+    /// corrupt or holds several lots, see [`EdigeoReader::read_bundles`], or
+    /// with the IO error met while reading the source.
+    pub fn try_read_bundle(&self) -> EdigeoResult<EdigeoBundle> {
+        match self.inner() {
+            Reader::Dir(dir_reader) => dir_reader.try_read_bundle(),
+            Reader::Tar(tar_reader) => tar_reader.try_read_bundle(),
+            Reader::File(thfreader) => thfreader.try_read_bundle(),
+        }
+    }
+
+    /// Reads the [`EdigeoBundle`] of the source like
+    /// [`EdigeoReader::try_read_bundle`].
+    ///
+    /// # Panics
+    /// Panics if the bundle cannot be read.
     pub fn read_bundle(&self) -> EdigeoBundle {
-        match self.into_inner() {
-            Reader::Dir(dir_reader) => dir_reader.read_bundle(),
-            Reader::Tar(tar_reader) => tar_reader.read_bundle(),
-            Reader::File(thfreader) => thfreader.read_bundle(),
+        self.try_read_bundle().unwrap()
+    }
+
+    /// Reads every complete lot of the source: each nested archive or
+    /// directory of an archive holding a lot gives one bundle.
+    pub fn read_bundles(&self) -> EdigeoResult<Vec<EdigeoBundle>> {
+        self.read_bundles_with(&mut ())
+    }
+
+    /// Reads every complete lot of the source like
+    /// [`EdigeoReader::read_bundles`], reporting to the sink each directory
+    /// of an archive that holds EDIGéO files but misses a mandatory one.
+    ///
+    /// Only the files are read here: their bytes are decoded and parsed by
+    /// [`Lot::from_bundle_lenient`](crate::Lot::from_bundle_lenient), which
+    /// reports the problems of their content.
+    pub fn read_bundles_with(
+        &self,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> EdigeoResult<Vec<EdigeoBundle>> {
+        match self.inner() {
+            Reader::Tar(tar_reader) => tar_reader.read_bundles(diagnostics),
+            _ => Ok(vec![self.try_read_bundle()?]),
         }
     }
 
-    /// Create a reader `with_tar` to create a TAR file reader
+    /// Streams every complete lot of the source to `f`, reporting the
+    /// incomplete lots of an archive to the sink.
+    ///
+    /// The lots of an archive are read one nested archive or directory at a
+    /// time, see [`archive::for_each_bundle`](crate::archive::for_each_bundle),
+    /// with at most `max_size` bytes unpacked in total.
+    pub fn for_each_bundle(
+        &self,
+        max_size: u64,
+        diagnostics: &mut dyn DiagnosticSink,
+        mut f: impl FnMut(EdigeoBundle) -> EdigeoResult<()>,
+    ) -> EdigeoResult<()> {
+        match self.inner() {
+            Reader::Tar(tar_reader) => {
+                let file = BufReader::new(File::open(&tar_reader.path)?);
+                for_each_bundle(&tar_reader.name(), file, max_size, diagnostics, f)
+            }
+            _ => f(self.try_read_bundle()?),
+        }
+    }
+
+    /// Reads the only lot of the source like
+    /// [`EdigeoReader::try_read_bundle`], reporting the incomplete lots of an
+    /// archive to the sink like [`EdigeoReader::read_bundles_with`].
+    pub fn read_bundle_with(
+        &self,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> EdigeoResult<EdigeoBundle> {
+        single_bundle(self.read_bundles_with(diagnostics)?)
+    }
+
+    /// Create a reader `with_tar` to create an archive reader
     pub fn with_tar<P: AsRef<Path>>(path: P) -> Self {
         Self {
             reader: Reader::Tar(TarReader::new(path)),
@@ -229,7 +342,7 @@ impl EdigeoReader {
     }
 
     /// Returns the inner [`EdigeoExchange`] Reader Enum
-    fn into_inner(&self) -> &Reader {
+    fn inner(&self) -> &Reader {
         &self.reader
     }
 }
@@ -238,16 +351,50 @@ impl EdigeoReader {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_misencoded() {
+        assert!(!is_misencoded(b"TEXT:Section"));
+        assert!(!is_misencoded(b"TEXT:D\xE9partement"));
+        assert!(is_misencoded("TEXT:Département".as_bytes()));
+        assert!(is_misencoded(b"TEXT:D\x81partement"));
+
+        let mut diagnostics = crate::diagnostics::Diagnostics::default();
+        let text = decode_file_with("Département".as_bytes(), "THF", &mut diagnostics);
+        assert_eq!(text, "DÃ©partement");
+        assert!(matches!(
+            diagnostics.iter().map(|d| &d.kind).collect::<Vec<_>>()[..],
+            [DiagnosticKind::Encoding]
+        ));
+    }
+
     #[test]
     fn test_edigeo_bundel_is_complete() {
         let bundle = EdigeoBundle::default();
-        assert_eq!(false, bundle.is_completed());
+        assert!(!bundle.is_completed());
     }
 
     #[test]
     #[should_panic]
     fn test_edigeo_bundel_is_complete_incorrect() {
         let bundle = EdigeoBundle::default();
-        assert_eq!(true, bundle.is_completed());
+        assert!(bundle.is_completed());
+    }
+
+    #[test]
+    fn test_bundle_from_files() {
+        let mut files = std::collections::HashMap::new();
+        for name in ["E.THF", "E.GEO", "E.QAL", "ET1.VEC", "ET2.VEC", "ET3.VEC"] {
+            files.insert(name.to_string(), b"BOMT 05:E.THF".to_vec());
+        }
+        assert!(matches!(
+            EdigeoBundle::from_files(files.clone()),
+            Err(crate::error::EdigeoError::IncompleteBundle)
+        ));
+
+        files.insert("ES1.VEC".to_string(), b"RTYSA03:PNO".to_vec());
+        files.insert("README".to_string(), Vec::new());
+        let bundle = EdigeoBundle::from_files(files).unwrap();
+        assert_eq!(bundle.s1, b"RTYSA03:PNO");
+        assert_eq!(bundle.dic, None);
     }
 }
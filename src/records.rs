@@ -0,0 +1,673 @@
+//! Contains the typed EDIGéO file models built from parsed [`Block`]s.
+use crate::{Block, BlockParse, Code, Coord, FormatResult, Line, ZoneName};
+use chrono::NaiveDate;
+use std::fmt;
+
+/// A reference to another descriptor, e.g. `ED0A01;SeSD;OBJ;PARCELLE_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reference {
+    /// Name of the lot holding the referenced descriptor
+    pub lot: String,
+    /// Identifier of the subset holding the referenced descriptor
+    pub subset: String,
+    /// Descriptor type (`RTY`) of the referenced descriptor
+    pub kind: String,
+    /// Record identifier (`RID`) of the referenced descriptor
+    pub id: String,
+}
+
+impl Reference {
+    /// Builds a [`Reference`] from a parsed descriptor value.
+    pub fn from_format(value: &FormatResult) -> Option<Self> {
+        match value {
+            FormatResult::Descriptor(parts) => match parts.as_slice() {
+                [FormatResult::Text(lot), FormatResult::Text(subset), FormatResult::Text(kind), FormatResult::Text(id)] => {
+                    Some(Self {
+                        lot: lot.clone(),
+                        subset: subset.clone(),
+                        kind: kind.clone(),
+                        id: id.clone(),
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn from_line(line: &Line) -> Option<Self> {
+        line.parsed_value.as_ref().and_then(Self::from_format)
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{};{};{};{}", self.lot, self.subset, self.kind, self.id)
+    }
+}
+
+/// An attribute value (`ATP`/`ATV` pair) of a vector descriptor.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attribute {
+    /// Identifier of the attribute type in the SCD, e.g. `IDU_id`
+    pub id: String,
+    /// Parsed value, `None` when the value is empty
+    pub value: Option<FormatResult>,
+    /// Value as written in the `ATV` zone, e.g. `+37054.`
+    pub raw: String,
+}
+
+/// A coded value (`AVL`/`AVD` pair) of a dictionary attribute.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodedValue {
+    /// The code stored in the data, e.g. `01`
+    pub code: String,
+    /// Its human readable description
+    pub description: String,
+}
+
+impl Block {
+    fn reference(&self, zone: ZoneName) -> Option<Reference> {
+        self.get(zone).and_then(Reference::from_line)
+    }
+
+    fn references(&self, zone: ZoneName) -> Vec<Reference> {
+        self.all(zone).filter_map(Reference::from_line).collect()
+    }
+
+    fn coord(&self, zone: ZoneName) -> Option<Coord> {
+        self.value(zone).and_then(Coord::from_format)
+    }
+
+    fn date(&self, zone: ZoneName) -> Option<NaiveDate> {
+        match self.value(zone)? {
+            FormatResult::Date(date) => Some(*date),
+            _ => None,
+        }
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        let mut attributes: Vec<Attribute> = Vec::new();
+        for line in self.entries() {
+            match &line.header.code {
+                Code::ZoneCode(ZoneName::ATP) => {
+                    if let Some(reference) = Reference::from_line(line) {
+                        attributes.push(Attribute {
+                            id: reference.id,
+                            value: None,
+                            raw: String::new(),
+                        });
+                    }
+                }
+                Code::ZoneCode(ZoneName::ATV) => {
+                    if let Some(attribute) = attributes.last_mut() {
+                        attribute.value = line.parsed_value.clone();
+                        attribute.raw = line.raw_value.clone();
+                    }
+                }
+                _ => {}
+            }
+        }
+        attributes
+    }
+}
+
+/// A `PNO` node descriptor.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    /// Record identifier
+    pub id: String,
+    /// Reference to the primitive type in the SCD
+    pub definition: Option<Reference>,
+    /// Node type (`TYP`)
+    pub node_type: Option<i32>,
+    /// Position of the node
+    pub coord: Option<Coord>,
+    /// Attribute values
+    pub attributes: Vec<Attribute>,
+    /// Reference to the quality descriptor
+    pub quality: Option<Reference>,
+}
+
+/// A `PAR` arc descriptor.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arc {
+    /// Record identifier
+    pub id: String,
+    /// Reference to the primitive type in the SCD
+    pub definition: Option<Reference>,
+    /// Arc type (`TYP`)
+    pub arc_type: Option<i32>,
+    /// Vertices of the arc (`COR`)
+    pub points: Vec<Coord>,
+    /// Attribute values
+    pub attributes: Vec<Attribute>,
+    /// Reference to the quality descriptor
+    pub quality: Option<Reference>,
+}
+
+/// A `PFE` face descriptor.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Face {
+    /// Record identifier
+    pub id: String,
+    /// Reference to the primitive type in the SCD
+    pub definition: Option<Reference>,
+    /// Attribute values
+    pub attributes: Vec<Attribute>,
+    /// Reference to the quality descriptor
+    pub quality: Option<Reference>,
+}
+
+/// A `FEA` object descriptor.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Object {
+    /// Record identifier
+    pub id: String,
+    /// Reference to the object type in the SCD, e.g. `PARCELLE_id`
+    pub definition: Option<Reference>,
+    /// Number of attributes declared by the descriptor (`ATC`)
+    pub attribute_count: Option<i32>,
+    /// Attribute values
+    pub attributes: Vec<Attribute>,
+    /// Reference to the quality descriptor
+    pub quality: Option<Reference>,
+}
+
+/// A `LNK` relation descriptor.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Link {
+    /// Record identifier
+    pub id: String,
+    /// Reference to the relation type in the SCD, e.g. `ID_S_RCO_FAC_GCHE`
+    pub definition: Option<Reference>,
+    /// Descriptors taking part in the relation (`FTP`)
+    pub members: Vec<Reference>,
+    /// Direction of travel of an arc in a composition (`SNS`)
+    pub direction: Option<String>,
+    /// Attribute values
+    pub attributes: Vec<Attribute>,
+}
+
+/// A `.VEC` vector data file holding one subset of a lot.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VECFile {
+    /// `PNO` node descriptors
+    pub nodes: Vec<Node>,
+    /// `PAR` arc descriptors
+    pub arcs: Vec<Arc>,
+    /// `PFE` face descriptors
+    pub faces: Vec<Face>,
+    /// `FEA` object descriptors
+    pub objects: Vec<Object>,
+    /// `LNK` relation descriptors
+    pub links: Vec<Link>,
+}
+
+impl BlockParse for VECFile {
+    fn from_blocks(blocks: Vec<Block>) -> Self {
+        let mut vec = Self::default();
+
+        for block in blocks {
+            match block.id() {
+                "PNO" => vec.nodes.push(Node {
+                    id: block.rid(),
+                    definition: block.reference(ZoneName::SCP),
+                    node_type: block.int(ZoneName::TYP),
+                    coord: block.coord(ZoneName::COR),
+                    attributes: block.attributes(),
+                    quality: block.reference(ZoneName::QAP),
+                }),
+                "PAR" => vec.arcs.push(Arc {
+                    id: block.rid(),
+                    definition: block.reference(ZoneName::SCP),
+                    arc_type: block.int(ZoneName::TYP),
+                    points: block
+                        .all(ZoneName::COR)
+                        .filter_map(|line| line.parsed_value.as_ref())
+                        .filter_map(Coord::from_format)
+                        .collect(),
+                    attributes: block.attributes(),
+                    quality: block.reference(ZoneName::QAP),
+                }),
+                "PFE" => vec.faces.push(Face {
+                    id: block.rid(),
+                    definition: block.reference(ZoneName::SCP),
+                    attributes: block.attributes(),
+                    quality: block.reference(ZoneName::QAP),
+                }),
+                "FEA" => vec.objects.push(Object {
+                    id: block.rid(),
+                    definition: block.reference(ZoneName::SCP),
+                    attribute_count: block.int(ZoneName::ATC),
+                    attributes: block.attributes(),
+                    quality: block.reference(ZoneName::QAP),
+                }),
+                "LNK" => vec.links.push(Link {
+                    id: block.rid(),
+                    definition: block.reference(ZoneName::SCP),
+                    members: block.references(ZoneName::FTP),
+                    direction: block.text(ZoneName::SNS),
+                    attributes: block.attributes(),
+                }),
+                _ => {}
+            }
+        }
+        vec
+    }
+}
+
+/// An `OBJ` object type of the conceptual data model.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectType {
+    /// Record identifier, e.g. `PARCELLE_id`
+    pub id: String,
+    /// Reference to the dictionary definition (`DIP`)
+    pub definition: Option<Reference>,
+    /// Geometry kind (`KND`): `ARE`, `LIN`, `PCT` or `CPX`
+    pub kind: String,
+    /// Attribute types allowed on the object (`AAP`)
+    pub attributes: Vec<Reference>,
+}
+
+/// An `ATT` attribute type of the conceptual data model.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttributeType {
+    /// Record identifier, e.g. `IDU_id`
+    pub id: String,
+    /// Reference to the dictionary definition (`DIP`)
+    pub definition: Option<Reference>,
+    /// Maximum length of the value (`CAN`)
+    pub max_length: Option<i32>,
+    /// Number of decimals of the value (`CAD`)
+    pub decimals: Option<i32>,
+    /// Unit of the value (`UNI`)
+    pub unit: Option<String>,
+    /// Lower bound of the value (`AV1`)
+    pub min: Option<FormatResult>,
+    /// Upper bound of the value (`AV2`)
+    pub max: Option<FormatResult>,
+}
+
+/// A `PGE` primitive type of the conceptual data model.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrimitiveType {
+    /// Record identifier, e.g. `ID_S_PRI_ARC`
+    pub id: String,
+    /// Primitive kind (`KND`): `NOD`, `ARC` or `FAC`
+    pub kind: String,
+}
+
+/// An `ASS` semantic or `REL` construction relation type.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelationType {
+    /// Record identifier, e.g. `ID_S_RCO_FAC_GCHE`
+    pub id: String,
+    /// Reference to the dictionary definition (`DIP`)
+    pub definition: Option<Reference>,
+    /// Relation kind (`KND`)
+    pub kind: Option<String>,
+    /// Types taking part in the relation (`SCP`)
+    pub members: Vec<Reference>,
+}
+
+/// The `.SCD` conceptual data model file.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SCDFile {
+    /// `OBJ` object types
+    pub objects: Vec<ObjectType>,
+    /// `ATT` attribute types
+    pub attributes: Vec<AttributeType>,
+    /// `PGE` primitive types
+    pub primitives: Vec<PrimitiveType>,
+    /// `ASS` semantic relation types
+    pub associations: Vec<RelationType>,
+    /// `REL` construction relation types
+    pub relations: Vec<RelationType>,
+}
+
+impl SCDFile {
+    /// Returns the object type with the given identifier.
+    pub fn object(&self, id: &str) -> Option<&ObjectType> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+
+    /// Returns the attribute type with the given identifier.
+    pub fn attribute(&self, id: &str) -> Option<&AttributeType> {
+        self.attributes.iter().find(|attribute| attribute.id == id)
+    }
+}
+
+impl BlockParse for SCDFile {
+    fn from_blocks(blocks: Vec<Block>) -> Self {
+        let mut scd = Self::default();
+
+        for block in blocks {
+            let relation = |block: &Block| RelationType {
+                id: block.rid(),
+                definition: block.reference(ZoneName::DIP),
+                kind: block.text(ZoneName::KND),
+                members: block.references(ZoneName::SCP),
+            };
+            match block.id() {
+                "OBJ" => scd.objects.push(ObjectType {
+                    id: block.rid(),
+                    definition: block.reference(ZoneName::DIP),
+                    kind: block.text(ZoneName::KND).unwrap_or_default(),
+                    attributes: block.references(ZoneName::AAP),
+                }),
+                "ATT" => scd.attributes.push(AttributeType {
+                    id: block.rid(),
+                    definition: block.reference(ZoneName::DIP),
+                    max_length: block.int(ZoneName::CAN),
+                    decimals: block.int(ZoneName::CAD),
+                    unit: block.text(ZoneName::UNI),
+                    min: block.value(ZoneName::AV1).cloned(),
+                    max: block.value(ZoneName::AV2).cloned(),
+                }),
+                "PGE" => scd.primitives.push(PrimitiveType {
+                    id: block.rid(),
+                    kind: block.text(ZoneName::KND).unwrap_or_default(),
+                }),
+                "ASS" => scd.associations.push(relation(&block)),
+                "REL" => scd.relations.push(relation(&block)),
+                _ => {}
+            }
+        }
+        scd
+    }
+}
+
+/// A `DID`, `DIA` or `DIR` nomenclature definition.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Definition {
+    /// Record identifier, e.g. `ID_N_ATT_IDU`
+    pub id: String,
+    /// Short label (`LAB`), e.g. `IDU`
+    pub label: String,
+    /// Definition text (`DEF`)
+    pub definition: Option<String>,
+    /// Origin of the definition (`ORI`)
+    pub origin: Option<String>,
+    /// Category (`CAT`)
+    pub category: Option<String>,
+    /// Value type (`TYP`) of an attribute definition
+    pub value_type: Option<String>,
+    /// Unit (`UNI`) of an attribute definition
+    pub unit: Option<String>,
+    /// Coded values (`AVL`/`AVD`) of an attribute definition
+    pub values: Vec<CodedValue>,
+}
+
+impl From<&Block> for Definition {
+    fn from(block: &Block) -> Self {
+        let text = |line: &Line| match &line.parsed_value {
+            Some(FormatResult::Text(value)) => value.clone(),
+            _ => String::new(),
+        };
+        Self {
+            id: block.rid(),
+            label: block.text(ZoneName::LAB).unwrap_or_default(),
+            definition: block.text(ZoneName::DEF),
+            origin: block.text(ZoneName::ORI),
+            category: block.text(ZoneName::CAT),
+            value_type: block.text(ZoneName::TYP),
+            unit: block.text(ZoneName::UNI),
+            values: block
+                .all(ZoneName::AVL)
+                .map(text)
+                .zip(block.all(ZoneName::AVD).map(text))
+                .map(|(code, description)| CodedValue { code, description })
+                .collect(),
+        }
+    }
+}
+
+/// The `.DIC` nomenclature file.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DICFile {
+    /// `DID` object definitions
+    pub objects: Vec<Definition>,
+    /// `DIA` attribute definitions
+    pub attributes: Vec<Definition>,
+    /// `DIR` relation definitions
+    pub relations: Vec<Definition>,
+}
+
+impl DICFile {
+    /// Returns the definition with the given identifier, whatever its type.
+    pub fn definition(&self, id: &str) -> Option<&Definition> {
+        self.objects
+            .iter()
+            .chain(&self.attributes)
+            .chain(&self.relations)
+            .find(|definition| definition.id == id)
+    }
+}
+
+impl BlockParse for DICFile {
+    fn from_blocks(blocks: Vec<Block>) -> Self {
+        let mut dic = Self::default();
+
+        for block in blocks {
+            match block.id() {
+                "DID" => dic.objects.push(Definition::from(&block)),
+                "DIA" => dic.attributes.push(Definition::from(&block)),
+                "DIR" => dic.relations.push(Definition::from(&block)),
+                _ => {}
+            }
+        }
+        dic
+    }
+}
+
+/// The `GEO` geographic reference descriptor.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeoReference {
+    /// Record identifier
+    pub id: String,
+    /// Reference type (`RET`), e.g. `MAP`
+    pub kind: Option<String>,
+    /// Reference name (`REN`)
+    pub name: Option<String>,
+    /// Reference code (`REL`), e.g. `LAMB93`
+    pub code: Option<String>,
+    /// Number of dimensions (`DIM`)
+    pub dimension: Option<i32>,
+    /// Altitude system (`ALS`)
+    pub altitude_system: Option<i32>,
+    /// Planimetric unit (`UNH`)
+    pub unit: Option<String>,
+}
+
+/// The `.GEO` geographic reference file.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GEOFile {
+    /// The geographic reference of the lot
+    pub reference: GeoReference,
+}
+
+impl BlockParse for GEOFile {
+    fn from_blocks(blocks: Vec<Block>) -> Self {
+        let reference = blocks
+            .iter()
+            .find(|block| block.id() == "GEO")
+            .map(|block| GeoReference {
+                id: block.rid(),
+                kind: block.text(ZoneName::RET),
+                name: block.text(ZoneName::REN),
+                code: block.text(ZoneName::REL),
+                dimension: block.int(ZoneName::DIM),
+                altitude_system: block.int(ZoneName::ALS),
+                unit: block.text(ZoneName::UNH),
+            })
+            .unwrap_or_default();
+        Self { reference }
+    }
+}
+
+/// A `GSE` data subset descriptor.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubsetDescriptor {
+    /// Record identifier, e.g. `SeTOP_1`
+    pub id: String,
+    /// Free information (`INF`)
+    pub info: Option<String>,
+    /// Structure of the subset (`STR`)
+    pub structure: Option<i32>,
+    /// Region (`REG`)
+    pub region: Option<String>,
+}
+
+/// The `.GEN` general information file.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GENFile {
+    /// Extent of the lot (`DEG` `CM1`/`CM2`)
+    pub extent: Option<(Coord, Coord)>,
+    /// `GSE` subset descriptors
+    pub subsets: Vec<SubsetDescriptor>,
+}
+
+impl BlockParse for GENFile {
+    fn from_blocks(blocks: Vec<Block>) -> Self {
+        let mut gen = Self::default();
+
+        for block in blocks {
+            match block.id() {
+                "DEG" => {
+                    gen.extent = block.coord(ZoneName::CM1).zip(block.coord(ZoneName::CM2));
+                }
+                "GSE" => gen.subsets.push(SubsetDescriptor {
+                    id: block.rid(),
+                    info: block.text(ZoneName::INF),
+                    structure: block.int(ZoneName::STR),
+                    region: block.text(ZoneName::REG),
+                }),
+                _ => {}
+            }
+        }
+        gen
+    }
+}
+
+/// A `QUP` quality descriptor.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quality {
+    /// Record identifier, e.g. `Actualite_Objet_243907`
+    pub id: String,
+    /// Date of the original survey (`ODA`)
+    pub observed: Option<NaiveDate>,
+    /// Type of the last update (`UTY`)
+    pub update_type: Option<i32>,
+    /// Origin of the last update (`ULO`)
+    pub update_origin: Option<i32>,
+    /// Date of the last update (`UDA`)
+    pub updated: Option<NaiveDate>,
+    /// Expiry date (`EDA`)
+    pub expires: Option<NaiveDate>,
+    /// Descriptors the quality applies to (`COP`)
+    pub objects: Vec<Reference>,
+}
+
+/// The `.QAL` quality file.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QALFile {
+    /// `QUP` quality descriptors
+    pub qualities: Vec<Quality>,
+}
+
+impl QALFile {
+    /// Returns the quality descriptor with the given identifier.
+    pub fn quality(&self, id: &str) -> Option<&Quality> {
+        self.qualities.iter().find(|quality| quality.id == id)
+    }
+}
+
+impl BlockParse for QALFile {
+    fn from_blocks(blocks: Vec<Block>) -> Self {
+        let qualities = blocks
+            .iter()
+            .filter(|block| block.id() == "QUP")
+            .map(|block| Quality {
+                id: block.rid(),
+                observed: block.date(ZoneName::ODA),
+                update_type: block.int(ZoneName::UTY),
+                update_origin: block.int(ZoneName::ULO),
+                updated: block.date(ZoneName::UDA),
+                expires: block.date(ZoneName::EDA),
+                objects: block.references(ZoneName::COP),
+            })
+            .collect();
+        Self { qualities }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_from_format() {
+        let line = Line::parse_line("SCPCP26:ED0A01;SeSD;OBJ;COMMUNE_id").unwrap();
+        let reference = Reference::from_line(&line).unwrap();
+        assert_eq!(reference.kind, "OBJ");
+        assert_eq!(reference.id, "COMMUNE_id");
+        assert_eq!(reference.to_string(), "ED0A01;SeSD;OBJ;COMMUNE_id");
+    }
+
+    #[test]
+    fn test_vec_parse_object_attributes() {
+        let vec = VECFile::parse(
+            "RTYSA03:FEA\nRIDSA12:Objet_243907\n\nSCPCP26:ED0A01;SeSD;OBJ;COMMUNE_id\n\
+             ATCSN01:1\nATPCP22:ED0A01;SeSD;ATT;IDU_id\nTEXT 06:8859-1\nATVST03:024\n\
+             QACSN01:0\n\nEOMT 00:\n",
+        )
+        .unwrap();
+        assert_eq!(vec.objects.len(), 1);
+        let object = &vec.objects[0];
+        assert_eq!(object.id, "Objet_243907");
+        assert_eq!(object.definition.as_ref().unwrap().id, "COMMUNE_id");
+        assert_eq!(
+            object.attributes,
+            vec![Attribute {
+                id: "IDU_id".to_string(),
+                value: Some(FormatResult::Text("024".to_string())),
+                raw: "024".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_dic_parse_continued_values() {
+        let dic = DICFile::parse(
+            "RTYSA03:DIA\nRIDSA12:ID_N_ATT_CAN\n\nLABSA03:CAN\nTYPSA01:A\nAVCSN01:1\n\
+             AVLSA02:07\nAVDST05:Canev\nNEXT 02:as\n",
+        )
+        .unwrap();
+        let definition = dic.definition("ID_N_ATT_CAN").unwrap();
+        assert_eq!(definition.label, "CAN");
+        assert_eq!(definition.values[0].description, "Canevas");
+    }
+}
@@ -0,0 +1,525 @@
+//! Contains the ESRI Shapefile writer, emitting one layer per object type of a [`Lot`].
+//!
+//! Each layer is written as `.shp`, `.shx`, `.dbf`, `.cpg` and, when the `REL`
+//! code of the lot is known, `.prj` files named after the object type
+//! (`PARCELLE.shp`, `BATIMENT.shp`, ...).
+use crate::error::{EdigeoError, EdigeoResult};
+use crate::geometry::{BoundingBox, Coord, Geometry, LineString};
+use crate::lot::{layer_name, ColumnNames, Feature, Lot};
+use crate::FormatResult;
+use encoding_rs::WINDOWS_1252;
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+/// Character encoding of the `.dbf` attribute table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DbfEncoding {
+    /// `WINDOWS_1252`, the superset of `ISO-8859-1` the EDIGéO files are encoded in
+    #[default]
+    Latin1,
+    /// `UTF-8`
+    Utf8,
+}
+
+impl DbfEncoding {
+    fn encode<'a>(&self, value: &'a str) -> Cow<'a, [u8]> {
+        match self {
+            DbfEncoding::Latin1 => WINDOWS_1252.encode(value).0,
+            DbfEncoding::Utf8 => Cow::Borrowed(value.as_bytes()),
+        }
+    }
+
+    /// Content of the `.cpg` file, naming the code page the `.dbf` bytes are
+    /// encoded in: `1252` rather than `ISO-8859-1`, which decodes `œ` or `€`
+    /// as control characters.
+    fn code_page(&self) -> &'static str {
+        match self {
+            DbfEncoding::Latin1 => "1252",
+            DbfEncoding::Utf8 => "UTF-8",
+        }
+    }
+
+    /// dBase language driver id stored in the `.dbf` header.
+    fn language_driver(&self) -> u8 {
+        match self {
+            DbfEncoding::Latin1 => 0x57,
+            DbfEncoding::Utf8 => 0x00,
+        }
+    }
+}
+
+/// Shape types of the ESRI Shapefile specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShapeType {
+    Null = 0,
+    Point = 1,
+    PolyLine = 3,
+    Polygon = 5,
+    MultiPoint = 8,
+}
+
+impl ShapeType {
+    /// Picks the shape type of a layer from the SCD geometry kind (`KND`) of
+    /// its object type, or from its geometries when the kind is unknown.
+    fn of_layer(kind: Option<&str>, features: &[Feature]) -> Self {
+        let geometries = || features.iter().filter_map(|f| f.geometry.as_ref());
+        let multipoint = geometries().any(|g| matches!(g, Geometry::MultiPoint(_)));
+
+        match kind {
+            Some("ARE") => ShapeType::Polygon,
+            Some("LIN") => ShapeType::PolyLine,
+            Some("PCT") if multipoint => ShapeType::MultiPoint,
+            Some("PCT") => ShapeType::Point,
+            _ => match geometries().next() {
+                Some(Geometry::Polygon(_) | Geometry::MultiPolygon(_)) => ShapeType::Polygon,
+                Some(Geometry::LineString(_) | Geometry::MultiLineString(_)) => ShapeType::PolyLine,
+                Some(_) if multipoint => ShapeType::MultiPoint,
+                Some(_) => ShapeType::Point,
+                None => ShapeType::Null,
+            },
+        }
+    }
+}
+
+/// Widest field of a `.dbf` table, its length being stored in one byte.
+const MAX_FIELD_LENGTH: usize = 254;
+
+/// A column of the `.dbf` attribute table.
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    name: String,
+    kind: u8,
+    length: usize,
+    decimals: usize,
+    /// SCD attribute identifier, `None` for the record identifier column
+    attribute: Option<String>,
+}
+
+impl Field {
+    fn value(&self, feature: &Feature) -> String {
+        let Some(attribute) = &self.attribute else {
+            return feature.id.clone();
+        };
+        match (self.kind, feature.attribute(attribute)) {
+            (_, None) => String::new(),
+            (b'N', Some(FormatResult::Float(value))) => format!("{value:.*}", self.decimals),
+            (b'N', Some(FormatResult::Int(value))) => {
+                format!("{:.*}", self.decimals, *value as f64)
+            }
+            (b'N', Some(FormatResult::Text(value))) => value
+                .trim()
+                .parse::<f64>()
+                .map(|value| format!("{value:.*}", self.decimals))
+                .unwrap_or_default(),
+            (_, Some(value)) => value.to_string(),
+        }
+    }
+}
+
+/// Writes the features of a [`Lot`] as one ESRI Shapefile per object type.
+/// ```ignore
+///     let lot = Lot::from_bundle(&EdigeoReader::new(file).read_bundle()).unwrap();
+///     let written = ShapefileWriter::new(DbfEncoding::Utf8).write_lot(&lot, "out/")?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ShapefileWriter {
+    encoding: DbfEncoding,
+}
+
+impl ShapefileWriter {
+    /// Constructor method for creating a new [`ShapefileWriter`] writing its
+    /// attribute tables in the given encoding.
+    pub fn new(encoding: DbfEncoding) -> Self {
+        Self { encoding }
+    }
+
+    /// Writes every object type of the lot into `dir` and returns the paths of
+    /// the `.shp` files written.
+    pub fn write_lot<P: AsRef<Path>>(&self, lot: &Lot, dir: P) -> EdigeoResult<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        lot.layers()
+            .iter()
+            .map(|(object_type, features)| self.write_layer(lot, object_type, features, &dir))
+            .collect()
+    }
+
+    /// Writes the features of one object type into `dir` and returns the path
+    /// of the `.shp` file written.
+    pub fn write_layer<P: AsRef<Path>>(
+        &self,
+        lot: &Lot,
+        object_type: &str,
+        features: &[Feature],
+        dir: P,
+    ) -> EdigeoResult<PathBuf> {
+        let base = dir.as_ref().join(layer_name(object_type));
+        let kind = lot
+            .scd
+            .object(object_type)
+            .map(|object| object.kind.as_str());
+        let shape_type = ShapeType::of_layer(kind, features);
+        let fields = self.fields(lot, object_type, features)?;
+
+        let (shp, shx) = write_shapes(shape_type, features);
+        std::fs::write(base.with_extension("shp"), shp)?;
+        std::fs::write(base.with_extension("shx"), shx)?;
+        std::fs::write(
+            base.with_extension("dbf"),
+            self.write_dbf(&fields, features),
+        )?;
+        std::fs::write(base.with_extension("cpg"), self.encoding.code_page())?;
+        if let Some(projection) = lot.projection() {
+            std::fs::write(base.with_extension("prj"), projection.to_esri_wkt())?;
+        }
+        Ok(base.with_extension("shp"))
+    }
+
+    /// Builds the `.dbf` columns: the record identifier followed by the SCD
+    /// attributes of the object type, named after their dictionary labels
+    /// truncated to 10 characters.
+    ///
+    /// Numeric fields are as wide as their widest value, and fail with
+    /// [`EdigeoError::ValueTooWide`] beyond 254 characters rather than being
+    /// truncated; character fields are truncated to 254 bytes.
+    fn fields(
+        &self,
+        lot: &Lot,
+        object_type: &str,
+        features: &[Feature],
+    ) -> EdigeoResult<Vec<Field>> {
+        let mut names = ColumnNames::dbf();
+        let mut fields = vec![Field {
+            name: names.unique("ID"),
+            kind: b'C',
+            length: 1,
+            decimals: 0,
+            attribute: None,
+        }];
+        for column in lot.attribute_columns(object_type, features, &mut names) {
+            let (kind, decimals) = match column.value_type.as_deref() {
+                Some("R" | "E") => (b'N', column.decimals.unwrap_or(3) as usize),
+                Some("N" | "I") => (b'N', 0),
+                Some("D") => (b'D', 0),
+                _ => (b'C', 0),
+            };
+            fields.push(Field {
+                name: column.name,
+                kind,
+                length: 1,
+                decimals,
+                attribute: Some(column.id),
+            });
+        }
+
+        for field in &mut fields {
+            field.length = self.length(field, features)?;
+        }
+        Ok(fields)
+    }
+
+    /// Returns the width of a field holding the values of the features.
+    fn length(&self, field: &Field, features: &[Feature]) -> EdigeoResult<usize> {
+        Ok(match field.kind {
+            b'D' => 8,
+            b'N' => {
+                let widest = features
+                    .iter()
+                    .map(|f| field.value(f))
+                    .max_by_key(String::len)
+                    .unwrap_or_default();
+                if widest.len() > MAX_FIELD_LENGTH {
+                    return Err(EdigeoError::ValueTooWide {
+                        field: field.name.clone(),
+                        value: widest,
+                    });
+                }
+                widest.len().max(field.decimals + 2)
+            }
+            _ => features
+                .iter()
+                .map(|f| self.encoding.encode(&field.value(f)).len())
+                .max()
+                .unwrap_or(0)
+                .clamp(1, MAX_FIELD_LENGTH),
+        })
+    }
+
+    fn write_dbf(&self, fields: &[Field], features: &[Feature]) -> Vec<u8> {
+        let header_length = 32 + 32 * fields.len() + 1;
+        let record_length = 1 + fields.iter().map(|f| f.length).sum::<usize>();
+        let today = chrono::Local::now().date_naive();
+        let mut dbf = Vec::with_capacity(header_length + record_length * features.len() + 1);
+
+        dbf.push(0x03);
+        dbf.extend_from_slice(&[
+            (chrono::Datelike::year(&today) - 1900) as u8,
+            chrono::Datelike::month(&today) as u8,
+            chrono::Datelike::day(&today) as u8,
+        ]);
+        dbf.extend_from_slice(&(features.len() as u32).to_le_bytes());
+        dbf.extend_from_slice(&(header_length as u16).to_le_bytes());
+        dbf.extend_from_slice(&(record_length as u16).to_le_bytes());
+        dbf.extend_from_slice(&[0; 17]);
+        dbf.push(self.encoding.language_driver());
+        dbf.extend_from_slice(&[0; 2]);
+
+        for field in fields {
+            let mut name = [0u8; 11];
+            name[..field.name.len()].copy_from_slice(field.name.as_bytes());
+            dbf.extend_from_slice(&name);
+            dbf.push(field.kind);
+            dbf.extend_from_slice(&[0; 4]);
+            dbf.push(field.length as u8);
+            dbf.push(field.decimals as u8);
+            dbf.extend_from_slice(&[0; 14]);
+        }
+        dbf.push(0x0D);
+
+        for feature in features {
+            dbf.push(b' ');
+            for field in fields {
+                let value = match field.kind {
+                    b'D' => match feature.attribute(field.attribute.as_deref().unwrap_or("")) {
+                        Some(FormatResult::Date(date)) => date.format("%Y%m%d").to_string(),
+                        _ => String::new(),
+                    },
+                    _ => field.value(feature),
+                };
+                let mut bytes = self.encoding.encode(&value).into_owned();
+                if bytes.len() > field.length {
+                    bytes.truncate(field.length);
+                    // Never cut a multi-byte UTF-8 character in half.
+                    while self.encoding == DbfEncoding::Utf8 && std::str::from_utf8(&bytes).is_err()
+                    {
+                        bytes.pop();
+                    }
+                }
+                let padding = vec![b' '; field.length - bytes.len()];
+                if field.kind == b'N' {
+                    dbf.extend_from_slice(&padding);
+                    dbf.extend_from_slice(&bytes);
+                } else {
+                    dbf.extend_from_slice(&bytes);
+                    dbf.extend_from_slice(&padding);
+                }
+            }
+        }
+        dbf.push(0x1A);
+        dbf
+    }
+}
+
+/// Encodes the geometries of a layer into the content of its `.shp` and `.shx` files.
+fn write_shapes(shape_type: ShapeType, features: &[Feature]) -> (Vec<u8>, Vec<u8>) {
+    let mut records = Vec::with_capacity(features.len());
+    let mut bbox: Option<BoundingBox> = None;
+
+    for feature in features {
+        // Shapefile exterior rings are clockwise, the reverse of ours.
+        let reversed = |polygon: &Vec<LineString>| {
+            polygon
+                .iter()
+                .map(|ring| ring.iter().rev().copied().collect())
+                .collect::<Vec<LineString>>()
+        };
+        let content = match (&feature.geometry, shape_type) {
+            (Some(Geometry::Point(point)), ShapeType::Point) => Some(point_content(point)),
+            (Some(Geometry::Point(point)), ShapeType::MultiPoint) => multipoint_content(&[*point]),
+            (Some(Geometry::MultiPoint(points)), ShapeType::MultiPoint) => {
+                multipoint_content(points)
+            }
+            (Some(Geometry::LineString(line)), ShapeType::PolyLine) => {
+                parts_content(ShapeType::PolyLine, std::slice::from_ref(line))
+            }
+            (Some(Geometry::MultiLineString(lines)), ShapeType::PolyLine) => {
+                parts_content(ShapeType::PolyLine, lines)
+            }
+            (Some(Geometry::Polygon(polygon)), ShapeType::Polygon) => {
+                parts_content(ShapeType::Polygon, &reversed(polygon))
+            }
+            (Some(Geometry::MultiPolygon(polygons)), ShapeType::Polygon) => parts_content(
+                ShapeType::Polygon,
+                &polygons.iter().flat_map(reversed).collect::<Vec<_>>(),
+            ),
+            // Features without geometry, or with an empty one, get a null shape.
+            _ => None,
+        };
+
+        match content {
+            Some(content) => {
+                if let Some(geometry_bbox) = feature.geometry.as_ref().and_then(Geometry::bbox) {
+                    bbox = Some(bbox.map_or(geometry_bbox, |b| b.expand(&geometry_bbox)));
+                }
+                records.push(content);
+            }
+            None => records.push((ShapeType::Null as i32).to_le_bytes().to_vec()),
+        }
+    }
+
+    let shp_length = 100 + records.iter().map(|r| 8 + r.len()).sum::<usize>();
+    let shx_length = 100 + 8 * records.len();
+    let mut shp = file_header(shape_type, shp_length, bbox);
+    let mut shx = file_header(shape_type, shx_length, bbox);
+
+    for (i, record) in records.iter().enumerate() {
+        shx.extend_from_slice(&((shp.len() / 2) as i32).to_be_bytes());
+        shx.extend_from_slice(&((record.len() / 2) as i32).to_be_bytes());
+        shp.extend_from_slice(&(i as i32 + 1).to_be_bytes());
+        shp.extend_from_slice(&((record.len() / 2) as i32).to_be_bytes());
+        shp.extend_from_slice(record);
+    }
+    (shp, shx)
+}
+
+/// The 100 bytes header shared by `.shp` and `.shx` files.
+fn file_header(shape_type: ShapeType, length: usize, bbox: Option<BoundingBox>) -> Vec<u8> {
+    let mut header = Vec::with_capacity(length);
+    header.extend_from_slice(&9994i32.to_be_bytes());
+    header.extend_from_slice(&[0; 20]);
+    header.extend_from_slice(&((length / 2) as i32).to_be_bytes());
+    header.extend_from_slice(&1000i32.to_le_bytes());
+    header.extend_from_slice(&(shape_type as i32).to_le_bytes());
+    let (min, max) = bbox.map_or((Coord { x: 0.0, y: 0.0 }, Coord { x: 0.0, y: 0.0 }), |b| {
+        (b.min, b.max)
+    });
+    for value in [min.x, min.y, max.x, max.y, 0.0, 0.0, 0.0, 0.0] {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+    header
+}
+
+fn push_coords<'a, I: IntoIterator<Item = &'a Coord>>(content: &mut Vec<u8>, coords: I) {
+    for coord in coords {
+        content.extend_from_slice(&coord.x.to_le_bytes());
+        content.extend_from_slice(&coord.y.to_le_bytes());
+    }
+}
+
+/// Writes the bounding box of some coordinates, `None` when there are none.
+fn push_bbox<'a, I: IntoIterator<Item = &'a Coord>>(
+    content: &mut Vec<u8>,
+    coords: I,
+) -> Option<()> {
+    let bbox = BoundingBox::from_coords(coords)?;
+    push_coords(content, [&bbox.min, &bbox.max]);
+    Some(())
+}
+
+fn point_content(point: &Coord) -> Vec<u8> {
+    let mut content = (ShapeType::Point as i32).to_le_bytes().to_vec();
+    push_coords(&mut content, [point]);
+    content
+}
+
+fn multipoint_content(points: &[Coord]) -> Option<Vec<u8>> {
+    let mut content = (ShapeType::MultiPoint as i32).to_le_bytes().to_vec();
+    push_bbox(&mut content, points)?;
+    content.extend_from_slice(&(points.len() as i32).to_le_bytes());
+    push_coords(&mut content, points);
+    Some(content)
+}
+
+fn parts_content(shape_type: ShapeType, parts: &[LineString]) -> Option<Vec<u8>> {
+    let mut content = (shape_type as i32).to_le_bytes().to_vec();
+    push_bbox(&mut content, parts.iter().flatten())?;
+    let num_points = parts.iter().map(Vec::len).sum::<usize>();
+    content.extend_from_slice(&(parts.len() as i32).to_le_bytes());
+    content.extend_from_slice(&(num_points as i32).to_le_bytes());
+    let mut start = 0;
+    for part in parts {
+        content.extend_from_slice(&(start as i32).to_le_bytes());
+        start += part.len();
+    }
+    push_coords(&mut content, parts.iter().flatten());
+    Some(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{coord, feature};
+
+    fn boundary_mark(geometry: Geometry) -> Feature {
+        feature("Objet_1", "BORNE_id", &[], Some(geometry))
+    }
+
+    #[test]
+    fn test_write_point_shapes() {
+        let features = [
+            boundary_mark(Geometry::Point(coord(1.0, 2.0))),
+            boundary_mark(Geometry::Point(coord(3.0, 4.0))),
+        ];
+        let (shp, shx) = write_shapes(ShapeType::Point, &features);
+        assert_eq!(shp.len(), 100 + 2 * 28);
+        assert_eq!(shx.len(), 100 + 2 * 8);
+        assert_eq!(&shp[24..28], &((100 + 2 * 28) / 2i32).to_be_bytes());
+        assert_eq!(&shx[100..104], &50i32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_empty_geometry() {
+        let features = [
+            boundary_mark(Geometry::MultiPoint(vec![])),
+            boundary_mark(Geometry::MultiPoint(vec![coord(1.0, 2.0)])),
+        ];
+        let (shp, _) = write_shapes(ShapeType::MultiPoint, &features);
+        // A null shape is its record header followed by the shape type 0.
+        assert_eq!(&shp[104..108], &2i32.to_be_bytes());
+        assert_eq!(&shp[108..112], &0i32.to_le_bytes());
+        assert_eq!(shp.len(), 100 + 12 + 64);
+    }
+
+    #[test]
+    fn test_numeric_field_length() {
+        let writer = ShapefileWriter::default();
+        let field = Field {
+            name: "SUPF".to_string(),
+            kind: b'N',
+            length: 1,
+            decimals: 3,
+            attribute: Some("SUPF_id".to_string()),
+        };
+        let parcel = |area| {
+            feature(
+                "Objet_1",
+                "PARCELLE_id",
+                &[("SUPF_id", FormatResult::Float(area))],
+                None,
+            )
+        };
+        assert_eq!(writer.length(&field, &[]).unwrap(), 5);
+        assert_eq!(
+            writer.length(&field, &[parcel(2.5), parcel(1e20)]).unwrap(),
+            "100000000000000000000.000".len()
+        );
+        assert!(matches!(
+            writer.length(&field, &[parcel(1e300)]),
+            Err(EdigeoError::ValueTooWide { .. })
+        ));
+    }
+
+    #[test]
+    fn test_shape_type_of_layer() {
+        let points = [boundary_mark(Geometry::MultiPoint(vec![coord(1.0, 2.0)]))];
+        assert_eq!(
+            ShapeType::of_layer(Some("ARE"), &points),
+            ShapeType::Polygon
+        );
+        assert_eq!(
+            ShapeType::of_layer(Some("PCT"), &points),
+            ShapeType::MultiPoint
+        );
+        assert_eq!(ShapeType::of_layer(None, &[]), ShapeType::Null);
+    }
+
+    #[test]
+    fn test_latin1_encoding() {
+        assert_eq!(DbfEncoding::Latin1.encode("Département").len(), 11);
+        assert_eq!(DbfEncoding::Latin1.encode("Bœuf").as_ref(), b"B\x9Cuf");
+        assert_eq!(DbfEncoding::Latin1.code_page(), "1252");
+        assert_eq!(DbfEncoding::Utf8.encode("Département").len(), 12);
+    }
+}
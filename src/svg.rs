@@ -0,0 +1,495 @@
+//! Contains the SVG renderer drawing a [`Lot`] as a cadastral sheet.
+//!
+//! Features are drawn in the conventional styling of the cadastral plan:
+//! white parcels with black boundaries, hatched buildings (dense for hard
+//! construction, `DUR` `01`, sparse for light construction), dashed section
+//! and commune boundaries, blue waterways and boundary markers as small
+//! circles, topped by the text labels with their lettering. Each drawn
+//! object carries its record identifier as `id`, on a group holding the
+//! circles of a multipoint, and the optional topology layer draws every arc
+//! and node with the issues of [`Lot::check_topology`] in red, their `id`
+//! prefixed by their subset as records of different subsets share
+//! identifiers, e.g. `SeTOP_1-Noeud_1`.
+//!
+//! ```no_run
+//! use edigeo::{svg::*, *};
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle()).unwrap();
+//! let renderer = SvgRenderer {
+//!     layers: vec![SvgLayer::Parcels, SvgLayer::Buildings, SvgLayer::Topology],
+//!     ..SvgRenderer::default()
+//! };
+//! renderer.write_lot(&lot, "sheet.svg").unwrap();
+//! ```
+use crate::error::EdigeoResult;
+use crate::geometry::{BoundingBox, Coord, Geometry};
+use crate::lot::{layer_name, Feature, Lot};
+use crate::topology::TopologyIssueKind;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+
+/// A group of objects drawn together, from bottom to top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SvgLayer {
+    /// Fiscal subdivisions and lieux-dits (`SUBDFISC`, `LIEUDIT`)
+    Subdivisions,
+    /// Surfaces: roads, waterways and other `TSURF` areas
+    Surfaces,
+    /// Parcels (`PARCELLE`)
+    Parcels,
+    /// Buildings (`BATIMENT`), hatched after their `DUR` code
+    Buildings,
+    /// Boundaries of communes, sections and sheets (`COMMUNE`, `SECTION`,
+    /// `SUBDSECT`)
+    Sections,
+    /// Line objects: `TLINE` symbols and road axes (`ZONCOMMUNI`)
+    Lines,
+    /// Boundary markers (`BORNE`, `BOULON`, `CROIX`)
+    Bornes,
+    /// Point symbols and survey points (`TPOINT`, `SYMBLIM`, `PTCANV`)
+    Symbols,
+    /// Text labels written by the `ID_S_OBJ_Z_1_2_2` objects
+    Labels,
+    /// Arcs, nodes and topology issues, for debugging
+    Topology,
+}
+
+impl SvgLayer {
+    /// Layers drawn by default: every layer but [`SvgLayer::Topology`].
+    pub const DEFAULT: [SvgLayer; 9] = [
+        SvgLayer::Subdivisions,
+        SvgLayer::Surfaces,
+        SvgLayer::Parcels,
+        SvgLayer::Buildings,
+        SvgLayer::Sections,
+        SvgLayer::Lines,
+        SvgLayer::Bornes,
+        SvgLayer::Symbols,
+        SvgLayer::Labels,
+    ];
+
+    /// Layer of an object type, `None` for the types not drawn.
+    fn of(object_type: &str) -> Option<Self> {
+        match layer_name(object_type) {
+            "SUBDFISC" | "LIEUDIT" => Some(SvgLayer::Subdivisions),
+            "TSURF" | "TRONROUTE" | "TRONFLUV" => Some(SvgLayer::Surfaces),
+            "PARCELLE" => Some(SvgLayer::Parcels),
+            "BATIMENT" => Some(SvgLayer::Buildings),
+            "COMMUNE" | "SECTION" | "SUBDSECT" => Some(SvgLayer::Sections),
+            "TLINE" | "ZONCOMMUNI" => Some(SvgLayer::Lines),
+            "BORNE" | "BOULON" | "CROIX" => Some(SvgLayer::Bornes),
+            "TPOINT" | "SYMBLIM" | "PTCANV" => Some(SvgLayer::Symbols),
+            _ => None,
+        }
+    }
+}
+
+/// Presentation attributes of an object type.
+fn style(feature: &Feature) -> &'static str {
+    match layer_name(&feature.object_type) {
+        "SUBDFISC" => r##"fill="none" stroke="#9a9a9a" stroke-width="0.5" stroke-dasharray="2 2""##,
+        "LIEUDIT" => r##"fill="none" stroke="#b0b0b0" stroke-width="0.5" stroke-dasharray="1 3""##,
+        "TRONFLUV" => r##"fill="#d6e9f8" stroke="#3f8fd2" stroke-width="0.8""##,
+        "TRONROUTE" => r##"fill="#f4f0e6" stroke="#a0a0a0" stroke-width="0.5""##,
+        "TSURF" => r##"fill="#eef4e4" stroke="#7a9a5a" stroke-width="0.5""##,
+        "PARCELLE" => r##"fill="#ffffff" stroke="#000000" stroke-width="0.8""##,
+        "BATIMENT" => match feature.text("DUR_id") {
+            Some(dur) if dur == "02" => {
+                r##"fill="url(#light)" stroke="#c0392b" stroke-width="0.6""##
+            }
+            _ => r##"fill="url(#hard)" stroke="#c0392b" stroke-width="1""##,
+        },
+        "COMMUNE" => {
+            r##"fill="none" stroke="#000000" stroke-width="2.5" stroke-dasharray="12 3 2 3""##
+        }
+        "SECTION" => r##"fill="none" stroke="#000000" stroke-width="1.8" stroke-dasharray="8 3""##,
+        "SUBDSECT" => r##"fill="none" stroke="#555555" stroke-width="1" stroke-dasharray="4 3""##,
+        "ZONCOMMUNI" => {
+            r##"fill="none" stroke="#808080" stroke-width="0.6" stroke-dasharray="6 2""##
+        }
+        "TLINE" => r##"fill="none" stroke="#000000" stroke-width="0.5""##,
+        "BORNE" | "BOULON" | "CROIX" => r##"fill="#ffffff" stroke="#000000" stroke-width="0.6""##,
+        _ => r##"fill="#000000" stroke="none""##,
+    }
+}
+
+/// Hatching patterns of the buildings, spaced in meters.
+const DEFS: &str = r##"<defs><pattern id="hard" patternUnits="userSpaceOnUse" width="1" height="1" patternTransform="rotate(45)"><rect width="1" height="1" fill="#f6dcd6"/><line x1="0" y1="0" x2="0" y2="1" stroke="#c0392b" stroke-width="0.5"/></pattern><pattern id="light" patternUnits="userSpaceOnUse" width="2.5" height="2.5" patternTransform="rotate(45)"><line x1="0" y1="0" x2="0" y2="2.5" stroke="#c0392b" stroke-width="0.25"/></pattern></defs>"##;
+
+/// Escapes the XML special characters of a text or attribute value.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders a [`Lot`] as an SVG document.
+/// ```ignore
+///     let lot = Lot::from_bundle(&EdigeoReader::new(file).read_bundle()).unwrap();
+///     let svg = SvgRenderer { width: 2000.0, ..SvgRenderer::default() }.render(&lot);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SvgRenderer {
+    /// Width of the image in pixels, its height following the viewport
+    pub width: f64,
+    /// Area drawn, in the coordinates of the lot. By default the extent of
+    /// the `.GEN` file, or the bounding box of the features when the extent
+    /// is missing or does not cover them.
+    pub viewport: Option<BoundingBox>,
+    /// Layers drawn
+    pub layers: Vec<SvgLayer>,
+}
+
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        Self {
+            width: 1200.0,
+            viewport: None,
+            layers: SvgLayer::DEFAULT.to_vec(),
+        }
+    }
+}
+
+/// Converts lot coordinates into SVG user units: meters from the upper left
+/// corner of the viewport, the Y axis pointing down.
+struct Transform {
+    min_x: f64,
+    max_y: f64,
+}
+
+impl Transform {
+    fn point(&self, c: &Coord) -> (f64, f64) {
+        (c.x - self.min_x, self.max_y - c.y)
+    }
+
+    /// Path data of a line, closed with `Z` for the rings of polygons.
+    fn path(&self, line: &[Coord], closed: bool, d: &mut String) {
+        for (i, c) in line.iter().enumerate() {
+            let (x, y) = self.point(c);
+            let _ = write!(d, "{}{x:.2} {y:.2}", if i == 0 { "M" } else { "L" });
+        }
+        if closed {
+            d.push('Z');
+        }
+    }
+
+    /// Path data of a geometry, `None` for points.
+    fn geometry(&self, geometry: &Geometry) -> Option<String> {
+        let mut d = String::new();
+        match geometry {
+            Geometry::Point(_) | Geometry::MultiPoint(_) => return None,
+            Geometry::LineString(line) => self.path(line, false, &mut d),
+            Geometry::MultiLineString(lines) => {
+                lines.iter().for_each(|line| self.path(line, false, &mut d))
+            }
+            Geometry::Polygon(rings) => rings.iter().for_each(|ring| self.path(ring, true, &mut d)),
+            Geometry::MultiPolygon(polygons) => polygons
+                .iter()
+                .flatten()
+                .for_each(|ring| self.path(ring, true, &mut d)),
+        }
+        Some(d)
+    }
+}
+
+impl SvgRenderer {
+    /// Returns the area drawn for a lot, see [`SvgRenderer::viewport`].
+    pub fn viewport(&self, lot: &Lot, features: &[Feature]) -> Option<BoundingBox> {
+        if self.viewport.is_some() {
+            return self.viewport;
+        }
+        let data = features
+            .iter()
+            .filter_map(|f| f.geometry.as_ref()?.bbox())
+            .reduce(|a, b| a.expand(&b));
+        let extent = lot.gen.extent.map(|(min, max)| BoundingBox { min, max });
+        match (extent, data) {
+            (Some(extent), Some(data))
+                if extent.min.x <= data.max.x
+                    && data.min.x <= extent.max.x
+                    && extent.min.y <= data.max.y
+                    && data.min.y <= extent.max.y =>
+            {
+                Some(extent)
+            }
+            (extent, data) => data.or(extent),
+        }
+    }
+
+    /// Renders the lot as an SVG document.
+    pub fn render(&self, lot: &Lot) -> String {
+        let features = lot.features();
+        let viewport = self.viewport(lot, &features).unwrap_or(BoundingBox {
+            min: Coord { x: 0.0, y: 0.0 },
+            max: Coord { x: 1.0, y: 1.0 },
+        });
+        let (width, height) = (
+            (viewport.max.x - viewport.min.x).max(f64::EPSILON),
+            (viewport.max.y - viewport.min.y).max(f64::EPSILON),
+        );
+        let transform = Transform {
+            min_x: viewport.min.x,
+            max_y: viewport.max.y,
+        };
+
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {width:.2} {height:.2}">"#,
+            self.width,
+            self.width * height / width,
+        );
+        let _ = write!(svg, "<title>{}</title>{DEFS}", escape(&lot.name));
+        let _ = write!(
+            svg,
+            r##"<rect width="{width:.2}" height="{height:.2}" fill="#ffffff"/>"##
+        );
+
+        let mut layers: BTreeMap<SvgLayer, Vec<&Feature>> = BTreeMap::new();
+        for feature in &features {
+            if let Some(layer) = SvgLayer::of(&feature.object_type) {
+                layers.entry(layer).or_default().push(feature);
+            }
+        }
+        let mut drawn: Vec<SvgLayer> = self.layers.clone();
+        drawn.sort();
+        drawn.dedup();
+        for layer in drawn {
+            let _ = write!(svg, r#"<g id="{layer:?}">"#);
+            match layer {
+                SvgLayer::Labels => self.labels(lot, &transform, &mut svg),
+                SvgLayer::Topology => self.topology(lot, &transform, &mut svg),
+                _ => {
+                    for feature in layers.get(&layer).into_iter().flatten() {
+                        self.feature(feature, &transform, &mut svg);
+                    }
+                }
+            }
+            svg.push_str("</g>");
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Renders the lot into an SVG file.
+    pub fn write_lot<P: AsRef<Path>>(&self, lot: &Lot, path: P) -> EdigeoResult<()> {
+        std::fs::write(path, self.render(lot))?;
+        Ok(())
+    }
+
+    fn feature(&self, feature: &Feature, transform: &Transform, svg: &mut String) {
+        let Some(geometry) = &feature.geometry else {
+            return;
+        };
+        let id = escape(&feature.id);
+        let style = style(feature);
+        // Strokes keep their width in pixels whatever the scale.
+        let scaling = r#"vector-effect="non-scaling-stroke""#;
+        match transform.geometry(geometry) {
+            Some(d) => {
+                let _ = write!(
+                    svg,
+                    r#"<path id="{id}" d="{d}" fill-rule="evenodd" {style} {scaling}/>"#
+                );
+            }
+            None => {
+                let coords = geometry.coords();
+                // Ids are unique: the circles of a multipoint share a group.
+                let grouped = coords.len() > 1;
+                if grouped {
+                    let _ = write!(svg, r#"<g id="{id}">"#);
+                }
+                for c in coords {
+                    let (x, y) = transform.point(c);
+                    svg.push_str("<circle");
+                    if !grouped {
+                        let _ = write!(svg, r#" id="{id}""#);
+                    }
+                    let _ = write!(
+                        svg,
+                        r#" cx="{x:.2}" cy="{y:.2}" r="0.4" {style} {scaling}/>"#
+                    );
+                }
+                if grouped {
+                    svg.push_str("</g>");
+                }
+            }
+        }
+    }
+
+    fn labels(&self, lot: &Lot, transform: &Transform, svg: &mut String) {
+        for label in lot.labels() {
+            let (Some(anchor), Some(text)) = (label.anchor, &label.text) else {
+                continue;
+            };
+            let (x, y) = transform.point(&anchor);
+            let anchor = match label.horizontal_alignment.as_deref() {
+                Some("3") => "middle",
+                Some("4") => "end",
+                _ => "start",
+            };
+            let baseline = match label.vertical_alignment.as_deref() {
+                Some("2" | "3") => "hanging",
+                Some("4") => "central",
+                Some("6") => "text-after-edge",
+                _ => "alphabetic",
+            };
+            let _ = write!(
+                svg,
+                r#"<text id="{}" x="{x:.2}" y="{y:.2}" font-family="{}" font-size="{:.2}" text-anchor="{anchor}" dominant-baseline="{baseline}""#,
+                escape(&label.id),
+                escape(label.font.as_deref().unwrap_or("serif")),
+                label.height.unwrap_or(2.0),
+            );
+            if label.rotation != 0.0 {
+                // Counterclockwise on the sheet, clockwise with Y pointing down.
+                let _ = write!(
+                    svg,
+                    r#" transform="rotate({:.2} {x:.2} {y:.2})""#,
+                    -label.rotation
+                );
+            }
+            let _ = write!(svg, ">{}</text>", escape(text));
+        }
+    }
+
+    fn topology(&self, lot: &Lot, transform: &Transform, svg: &mut String) {
+        // Records involved in an issue, with its description.
+        let mut issues: BTreeMap<(&str, &str), Vec<String>> = BTreeMap::new();
+        let report = lot.check_topology();
+        for issue in &report.issues {
+            let records: Vec<&str> = match &issue.kind {
+                TopologyIssueKind::UnknownMember { link, .. } => vec![link],
+                TopologyIssueKind::MissingArcEnd { arc, .. } => vec![arc],
+                TopologyIssueKind::ArcEndMismatch { arc, node, .. } => vec![arc, node],
+                TopologyIssueKind::OpenFace { arcs, .. } => {
+                    arcs.iter().map(String::as_str).collect()
+                }
+                TopologyIssueKind::ArcWithTooManyFaces { arc, .. } => vec![arc],
+            };
+            for record in records {
+                issues
+                    .entry((issue.subset.as_str(), record))
+                    .or_default()
+                    .push(issue.kind.to_string());
+            }
+        }
+
+        let scaling = r#"vector-effect="non-scaling-stroke""#;
+        let mut faulty = HashSet::new();
+        for (subset, vec) in &lot.subsets {
+            for arc in &vec.arcs {
+                let mut d = String::new();
+                transform.path(&arc.points, false, &mut d);
+                let id = escape(&format!("{subset}-{}", arc.id));
+                match issues.get(&(subset.as_str(), arc.id.as_str())) {
+                    Some(descriptions) => {
+                        faulty.insert((subset, &arc.id));
+                        let _ = write!(
+                            svg,
+                            r##"<path id="{id}" d="{d}" fill="none" stroke="#e00000" stroke-width="3" {scaling}><title>{}</title></path>"##,
+                            escape(&descriptions.join("\n"))
+                        );
+                    }
+                    None => {
+                        let _ = write!(
+                            svg,
+                            r##"<path id="{id}" d="{d}" fill="none" stroke="#2a7ab0" stroke-width="0.5" {scaling}/>"##
+                        );
+                    }
+                }
+            }
+            for node in &vec.nodes {
+                let Some(coord) = &node.coord else {
+                    continue;
+                };
+                let (x, y) = transform.point(coord);
+                let id = escape(&format!("{subset}-{}", node.id));
+                match issues.get(&(subset.as_str(), node.id.as_str())) {
+                    Some(descriptions) => {
+                        let _ = write!(
+                            svg,
+                            r##"<circle id="{id}" cx="{x:.2}" cy="{y:.2}" r="0.6" fill="#e00000"><title>{}</title></circle>"##,
+                            escape(&descriptions.join("\n"))
+                        );
+                    }
+                    None => {
+                        let _ = write!(
+                            svg,
+                            r##"<circle id="{id}" cx="{x:.2}" cy="{y:.2}" r="0.2" fill="#2a7ab0"/>"##
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{feature, text};
+
+    fn building(dur: &str) -> Feature {
+        feature("Objet_1", "BATIMENT_id", &[("DUR_id", text(dur))], None)
+    }
+
+    #[test]
+    fn test_styles() {
+        assert!(style(&building("01")).contains("url(#hard)"));
+        assert!(style(&building("02")).contains("url(#light)"));
+        assert_eq!(SvgLayer::of("PARCELLE_id"), Some(SvgLayer::Parcels));
+        assert_eq!(SvgLayer::of("ID_S_OBJ_Z_1_2_2"), None);
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+
+    #[test]
+    fn test_transform() {
+        let transform = Transform {
+            min_x: 100.0,
+            max_y: 50.0,
+        };
+        let ring = [
+            Coord { x: 100.0, y: 40.0 },
+            Coord { x: 110.0, y: 40.0 },
+            Coord { x: 110.0, y: 50.0 },
+            Coord { x: 100.0, y: 40.0 },
+        ];
+        assert_eq!(
+            transform.geometry(&Geometry::Polygon(vec![ring.to_vec()])),
+            Some("M0.00 10.00L10.00 10.00L10.00 0.00L0.00 10.00Z".to_string())
+        );
+        assert_eq!(transform.geometry(&Geometry::Point(ring[0])), None);
+    }
+
+    #[test]
+    fn test_multipoint_ids() {
+        let transform = Transform {
+            min_x: 0.0,
+            max_y: 10.0,
+        };
+        let renderer = SvgRenderer::default();
+        let point = |x| Coord { x, y: 5.0 };
+        let mut svg = String::new();
+        let multipoint = Geometry::MultiPoint(vec![point(1.0), point(2.0)]);
+        let borne = feature("Objet_1", "BORNE_id", &[], Some(multipoint));
+        renderer.feature(&borne, &transform, &mut svg);
+        assert!(svg.starts_with(r#"<g id="Objet_1"><circle cx="1.00" cy="5.00""#));
+        assert_eq!(svg.matches("<circle ").count(), 2);
+        assert_eq!(svg.matches("id=").count(), 1);
+
+        let mut svg = String::new();
+        let borne = feature(
+            "Objet_2",
+            "BORNE_id",
+            &[],
+            Some(Geometry::Point(point(1.0))),
+        );
+        renderer.feature(&borne, &transform, &mut svg);
+        assert!(svg.starts_with(r#"<circle id="Objet_2" cx="1.00""#));
+    }
+}
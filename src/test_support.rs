@@ -0,0 +1,98 @@
+//! Contains the records and features shared by the unit tests, all in the
+//! subset `SeTOP_1` of the lot `ED0A01`.
+use crate::geometry::{Coord, Geometry, LineString};
+use crate::lot::Feature;
+use crate::records::{Arc, Attribute, Face, Link, Node, Reference};
+use crate::FormatResult;
+
+pub(crate) fn coord(x: f64, y: f64) -> Coord {
+    Coord { x, y }
+}
+
+pub(crate) fn line(points: &[(f64, f64)]) -> LineString {
+    points.iter().map(|&(x, y)| coord(x, y)).collect()
+}
+
+pub(crate) fn text(value: &str) -> FormatResult {
+    FormatResult::Text(value.to_string())
+}
+
+/// A feature of the given type with the given attribute values.
+pub(crate) fn feature(
+    id: &str,
+    object_type: &str,
+    attributes: &[(&str, FormatResult)],
+    geometry: Option<Geometry>,
+) -> Feature {
+    Feature {
+        id: id.to_string(),
+        subset: "SeTOP_1".to_string(),
+        object_type: object_type.to_string(),
+        attributes: attributes
+            .iter()
+            .map(|(id, value)| Attribute {
+                id: id.to_string(),
+                value: Some(value.clone()),
+                raw: String::new(),
+            })
+            .collect(),
+        quality: None,
+        geometry,
+    }
+}
+
+pub(crate) fn reference(kind: &str, id: &str) -> Reference {
+    Reference {
+        lot: "ED0A01".to_string(),
+        subset: "SeTOP_1".to_string(),
+        kind: kind.to_string(),
+        id: id.to_string(),
+    }
+}
+
+/// A link of the given relation between the `(kind, id)` members, named
+/// after them: `Compo_Arc_1_Face_1`.
+pub(crate) fn link(relation: &str, members: &[(&str, &str)]) -> Link {
+    let ids: Vec<_> = members.iter().map(|(_, id)| *id).collect();
+    Link {
+        id: format!("Compo_{}", ids.join("_")),
+        definition: Some(reference("REL", relation)),
+        members: members
+            .iter()
+            .map(|(kind, id)| reference(kind, id))
+            .collect(),
+        direction: None,
+        attributes: vec![],
+    }
+}
+
+pub(crate) fn node(id: &str, coord: Coord) -> Node {
+    Node {
+        id: id.to_string(),
+        definition: None,
+        node_type: None,
+        coord: Some(coord),
+        attributes: vec![],
+        quality: None,
+    }
+}
+
+pub(crate) fn arc(id: &str, points: LineString) -> Arc {
+    Arc {
+        id: id.to_string(),
+        definition: None,
+        arc_type: None,
+        points,
+        attributes: vec![],
+        quality: None,
+    }
+}
+
+pub(crate) fn face(id: &str) -> Face {
+    Face {
+        id: id.to_string(),
+        definition: None,
+        attributes: vec![],
+        quality: None,
+    }
+}
@@ -0,0 +1,328 @@
+//! Contains the topological consistency checks of the `PNO`/`PAR`/`PFE`/`LNK`
+//! records of a [`VECFile`].
+use crate::geometry::Coord;
+use crate::lot::Lot;
+use crate::records::{Link, VECFile};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// A topological inconsistency, naming the RIDs of the records involved.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum TopologyIssueKind {
+    /// A link refers to a node, arc or face missing from the subset
+    UnknownMember {
+        /// RID of the `LNK` record
+        link: String,
+        /// RID of the missing record
+        member: String,
+    },
+    /// An arc has no `ID_S_RCO_NOD_INI` or `ID_S_RCO_NOD_FIN` link
+    MissingArcEnd {
+        /// RID of the `PAR` record
+        arc: String,
+        /// `INI` or `FIN`
+        end: String,
+    },
+    /// The first or last `COR` of an arc is not on its initial or final node
+    ArcEndMismatch {
+        /// RID of the `PAR` record
+        arc: String,
+        /// RID of the `PNO` record
+        node: String,
+        /// `INI` or `FIN`
+        end: String,
+    },
+    /// The left and right arcs of a face do not form closed rings
+    OpenFace {
+        /// RID of the `PFE` record
+        face: String,
+        /// RIDs of the arcs ending on an unmatched vertex
+        arcs: Vec<String>,
+    },
+    /// An arc bounds more than two faces
+    ArcWithTooManyFaces {
+        /// RID of the `PAR` record
+        arc: String,
+        /// RIDs of the faces it bounds
+        faces: Vec<String>,
+    },
+}
+
+impl fmt::Display for TopologyIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyIssueKind::UnknownMember { link, member } => {
+                write!(f, "{link} refers to unknown record {member}")
+            }
+            TopologyIssueKind::MissingArcEnd { arc, end } => {
+                write!(f, "{arc} has no {end} node")
+            }
+            TopologyIssueKind::ArcEndMismatch { arc, node, end } => {
+                write!(f, "{end} vertex of {arc} is not on {node}")
+            }
+            TopologyIssueKind::OpenFace { face, arcs } => {
+                write!(f, "{face} is not closed at {}", arcs.join(", "))
+            }
+            TopologyIssueKind::ArcWithTooManyFaces { arc, faces } => {
+                write!(
+                    f,
+                    "{arc} bounds {} faces: {}",
+                    faces.len(),
+                    faces.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// A topological inconsistency found in one subset of a lot.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopologyIssue {
+    /// Identifier of the subset, e.g. `SeTOP_1`
+    pub subset: String,
+    /// What is wrong and with which records
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub kind: TopologyIssueKind,
+}
+
+impl fmt::Display for TopologyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.subset, self.kind)
+    }
+}
+
+/// The outcome of [`Lot::check_topology`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopologyReport {
+    /// Name of the checked lot
+    pub lot: String,
+    /// Every issue found, by subset
+    pub issues: Vec<TopologyIssue>,
+}
+
+impl TopologyReport {
+    /// Returns `true` when no issue was found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Exact key of a vertex, arcs sharing a node share its coordinates.
+fn vertex(c: &Coord) -> (u64, u64) {
+    (c.x.to_bits(), c.y.to_bits())
+}
+
+fn relation(link: &Link) -> &str {
+    link.definition.as_ref().map_or("", |d| d.id.as_str())
+}
+
+impl VECFile {
+    /// Checks the topology of the subset.
+    ///
+    /// The first and last `COR` of every arc must lie on the nodes given by
+    /// its `ID_S_RCO_NOD_INI`/`_FIN` links, the arcs on the left and right of
+    /// every face must form closed rings and no arc may bound more than two
+    /// faces.
+    pub fn check_topology(&self) -> Vec<TopologyIssueKind> {
+        let nodes: HashMap<&str, Option<Coord>> = self
+            .nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node.coord))
+            .collect();
+        let arcs: HashMap<&str, &[Coord]> = self
+            .arcs
+            .iter()
+            .map(|arc| (arc.id.as_str(), arc.points.as_slice()))
+            .collect();
+        let faces: HashSet<&str> = self.faces.iter().map(|face| face.id.as_str()).collect();
+
+        let mut issues = Vec::new();
+        let mut arc_ends: HashMap<(&str, &str), &str> = HashMap::new();
+        let mut face_arcs: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut arc_faces: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+        for link in &self.links {
+            let member = |kind: &str| link.members.iter().find(|m| m.kind == kind);
+            let known = |id: &str, kind: &str| match kind {
+                "PNO" => nodes.contains_key(id),
+                "PAR" => arcs.contains_key(id),
+                "PFE" => faces.contains(id),
+                _ => true,
+            };
+            for unknown in link.members.iter().filter(|m| !known(&m.id, &m.kind)) {
+                issues.push(TopologyIssueKind::UnknownMember {
+                    link: link.id.clone(),
+                    member: unknown.id.clone(),
+                });
+            }
+
+            match (member("PAR"), member("PNO"), member("PFE")) {
+                (Some(arc), Some(node), None) => {
+                    let end = if relation(link).ends_with("FIN") {
+                        "FIN"
+                    } else {
+                        "INI"
+                    };
+                    arc_ends.insert((&arc.id, end), &node.id);
+                }
+                (Some(arc), None, Some(face)) => {
+                    face_arcs.entry(&face.id).or_default().push(&arc.id);
+                    let faces = arc_faces.entry(&arc.id).or_default();
+                    if !faces.contains(&face.id.as_str()) {
+                        faces.push(&face.id);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for arc in &self.arcs {
+            for (end, point) in [("INI", arc.points.first()), ("FIN", arc.points.last())] {
+                let Some(node) = arc_ends.get(&(arc.id.as_str(), end)) else {
+                    issues.push(TopologyIssueKind::MissingArcEnd {
+                        arc: arc.id.clone(),
+                        end: end.to_string(),
+                    });
+                    continue;
+                };
+                let node_coord = nodes.get(node).copied().flatten();
+                if let (Some(point), Some(node_coord)) = (point, node_coord) {
+                    if *point != node_coord {
+                        issues.push(TopologyIssueKind::ArcEndMismatch {
+                            arc: arc.id.clone(),
+                            node: node.to_string(),
+                            end: end.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        for face in &self.faces {
+            let bounding = face_arcs.get(face.id.as_str()).cloned().unwrap_or_default();
+            // Every vertex of a closed ring is the end of an even number of arcs.
+            let mut degrees: HashMap<(u64, u64), usize> = HashMap::new();
+            let ends = |arc: &&str| {
+                let points = arcs.get(arc).copied().unwrap_or_default();
+                points
+                    .first()
+                    .zip(points.last())
+                    .map(|(a, b)| (vertex(a), vertex(b)))
+            };
+            for (first, last) in bounding.iter().filter_map(ends) {
+                *degrees.entry(first).or_default() += 1;
+                *degrees.entry(last).or_default() += 1;
+            }
+            let mut open: Vec<String> = bounding
+                .iter()
+                .filter(|arc| {
+                    ends(arc).is_some_and(|(first, last)| {
+                        degrees[&first] % 2 == 1 || degrees[&last] % 2 == 1
+                    })
+                })
+                .map(|arc| arc.to_string())
+                .collect();
+            open.dedup();
+            if bounding.is_empty() || !open.is_empty() {
+                issues.push(TopologyIssueKind::OpenFace {
+                    face: face.id.clone(),
+                    arcs: open,
+                });
+            }
+        }
+
+        for (arc, faces) in arc_faces.into_iter().filter(|(_, faces)| faces.len() > 2) {
+            issues.push(TopologyIssueKind::ArcWithTooManyFaces {
+                arc: arc.to_string(),
+                faces: faces.into_iter().map(str::to_string).collect(),
+            });
+        }
+        issues
+    }
+}
+
+/// `STR` code of the `.GEN` file for spaghetti subsets.
+const SPAGHETTI: i32 = 3;
+
+impl Lot {
+    /// Checks the topology of every topological subset, see
+    /// [`VECFile::check_topology`].
+    ///
+    /// Subsets declared as spaghetti in the `.GEN` file (`STR` 3) hold
+    /// independent primitives and are skipped.
+    pub fn check_topology(&self) -> TopologyReport {
+        let spaghetti: HashSet<&str> = self
+            .gen
+            .subsets
+            .iter()
+            .filter(|subset| subset.structure == Some(SPAGHETTI))
+            .map(|subset| subset.id.as_str())
+            .collect();
+
+        TopologyReport {
+            lot: self.name.clone(),
+            issues: self
+                .subsets
+                .iter()
+                .filter(|(subset, _)| !spaghetti.contains(subset.as_str()))
+                .flat_map(|(subset, vec)| {
+                    vec.check_topology()
+                        .into_iter()
+                        .map(move |kind| TopologyIssue {
+                            subset: subset.clone(),
+                            kind,
+                        })
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{arc, coord, face, line, link, node};
+
+    #[test]
+    fn test_check_topology() {
+        let vec = VECFile {
+            nodes: vec![
+                node("Noeud_1", coord(0.0, 0.0)),
+                node("Noeud_2", coord(1.0, 0.0)),
+            ],
+            arcs: vec![
+                arc("Arc_1", line(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0)])),
+                arc("Arc_2", line(&[(1.0, 0.0), (0.5, 0.0)])),
+            ],
+            faces: vec![face("Face_1")],
+            objects: vec![],
+            links: vec![
+                link("ID_S_RCO_NOD_INI", &[("PAR", "Arc_1"), ("PNO", "Noeud_1")]),
+                link("ID_S_RCO_NOD_FIN", &[("PAR", "Arc_1"), ("PNO", "Noeud_2")]),
+                link("ID_S_RCO_NOD_INI", &[("PAR", "Arc_2"), ("PNO", "Noeud_1")]),
+                link("ID_S_RCO_FAC_GCHE", &[("PAR", "Arc_1"), ("PFE", "Face_1")]),
+                link("ID_S_RCO_FAC_DRTE", &[("PAR", "Arc_2"), ("PFE", "Face_1")]),
+            ],
+        };
+
+        let issues = vec.check_topology();
+        assert!(issues.contains(&TopologyIssueKind::ArcEndMismatch {
+            arc: "Arc_2".to_string(),
+            node: "Noeud_1".to_string(),
+            end: "INI".to_string(),
+        }));
+        assert!(issues.contains(&TopologyIssueKind::MissingArcEnd {
+            arc: "Arc_2".to_string(),
+            end: "FIN".to_string(),
+        }));
+        assert!(issues.contains(&TopologyIssueKind::OpenFace {
+            face: "Face_1".to_string(),
+            arcs: vec!["Arc_1".to_string(), "Arc_2".to_string()],
+        }));
+        assert_eq!(issues.len(), 3);
+    }
+}
@@ -0,0 +1,383 @@
+//! Contains the validation of the `FEA` objects of a [`Lot`] against the
+//! conceptual data model of its `.SCD` file.
+use crate::geometry::Geometry;
+use crate::lot::Lot;
+use crate::records::{Attribute, AttributeType, ObjectType};
+use crate::FormatResult;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A breach of the conceptual data model found on an object.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum IssueKind {
+    /// The object type is not declared by an `OBJ` descriptor
+    UnknownObjectType,
+    /// The attribute is not listed in the `AAP` zones of the object type
+    UndeclaredAttribute {
+        /// SCD identifier of the attribute
+        attribute: String,
+    },
+    /// The value is longer than the `CAN` length of the attribute type
+    ValueTooLong {
+        /// SCD identifier of the attribute
+        attribute: String,
+        /// Length of the value
+        length: usize,
+        /// Maximum length
+        max_length: usize,
+    },
+    /// The value has more decimals than the `CAD` of the attribute type
+    TooManyDecimals {
+        /// SCD identifier of the attribute
+        attribute: String,
+        /// Significant decimals of the value
+        decimals: usize,
+        /// Maximum number of decimals
+        max_decimals: usize,
+    },
+    /// The value lies outside the `AV1`/`AV2` bounds of the attribute type
+    ValueOutOfBounds {
+        /// SCD identifier of the attribute
+        attribute: String,
+        /// The value
+        value: f64,
+        /// Lower bound
+        min: Option<f64>,
+        /// Upper bound
+        max: Option<f64>,
+    },
+    /// The `ATC` count differs from the number of `ATP` attributes
+    AttributeCountMismatch {
+        /// Count declared by `ATC`
+        declared: i32,
+        /// Number of attributes found
+        found: usize,
+    },
+    /// The assembled geometry does not match the `KND` of the object type
+    GeometryKindMismatch {
+        /// Kind declared by the object type: `ARE`, `LIN` or `PCT`
+        expected: String,
+        /// Kind of the assembled geometry, `None` when it has no geometry
+        found: Option<String>,
+    },
+}
+
+/// A validation issue located on one object of a lot.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Issue {
+    /// Identifier of the subset holding the object
+    pub subset: String,
+    /// Record identifier of the object, e.g. `Objet_243907`
+    pub record: String,
+    /// Object type in the SCD, e.g. `PARCELLE_id`
+    pub object_type: String,
+    /// What is wrong with the object
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} ({}): ",
+            self.subset, self.record, self.object_type
+        )?;
+        match &self.kind {
+            IssueKind::UnknownObjectType => write!(f, "unknown object type"),
+            IssueKind::UndeclaredAttribute { attribute } => {
+                write!(f, "undeclared attribute {attribute}")
+            }
+            IssueKind::ValueTooLong {
+                attribute,
+                length,
+                max_length,
+            } => write!(
+                f,
+                "{attribute} is {length} long, at most {max_length} allowed"
+            ),
+            IssueKind::TooManyDecimals {
+                attribute,
+                decimals,
+                max_decimals,
+            } => write!(
+                f,
+                "{attribute} has {decimals} decimals, at most {max_decimals} allowed"
+            ),
+            IssueKind::ValueOutOfBounds {
+                attribute,
+                value,
+                min,
+                max,
+            } => write!(f, "{attribute} = {value} is outside [{min:?}, {max:?}]"),
+            IssueKind::AttributeCountMismatch { declared, found } => {
+                write!(f, "ATC declares {declared} attributes, {found} found")
+            }
+            IssueKind::GeometryKindMismatch { expected, found } => write!(
+                f,
+                "expected {expected} geometry, found {}",
+                found.as_deref().unwrap_or("none")
+            ),
+        }
+    }
+}
+
+/// The outcome of [`Lot::validate`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidationReport {
+    /// Name of the validated lot
+    pub lot: String,
+    /// Number of objects checked
+    pub objects: usize,
+    /// Every issue found, in file order
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// Returns `true` when no issue was found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Returns the `KND` matching a geometry.
+fn geometry_kind(geometry: &Geometry) -> &'static str {
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => "PCT",
+        Geometry::LineString(_) | Geometry::MultiLineString(_) => "LIN",
+        Geometry::Polygon(_) | Geometry::MultiPolygon(_) => "ARE",
+    }
+}
+
+/// Returns a numeric value or bound, `None` for text and empty bounds.
+fn number(value: &FormatResult) -> Option<f64> {
+    match value {
+        FormatResult::Float(value) => Some(*value),
+        FormatResult::Int(value) => Some(*value as f64),
+        _ => None,
+    }
+}
+
+/// Checks one attribute value against its attribute type, returning every
+/// issue found.
+///
+/// The `CAN` length and `CAD` decimals are measured on the value as written,
+/// without its sign. Trailing zeros are not significant decimals: IGN writes
+/// `+2.500000` for attributes declaring 3.
+fn check_value(attribute: &Attribute, schema: &AttributeType) -> Vec<IssueKind> {
+    let mut issues = Vec::new();
+    let Some(value) = &attribute.value else {
+        return issues;
+    };
+    let raw = attribute.raw.trim_start_matches(['+', '-']);
+
+    let length = raw.chars().count();
+    if let Some(max_length) = schema.max_length.filter(|max| *max > 0) {
+        if length > max_length as usize {
+            issues.push(IssueKind::ValueTooLong {
+                attribute: attribute.id.clone(),
+                length,
+                max_length: max_length as usize,
+            });
+        }
+    }
+
+    let Some(value) = number(value) else {
+        return issues;
+    };
+    if let (Some(max_decimals), Some((_, fraction))) = (schema.decimals, raw.split_once('.')) {
+        let decimals = fraction.trim_end_matches('0').len();
+        if decimals > max_decimals.max(0) as usize {
+            issues.push(IssueKind::TooManyDecimals {
+                attribute: attribute.id.clone(),
+                decimals,
+                max_decimals: max_decimals.max(0) as usize,
+            });
+        }
+    }
+    let min = schema.min.as_ref().and_then(number);
+    let max = schema.max.as_ref().and_then(number);
+    if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+        issues.push(IssueKind::ValueOutOfBounds {
+            attribute: attribute.id.clone(),
+            value,
+            min,
+            max,
+        });
+    }
+    issues
+}
+
+impl Lot {
+    /// Validates every `FEA` object of the lot against the `.SCD` file.
+    ///
+    /// Reports objects of undeclared types, attributes not listed by their
+    /// object type, values exceeding the length, decimals or bounds of their attribute
+    /// type, `ATC` counts that differ from the attributes found and assembled
+    /// geometries that do not match the `KND` of the object type.
+    pub fn validate(&self) -> ValidationReport {
+        let object_types: HashMap<&str, &ObjectType> = self
+            .scd
+            .objects
+            .iter()
+            .map(|object| (object.id.as_str(), object))
+            .collect();
+        let attribute_types: HashMap<&str, &AttributeType> = self
+            .scd
+            .attributes
+            .iter()
+            .map(|attribute| (attribute.id.as_str(), attribute))
+            .collect();
+        let features = self.features();
+        let geometries: HashMap<(&str, &str), Option<&Geometry>> = features
+            .iter()
+            .map(|f| ((f.subset.as_str(), f.id.as_str()), f.geometry.as_ref()))
+            .collect();
+
+        let mut report = ValidationReport {
+            lot: self.name.clone(),
+            ..Default::default()
+        };
+        for (subset, vec) in &self.subsets {
+            for object in &vec.objects {
+                report.objects += 1;
+                let object_type = object
+                    .definition
+                    .as_ref()
+                    .map_or("", |definition| definition.id.as_str());
+                let mut issue = |kind| {
+                    report.issues.push(Issue {
+                        subset: subset.clone(),
+                        record: object.id.clone(),
+                        object_type: object_type.to_string(),
+                        kind,
+                    })
+                };
+
+                if let Some(declared) = object.attribute_count {
+                    if usize::try_from(declared).ok() != Some(object.attributes.len()) {
+                        issue(IssueKind::AttributeCountMismatch {
+                            declared,
+                            found: object.attributes.len(),
+                        });
+                    }
+                }
+
+                let Some(schema) = object_types.get(object_type) else {
+                    issue(IssueKind::UnknownObjectType);
+                    continue;
+                };
+
+                for attribute in &object.attributes {
+                    if !schema.attributes.iter().any(|aap| aap.id == attribute.id) {
+                        issue(IssueKind::UndeclaredAttribute {
+                            attribute: attribute.id.clone(),
+                        });
+                    }
+                    if let Some(attribute_type) = attribute_types.get(attribute.id.as_str()) {
+                        check_value(attribute, attribute_type)
+                            .into_iter()
+                            .for_each(&mut issue);
+                    }
+                }
+
+                if matches!(schema.kind.as_str(), "ARE" | "LIN" | "PCT") {
+                    let found = geometries
+                        .get(&(subset.as_str(), object.id.as_str()))
+                        .copied()
+                        .flatten()
+                        .map(geometry_kind);
+                    if found != Some(schema.kind.as_str()) {
+                        issue(IssueKind::GeometryKindMismatch {
+                            expected: schema.kind.clone(),
+                            found: found.map(str::to_string),
+                        });
+                    }
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute_type(max_length: i32, min: Option<f64>, max: Option<f64>) -> AttributeType {
+        AttributeType {
+            id: "SUPF_id".to_string(),
+            definition: None,
+            max_length: Some(max_length),
+            decimals: Some(2),
+            unit: None,
+            min: min.map(FormatResult::Float),
+            max: max.map(FormatResult::Float),
+        }
+    }
+
+    /// An attribute parsed from its raw value as a float, or as text.
+    fn attribute(raw: &str) -> Attribute {
+        Attribute {
+            id: "SUPF_id".to_string(),
+            value: Some(match raw.parse() {
+                Ok(value) => FormatResult::Float(value),
+                Err(_) => FormatResult::Text(raw.to_string()),
+            }),
+            raw: raw.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_value() {
+        let schema = attribute_type(5, Some(0.0), Some(1000.0));
+        assert_eq!(check_value(&attribute("+12."), &schema), vec![]);
+        assert!(matches!(
+            check_value(&attribute("+1001"), &schema)[..],
+            [IssueKind::ValueOutOfBounds { .. }]
+        ));
+        assert!(matches!(
+            check_value(&attribute("ABCDEF"), &schema)[..],
+            [IssueKind::ValueTooLong {
+                length: 6,
+                max_length: 5,
+                ..
+            }]
+        ));
+        // The length is measured on the raw value, not on `12.5`.
+        assert!(matches!(
+            check_value(&attribute("+12.500"), &schema)[..],
+            [IssueKind::ValueTooLong { length: 6, .. }]
+        ));
+        // Every issue of the value is reported.
+        assert!(matches!(
+            check_value(&attribute("+1000.125"), &schema)[..],
+            [
+                IssueKind::ValueTooLong { length: 8, .. },
+                IssueKind::TooManyDecimals { decimals: 3, .. },
+                IssueKind::ValueOutOfBounds { .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_check_decimals() {
+        let schema = attribute_type(10, None, None);
+        assert_eq!(check_value(&attribute("+2.50"), &schema), vec![]);
+        assert_eq!(check_value(&attribute("-2.500000"), &schema), vec![]);
+        assert_eq!(
+            check_value(&attribute("+2.125"), &schema),
+            vec![IssueKind::TooManyDecimals {
+                attribute: "SUPF_id".to_string(),
+                decimals: 3,
+                max_decimals: 2,
+            }]
+        );
+        // Text values have no decimals.
+        assert_eq!(check_value(&attribute("A.BCD"), &schema), vec![]);
+    }
+}
@@ -0,0 +1,211 @@
+//! Contains the serialisation of a [`Geometry`] to WKT, WKB and the PostGIS
+//! EWKB flavour carrying a SRID.
+//!
+//! ```no_run
+//! use edigeo::*;
+//!
+//! let lot = Lot::from_bundle(&EdigeoReader::new("data/edigeo.tar.bz2").read_bundle()).unwrap();
+//! for feature in lot.features() {
+//!     let ewkb = feature.geometry.map(|geometry| geometry.to_ewkb_hex(lot.srid()));
+//!     println!("{}\t{}\t{:?}", feature.id, feature.object_type, ewkb);
+//! }
+//! ```
+use crate::geometry::{Coord, Geometry, LineString, Polygon};
+
+/// Flag of the EWKB geometry type announcing a SRID.
+const EWKB_SRID_FLAG: u32 = 0x2000_0000;
+
+impl Geometry {
+    /// Returns the OGC well known text of the geometry, e.g. `POINT (1 2)` or
+    /// `POLYGON EMPTY`.
+    pub fn to_wkt(&self) -> String {
+        let coord = |c: &Coord| format!("{} {}", c.x, c.y);
+        // Empty geometries and parts are written `EMPTY`, e.g. `POLYGON EMPTY`.
+        let list = |items: Vec<String>| {
+            if items.is_empty() {
+                "EMPTY".to_string()
+            } else {
+                format!("({})", items.join(", "))
+            }
+        };
+        let line = |line: &LineString| list(line.iter().map(coord).collect());
+        let polygon = |polygon: &Polygon| list(polygon.iter().map(line).collect());
+
+        match self {
+            Geometry::Point(c) => format!("POINT ({})", coord(c)),
+            Geometry::MultiPoint(points) => format!(
+                "MULTIPOINT {}",
+                list(points.iter().map(|c| format!("({})", coord(c))).collect())
+            ),
+            Geometry::LineString(points) => format!("LINESTRING {}", line(points)),
+            Geometry::MultiLineString(lines) => {
+                format!("MULTILINESTRING {}", list(lines.iter().map(line).collect()))
+            }
+            Geometry::Polygon(rings) => format!("POLYGON {}", polygon(rings)),
+            Geometry::MultiPolygon(polygons) => {
+                format!(
+                    "MULTIPOLYGON {}",
+                    list(polygons.iter().map(polygon).collect())
+                )
+            }
+        }
+    }
+
+    /// Returns the little endian OGC well known binary of the geometry.
+    pub fn to_wkb(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        write_wkb(&mut buffer, self, None);
+        buffer
+    }
+
+    /// Returns the little endian PostGIS extended WKB of the geometry, with
+    /// the SRID stored in the header when given.
+    pub fn to_ewkb(&self, srid: Option<u32>) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        write_wkb(&mut buffer, self, srid);
+        buffer
+    }
+
+    /// Returns the EWKB of the geometry as upper case hexadecimal, the text
+    /// representation accepted by PostGIS for `geometry` columns in `COPY`.
+    pub fn to_ewkb_hex(&self, srid: Option<u32>) -> String {
+        self.to_ewkb(srid)
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect()
+    }
+}
+
+fn write_header(buffer: &mut Vec<u8>, kind: u32, srid: Option<u32>) {
+    buffer.push(1);
+    match srid {
+        Some(srid) => {
+            buffer.extend((kind | EWKB_SRID_FLAG).to_le_bytes());
+            buffer.extend(srid.to_le_bytes());
+        }
+        None => buffer.extend(kind.to_le_bytes()),
+    }
+}
+
+fn write_coords(buffer: &mut Vec<u8>, coords: &[Coord]) {
+    buffer.extend((coords.len() as u32).to_le_bytes());
+    for c in coords {
+        buffer.extend(c.x.to_le_bytes());
+        buffer.extend(c.y.to_le_bytes());
+    }
+}
+
+fn write_rings(buffer: &mut Vec<u8>, rings: &[LineString]) {
+    buffer.extend((rings.len() as u32).to_le_bytes());
+    for ring in rings {
+        write_coords(buffer, ring);
+    }
+}
+
+/// Writes a geometry, only the outermost one carrying the SRID.
+fn write_wkb(buffer: &mut Vec<u8>, geometry: &Geometry, srid: Option<u32>) {
+    match geometry {
+        Geometry::Point(c) => {
+            write_header(buffer, 1, srid);
+            buffer.extend(c.x.to_le_bytes());
+            buffer.extend(c.y.to_le_bytes());
+        }
+        Geometry::LineString(points) => {
+            write_header(buffer, 2, srid);
+            write_coords(buffer, points);
+        }
+        Geometry::Polygon(rings) => {
+            write_header(buffer, 3, srid);
+            write_rings(buffer, rings);
+        }
+        Geometry::MultiPoint(points) => {
+            write_header(buffer, 4, srid);
+            buffer.extend((points.len() as u32).to_le_bytes());
+            for point in points {
+                write_wkb(buffer, &Geometry::Point(*point), None);
+            }
+        }
+        Geometry::MultiLineString(lines) => {
+            write_header(buffer, 5, srid);
+            buffer.extend((lines.len() as u32).to_le_bytes());
+            for line in lines {
+                write_header(buffer, 2, None);
+                write_coords(buffer, line);
+            }
+        }
+        Geometry::MultiPolygon(polygons) => {
+            write_header(buffer, 6, srid);
+            buffer.extend((polygons.len() as u32).to_le_bytes());
+            for polygon in polygons {
+                write_header(buffer, 3, None);
+                write_rings(buffer, polygon);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Polygon {
+        vec![[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]
+            .iter()
+            .map(|&(x, y)| Coord { x, y })
+            .collect()]
+    }
+
+    #[test]
+    fn test_to_wkt() {
+        let point = Geometry::Point(Coord {
+            x: 967887.24,
+            y: 6558363.3,
+        });
+        assert_eq!(point.to_wkt(), "POINT (967887.24 6558363.3)");
+
+        let multi = Geometry::MultiPolygon(vec![square(), square()]);
+        assert_eq!(
+            multi.to_wkt(),
+            "MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((0 0, 1 0, 1 1, 0 0)))"
+        );
+    }
+
+    #[test]
+    fn test_empty_to_wkt() {
+        assert_eq!(Geometry::Polygon(vec![]).to_wkt(), "POLYGON EMPTY");
+        assert_eq!(Geometry::MultiPoint(vec![]).to_wkt(), "MULTIPOINT EMPTY");
+        assert_eq!(Geometry::LineString(vec![]).to_wkt(), "LINESTRING EMPTY");
+        assert_eq!(
+            Geometry::MultiLineString(vec![]).to_wkt(),
+            "MULTILINESTRING EMPTY"
+        );
+        assert_eq!(
+            Geometry::MultiPolygon(vec![vec![], square()]).to_wkt(),
+            "MULTIPOLYGON (EMPTY, ((0 0, 1 0, 1 1, 0 0)))"
+        );
+    }
+
+    #[test]
+    fn test_to_ewkb() {
+        let point = Geometry::Point(Coord { x: 1.0, y: 2.0 });
+        assert_eq!(
+            point.to_wkb(),
+            [
+                vec![1, 1, 0, 0, 0],
+                1.0f64.to_le_bytes().to_vec(),
+                2.0f64.to_le_bytes().to_vec()
+            ]
+            .concat()
+        );
+        assert!(point
+            .to_ewkb_hex(Some(2154))
+            .starts_with("01010000206A080000"));
+
+        let polygon = Geometry::Polygon(square());
+        // Header, SRID, ring count, point count and four points.
+        assert_eq!(
+            polygon.to_ewkb(Some(2154)).len(),
+            1 + 4 + 4 + 4 + 4 + 4 * 16
+        );
+    }
+}
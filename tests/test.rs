@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::Read;
 
 use edigeo::*;
@@ -54,3 +55,976 @@ fn edigeo_read_thf() {
 
     assert_eq!(thf, e.thf);
 }
+
+#[test]
+fn lot_assembles_features() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let layers = lot.layers();
+
+    assert_eq!(lot.name, "ED0A01");
+    assert_eq!(layers["PARCELLE_id"].len(), 404);
+    assert!(layers["PARCELLE_id"]
+        .iter()
+        .all(|parcel| matches!(parcel.geometry, Some(Geometry::Polygon(_)))));
+}
+
+#[test]
+fn write_shapefiles() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let dir = std::env::temp_dir().join("edigeo-shapefiles");
+
+    let written = shapefile::ShapefileWriter::new(shapefile::DbfEncoding::Utf8)
+        .write_lot(&lot, &dir)
+        .unwrap();
+
+    assert!(written.contains(&dir.join("PARCELLE.shp")));
+    for extension in ["shx", "dbf", "prj", "cpg"] {
+        assert!(dir.join("PARCELLE").with_extension(extension).exists());
+    }
+}
+
+#[test]
+fn primitives_to_ewkb() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    assert_eq!(lot.srid(), Some(2154));
+
+    let primitives = lot.primitives();
+    let faces: Vec<_> = primitives.iter().filter(|p| p.kind == "PFE").collect();
+    assert!(faces
+        .iter()
+        .filter(|face| face.geometry.is_none())
+        .all(|face| face.id == "Face_0"));
+
+    let face = faces
+        .iter()
+        .find_map(|face| face.geometry.as_ref())
+        .unwrap();
+    assert!(face.to_wkt().starts_with("POLYGON (("));
+    assert_eq!(
+        &face.to_ewkb(lot.srid())[1..9],
+        &[3, 0, 0, 0x20, 0x6A, 0x08, 0, 0]
+    );
+}
+
+#[cfg(feature = "geozero")]
+#[test]
+fn lot_to_geojson() {
+    use geozero::{geojson::GeoJsonWriter, GeozeroDatasource};
+
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let mut lot = Lot::from_bundle(&bundle).unwrap();
+    let mut out = Vec::new();
+    lot.process(&mut GeoJsonWriter::new(&mut out)).unwrap();
+
+    let json = String::from_utf8(out).unwrap();
+    assert!(json.contains("\"name\": \"ED0A01\""));
+    assert!(json.contains("\"object_type\": \"PARCELLE\""));
+    assert_eq!(
+        json.matches("\"type\": \"Feature\"").count(),
+        lot.features().len()
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn lot_serde_round_trip() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+
+    let json = serde_json::to_string(&lot).unwrap();
+    assert_eq!(serde_json::from_str::<Lot>(&json).unwrap(), lot);
+
+    let line = Line::parse_line("RIDSA06:Arc_12").unwrap();
+    let json = serde_json::to_string(&line.header.code).unwrap();
+    assert_eq!(json, "\"RID\"");
+    assert_eq!(
+        serde_json::from_str::<Code>(&json).unwrap(),
+        line.header.code
+    );
+}
+
+#[test]
+fn validate_lot_against_scd() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let mut lot = Lot::from_bundle(&bundle).unwrap();
+
+    let report = lot.validate();
+    assert_eq!(report.objects, 1223);
+    // IGN writes the direction cosines of 88 labels with up to 6 decimals
+    // where the SCD declares 3.
+    assert_eq!(report.issues.len(), 4 * 88);
+    assert!(report.issues.iter().all(|issue| matches!(
+        &issue.kind,
+        IssueKind::TooManyDecimals { attribute, max_decimals: 3, .. }
+            if attribute.starts_with("ID_S_ATT_DI")
+    )));
+
+    let object = &mut lot.subsets.get_mut("SeTOP_1").unwrap().objects[0];
+    object.attributes.push(Attribute {
+        id: "NOT_DECLARED_id".to_string(),
+        value: None,
+        raw: String::new(),
+    });
+    let report = lot.validate();
+    let kinds: Vec<_> = report.issues.iter().map(|issue| &issue.kind).collect();
+    assert!(kinds.contains(&&IssueKind::UndeclaredAttribute {
+        attribute: "NOT_DECLARED_id".to_string()
+    }));
+    assert!(kinds
+        .iter()
+        .any(|kind| matches!(kind, IssueKind::AttributeCountMismatch { .. })));
+
+    let object = &mut lot.subsets.get_mut("SeTOP_1").unwrap().objects[1];
+    object.attribute_count = Some(-1);
+    let report = lot.validate();
+    assert!(report.issues.iter().any(|issue| matches!(
+        issue.kind,
+        IssueKind::AttributeCountMismatch { declared: -1, .. }
+    )));
+}
+
+#[test]
+fn check_lot_topology() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let mut lot = Lot::from_bundle(&bundle).unwrap();
+    assert!(lot.check_topology().is_valid());
+
+    let vec = lot.subsets.get_mut("SeTOP_1").unwrap();
+    let arc = vec.arcs[0].id.clone();
+    vec.arcs[0].points.pop();
+    let report = lot.check_topology();
+    assert!(!report.is_valid());
+    assert!(report.issues.iter().any(|issue| matches!(
+        &issue.kind,
+        TopologyIssueKind::ArcEndMismatch { arc: id, end, .. } if *id == arc && end == "FIN"
+    )));
+}
+
+#[test]
+fn bundle_counts_match() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    assert!(bundle.check_counts().unwrap().is_empty());
+}
+
+#[test]
+fn truncated_bundle_rejected() {
+    let mut bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    // Drop a coordinate of the first arc, as a corrupted transfer would.
+    let find = |data: &[u8], from: usize, pattern: &[u8]| {
+        from + data[from..]
+            .windows(pattern.len())
+            .position(|w| w == pattern)
+            .unwrap()
+    };
+    let start = find(&bundle.t1, find(&bundle.t1, 0, b"\nPTC"), b"\nCOR") + 1;
+    let end = find(&bundle.t1, start, b"\n") + 1;
+    bundle.t1.drain(start..end);
+
+    assert!(matches!(
+        Lot::from_bundle(&bundle),
+        Err(edigeo::error::EdigeoError::CountMismatch(_))
+    ));
+    let mut diagnostics = edigeo::diagnostics::Diagnostics::default();
+    Lot::from_bundle_lenient(&bundle, ValueSize::Check, &mut diagnostics);
+    assert!(!diagnostics.is_empty() && !diagnostics.has_errors());
+}
+
+#[test]
+fn value_sizes_checked_on_bytes() {
+    // The directory copy of the lot was re-saved as UTF-8, so accented values
+    // are longer than their declared size.
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01/").read_bundle();
+    match Lot::from_bundle(&bundle) {
+        Err(edigeo::error::EdigeoError::AtLine { source, .. }) => {
+            assert!(matches!(
+                *source,
+                edigeo::error::EdigeoError::ValueSizeMismatch { .. }
+            ))
+        }
+        other => panic!("expected a value size mismatch, got {other:?}"),
+    }
+
+    let lot = Lot::from_bundle_with(&bundle, ValueSize::Ignore).unwrap();
+    assert_eq!(lot.name, "ED0A01");
+}
+
+#[test]
+fn lenient_lot_reports_diagnostics() {
+    use edigeo::diagnostics::{DiagnosticKind, Diagnostics};
+
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01/").read_bundle();
+    let mut diagnostics = Diagnostics::default();
+    let lot = Lot::from_bundle_lenient(&bundle, ValueSize::Check, &mut diagnostics);
+
+    assert_eq!(lot.name, "ED0A01");
+    assert!(!diagnostics.is_empty());
+    assert!(!diagnostics.has_errors());
+    assert!(diagnostics.iter().all(|d| matches!(
+        d.kind,
+        DiagnosticKind::Recovered { .. } | DiagnosticKind::Encoding
+    )));
+    let misencoded: Vec<_> = diagnostics
+        .iter()
+        .filter(|d| d.kind == DiagnosticKind::Encoding)
+        .filter_map(|d| d.file.as_deref())
+        .collect();
+    assert_eq!(misencoded, ["THF", "S1", "DIC"]);
+
+    let expected = Lot::from_bundle_with(&bundle, ValueSize::Ignore).unwrap();
+    assert_eq!(lot, expected);
+}
+
+#[test]
+fn bundle_from_memory() {
+    let data = std::fs::read("data/edigeo-740240000A01.tar.bz2").unwrap();
+    let from_file = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+
+    let from_bytes = bundle::EdigeoBundle::from_bytes(&data).unwrap();
+    assert_eq!(from_bytes.thf, from_file.thf);
+    assert_eq!(from_bytes.scd, from_file.scd);
+
+    let from_reader = bundle::EdigeoBundle::from_reader(std::io::Cursor::new(data)).unwrap();
+    assert_eq!(from_reader.t1, from_file.t1);
+
+    let lot = Lot::from_bundle(&from_reader).unwrap();
+    assert_eq!(lot.name, "ED0A01");
+}
+
+/// Returns the files of the test lot, unpacked from its `.tar.bz2`.
+fn lot_files() -> Vec<(String, Vec<u8>)> {
+    let data = std::fs::read("data/edigeo-740240000A01.tar.bz2").unwrap();
+    archive::unpack("lot.tar.bz2", data).unwrap()
+}
+
+fn tar_of(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, name.rsplit('/').next().unwrap(), &data[..])
+            .unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn read_other_archive_formats() {
+    use std::io::Write;
+
+    let files = lot_files();
+    assert_eq!(files.len(), 10);
+
+    // Plain tar and tar.gz
+    let tar = tar_of(&files);
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    gz.write_all(&tar).unwrap();
+    let tar_gz = gz.finish().unwrap();
+    for data in [&tar, &tar_gz] {
+        let bundle = bundle::EdigeoBundle::from_bytes(data).unwrap();
+        assert_eq!(Lot::from_bundle(&bundle).unwrap().name, "ED0A01");
+    }
+
+    // Zip with lower case names in a sub directory
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    for (name, data) in &files {
+        let name = name.rsplit('/').next().unwrap().to_lowercase();
+        zip.start_file(format!("ed0a01/{name}"), options).unwrap();
+        zip.write_all(data).unwrap();
+    }
+    let zip = zip.finish().unwrap().into_inner();
+    let bundle = bundle::EdigeoBundle::from_bytes(&zip).unwrap();
+    assert_eq!(
+        bundle.t1,
+        files.iter().find(|(n, _)| n.ends_with("T1.VEC")).unwrap().1
+    );
+
+    // Department dump: a tar holding one tar.bz2 per sheet
+    let sheet = std::fs::read("data/edigeo-740240000A01.tar.bz2").unwrap();
+    let dump = tar_of(&[
+        ("A01.tar.bz2".to_string(), sheet.clone()),
+        ("A02.tar.bz2".to_string(), sheet),
+    ]);
+    assert_eq!(
+        archive::read_bundles("dump.tar", dump.clone())
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(matches!(
+        bundle::EdigeoBundle::from_bytes(&dump),
+        Err(edigeo::error::EdigeoError::InvalidArchive(_))
+    ));
+}
+
+#[test]
+fn stream_bundles_of_dump() {
+    use edigeo::error::EdigeoError;
+
+    let sheet = std::fs::read("data/edigeo-740240000A01.tar.bz2").unwrap();
+    let dump = tar_of(&[
+        ("A01.tar.bz2".to_string(), sheet.clone()),
+        ("A02.tar.bz2".to_string(), sheet),
+    ]);
+    let mut names = Vec::new();
+    archive::for_each_bundle(
+        "dump.tar",
+        &dump[..],
+        archive::MAX_UNPACKED_SIZE,
+        &mut (),
+        |bundle| {
+            names.push(bundle.name.unwrap());
+            Ok(())
+        },
+    )
+    .unwrap();
+    assert_eq!(names, ["dump.tar/A01.tar", "dump.tar/A02.tar"]);
+
+    // The walk stops at the first error, before the second sheet is read.
+    let mut read = 0;
+    let result = archive::for_each_bundle(
+        "dump.tar",
+        &dump[..],
+        archive::MAX_UNPACKED_SIZE,
+        &mut (),
+        |_| {
+            read += 1;
+            Err(EdigeoError::IncompleteBundle)
+        },
+    );
+    assert!(matches!(result, Err(EdigeoError::IncompleteBundle)));
+    assert_eq!(read, 1);
+
+    assert!(matches!(
+        archive::for_each_bundle("dump.tar", &dump[..], 100_000, &mut (), |_| Ok(())),
+        Err(EdigeoError::InvalidArchive(_))
+    ));
+}
+
+#[test]
+fn try_read_bundle_errors() {
+    use edigeo::error::EdigeoError;
+
+    let dir = std::env::temp_dir().join("edigeo-try-read");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let sheet = std::fs::read("data/edigeo-740240000A01.tar.bz2").unwrap();
+    let dump = dir.join("dump.tar");
+    std::fs::write(
+        &dump,
+        tar_of(&[
+            ("A01.tar.bz2".to_string(), sheet.clone()),
+            ("A02.tar.bz2".to_string(), sheet.clone()),
+        ]),
+    )
+    .unwrap();
+    assert!(matches!(
+        EdigeoReader::new(&dump).try_read_bundle(),
+        Err(EdigeoError::InvalidArchive(_))
+    ));
+    assert_eq!(EdigeoReader::new(&dump).read_bundles().unwrap().len(), 2);
+
+    let corrupt = dir.join("corrupt.tar.bz2");
+    std::fs::write(&corrupt, &sheet[..sheet.len() / 2]).unwrap();
+    assert!(EdigeoReader::new(&corrupt).try_read_bundle().is_err());
+
+    let incomplete = dir.join("incomplete");
+    std::fs::create_dir_all(&incomplete).unwrap();
+    std::fs::write(incomplete.join("E0000A01.THF"), b"BOMT 12:E0000A01.THF").unwrap();
+    assert!(matches!(
+        EdigeoReader::new(&incomplete).try_read_bundle(),
+        Err(EdigeoError::IncompleteBundle)
+    ));
+    assert!(matches!(
+        EdigeoReader::new(incomplete.join("E0000A01.THF")).try_read_bundle(),
+        Err(EdigeoError::IncompleteBundle)
+    ));
+    assert!(matches!(
+        EdigeoReader::with_dir(dir.join("missing")).try_read_bundle(),
+        Err(EdigeoError::IoError(_))
+    ));
+}
+
+#[test]
+fn reader_reports_incomplete_lots() {
+    use edigeo::diagnostics::{DiagnosticKind, Diagnostics, Severity};
+
+    let files = lot_files();
+    let mut tar = tar::Builder::new(Vec::new());
+    for (name, data) in &files {
+        let name = name.rsplit('/').next().unwrap();
+        for directory in ["A01", "A02"] {
+            // A02 lacks everything but its `.THF`.
+            if directory == "A02" && !name.ends_with(".THF") {
+                continue;
+            }
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, format!("{directory}/{name}"), &data[..])
+                .unwrap();
+        }
+    }
+    let path = std::env::temp_dir().join("edigeo-incomplete.tar");
+    std::fs::write(&path, tar.into_inner().unwrap()).unwrap();
+
+    let mut diagnostics = Diagnostics::default();
+    let bundle = EdigeoReader::new(&path)
+        .read_bundle_with(&mut diagnostics)
+        .unwrap();
+    assert_eq!(bundle.name.as_deref(), Some("edigeo-incomplete.tar/A01"));
+
+    let diagnostics = diagnostics.into_vec();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(
+        diagnostics[0].file.as_deref(),
+        Some("edigeo-incomplete.tar/A02")
+    );
+    assert!(matches!(
+        diagnostics[0].kind,
+        DiagnosticKind::Recovered { .. }
+    ));
+}
+
+#[test]
+fn lot_labels() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let labels = lot.labels();
+
+    assert!(labels.iter().all(|label| label.anchor.is_some()));
+    let parcel = labels
+        .iter()
+        .find(|label| label.id == "Attribut_TEX_id_Objet_243368")
+        .unwrap();
+    assert_eq!(parcel.text.as_deref(), Some("33"));
+    assert_eq!(parcel.object_type.as_deref(), Some("PARCELLE_id"));
+    assert_eq!(parcel.font.as_deref(), Some("Times New Roman"));
+    assert_eq!(parcel.height, Some(2.0));
+    assert_eq!(parcel.rotation, 0.0);
+}
+
+#[cfg(feature = "pci")]
+#[test]
+fn lot_into_cadastre() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let cadastre = Lot::from_bundle(&bundle).unwrap().cadastre();
+
+    assert_eq!(cadastre.parcelles.len(), 404);
+    assert!(cadastre
+        .parcelles
+        .iter()
+        .all(|p| p.idu.starts_with("0240000A")));
+    assert_eq!(cadastre.batiments.len(), 81);
+    assert_eq!(cadastre.bornes.len(), 113);
+    assert_eq!(cadastre.communes[0].idu, "024");
+    assert_eq!(cadastre.communes[0].tex2.as_deref(), Some("AYSE"));
+    assert_eq!(cadastre.sections[0].tex.as_deref(), Some("A"));
+    assert_eq!(cadastre.subdivisions[0].eor, Some(2000));
+    assert_eq!(cadastre.lieux_dits.len(), 9);
+    assert_eq!(cadastre.others.len(), 504);
+}
+
+#[test]
+fn lot_parcel_idus() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let sheet = bundle.sheet().unwrap();
+    assert_eq!(sheet.to_string(), "740240000A01");
+
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let idus = lot.parcel_idus().unwrap();
+    assert_eq!(idus.len(), 404);
+    assert!(idus
+        .iter()
+        .all(|(_, idu)| idu.to_string().len() == 14 && idu.insee() == "74024"));
+    assert!(idus
+        .iter()
+        .any(|(id, idu)| id == "Objet_243368" && idu.to_string() == "740240000A0033"));
+
+    let files = bundle::EdigeoBundle::from_files(lot_files()).unwrap();
+    let lot = Lot::from_bundle(&files).unwrap();
+    assert!(matches!(
+        lot.parcel_idus(),
+        Err(edigeo::error::EdigeoError::InvalidIdentifier(_))
+    ));
+    assert_eq!(lot.parcel_idus_in("74").unwrap(), idus);
+}
+
+#[cfg(feature = "rstar")]
+#[test]
+fn lot_spatial_queries() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+
+    // The anchor of the number of parcel 33 lies inside the parcel.
+    let anchor = Coord {
+        x: 965008.83,
+        y: 6560831.71,
+    };
+    let parcels: Vec<_> = lot
+        .query_point(&anchor)
+        .into_iter()
+        .filter(|entry| entry.object_type.as_deref() == Some("PARCELLE_id"))
+        .collect();
+    assert_eq!(parcels.len(), 1);
+    assert_eq!(parcels[0].id, "Objet_243368");
+
+    let (nearest, distance) = lot
+        .nearest(&anchor)
+        .find(|(entry, _)| entry.object_type.as_deref() == Some("BATIMENT_id"))
+        .unwrap();
+    assert!(distance > 0.0);
+    let bbox = BoundingBox {
+        min: Coord {
+            x: anchor.x - distance - 1.0,
+            y: anchor.y - distance - 1.0,
+        },
+        max: Coord {
+            x: anchor.x + distance + 1.0,
+            y: anchor.y + distance + 1.0,
+        },
+    };
+    let in_bbox = lot.query_bbox(&bbox);
+    assert!(in_bbox.iter().any(|entry| entry.id == nearest.id));
+    assert!(in_bbox.iter().any(|entry| entry.kind == "PAR"));
+}
+
+#[cfg(all(feature = "rstar", feature = "serde"))]
+#[test]
+fn spatial_index_serialized_with_lot() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let size = lot.index().len();
+
+    let json = serde_json::to_string(&lot).unwrap();
+    let restored = serde_json::from_str::<Lot>(&json).unwrap();
+    assert!(restored.index.get().is_some());
+    assert_eq!(restored.index().len(), size);
+    assert_eq!(restored, lot);
+}
+
+#[cfg(feature = "rstar")]
+#[test]
+fn spatial_index_not_part_of_lot() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let size = lot.index().len();
+    assert_eq!(lot, lot.clone());
+    assert!(lot.clone().index.get().is_none());
+
+    // The merged lot indexes its prefixed records, not those of the first lot.
+    let merged = Lot::merge(std::slice::from_ref(&lot), 0.01).unwrap();
+    assert_eq!(merged.index().len(), size);
+    let bbox = lot.features()[0].geometry.as_ref().unwrap().bbox().unwrap();
+    let entries = merged.query_bbox(&bbox);
+    assert!(!entries.is_empty());
+    assert!(entries.iter().all(|entry| entry.id.starts_with("ED0A01_")));
+}
+
+#[test]
+fn merge_lot_with_itself() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let merged = Lot::merge(&[lot.clone(), lot.clone()], 0.01).unwrap();
+
+    let count = |lot: &Lot| -> [usize; 4] {
+        let vecs = lot.subsets.values();
+        [
+            vecs.clone().map(|vec| vec.nodes.len()).sum(),
+            vecs.clone().map(|vec| vec.arcs.len()).sum(),
+            vecs.clone().map(|vec| vec.faces.len()).sum(),
+            vecs.map(|vec| vec.objects.len()).sum(),
+        ]
+    };
+    let [nodes, arcs, faces, objects] = count(&lot);
+    let outer_faces = lot
+        .primitives()
+        .iter()
+        .filter(|primitive| primitive.kind == "PFE" && primitive.geometry.is_none())
+        .count();
+    // The copy only adds the faces surrounding the sheet: nodes, arcs and
+    // faces are merged.
+    assert_eq!(count(&merged)[..3], [nodes, arcs, faces + outer_faces]);
+
+    // Its objects running along the sheet boundary, such as the commune,
+    // are dissolved, but not its points nor the objects inside the sheet.
+    let types = |lot: &Lot| -> HashMap<String, usize> {
+        let mut types = HashMap::new();
+        for feature in lot.features() {
+            *types.entry(feature.object_type).or_default() += 1;
+        }
+        types
+    };
+    let (original, copies) = (types(&lot), types(&merged));
+    for object_type in ["COMMUNE_id", "SECTION_id", "LIEUDIT_id"] {
+        assert_eq!(copies[object_type], original[object_type]);
+    }
+    assert_eq!(copies["BORNE_id"], 2 * original["BORNE_id"]);
+    assert!(copies["PARCELLE_id"] > original["PARCELLE_id"]);
+    assert!(copies["PARCELLE_id"] < 2 * original["PARCELLE_id"]);
+    assert!(count(&merged)[3] < 2 * objects);
+
+    let geometries = |lot: &Lot| -> HashMap<String, Option<Geometry>> {
+        lot.features()
+            .into_iter()
+            .map(|feature| (feature.id, feature.geometry))
+            .collect()
+    };
+    let original = geometries(&lot);
+    let merged = geometries(&merged);
+    assert!(original
+        .iter()
+        .all(|(id, geometry)| merged.get(&format!("ED0A01_{id}")) == Some(geometry)));
+}
+
+#[test]
+fn diff_lot_editions() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let old = Lot::from_bundle(&bundle).unwrap();
+    assert!(old.diff(&old).is_empty());
+
+    // The next edition renumbers its records, updates the surface of a
+    // parcel and demolishes a building.
+    let mut new = old.clone();
+    for vec in new.subsets.values_mut() {
+        for object in &mut vec.objects {
+            object.id = format!("{}_2", object.id);
+        }
+        for link in &mut vec.links {
+            for member in link.members.iter_mut().filter(|m| m.kind == "FEA") {
+                member.id = format!("{}_2", member.id);
+            }
+        }
+    }
+    let parcel = new
+        .subsets
+        .values_mut()
+        .flat_map(|vec| &mut vec.objects)
+        .find(|object| object.id == "Objet_243368_2")
+        .unwrap();
+    let attribute = parcel
+        .attributes
+        .iter_mut()
+        .find(|attribute| attribute.id == "SUPF_id")
+        .unwrap();
+    attribute.value = Some(FormatResult::Int(1234));
+    let quality = parcel.quality.clone().unwrap();
+    let quality = new
+        .qal
+        .qualities
+        .iter_mut()
+        .find(|q| q.id == quality.id)
+        .unwrap();
+    quality.updated = chrono::NaiveDate::from_ymd_opt(2099, 1, 1);
+    let building = new
+        .features()
+        .into_iter()
+        .find(|feature| feature.object_type == "BATIMENT_id")
+        .unwrap();
+    for vec in new.subsets.values_mut() {
+        vec.objects.retain(|object| object.id != building.id);
+    }
+
+    let diff = old.diff(&new);
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(format!("{}_2", diff.removed[0].id), building.id);
+    assert_eq!(diff.modified.len(), 1);
+    let change = &diff.modified[0];
+    assert_eq!(change.new.id, "Objet_243368_2");
+    assert_eq!(change.attributes.len(), 1);
+    assert_eq!(change.attributes[0].id, "SUPF_id");
+    assert_eq!(change.attributes[0].new, Some(FormatResult::Int(1234)));
+    assert_eq!(change.geometry, None);
+    assert!(change.is_dated());
+}
+
+#[test]
+fn write_csv_tables() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let dir = std::env::temp_dir().join("edigeo-csv");
+
+    let writer = csv::CsvWriter {
+        geometry: csv::CsvGeometry::Centroid,
+        decode_values: true,
+        ..csv::CsvWriter::default()
+    };
+    let written = writer.write_lot(&lot, &dir).unwrap();
+    assert!(written.contains(&dir.join("PARCELLE.csv")));
+
+    let parcels = std::fs::read_to_string(dir.join("PARCELLE.csv")).unwrap();
+    let mut lines = parcels.lines();
+    assert_eq!(lines.next(), Some("ID,COAR,IDU,INDP,SUPF,TEX,X,Y"));
+    assert_eq!(lines.count(), 404);
+    assert!(parcels.contains(",0240000A0033,Parcelle figurée au plan,"));
+
+    let buildings = std::fs::read_to_string(dir.join("BATIMENT.csv")).unwrap();
+    assert!(buildings
+        .lines()
+        .skip(1)
+        .all(|line| line.contains(",Bâti ")));
+
+    let writer = csv::CsvWriter {
+        geometry: csv::CsvGeometry::Wkt,
+        ..csv::CsvWriter::new(b'\t')
+    };
+    let written = writer.write_lot(&lot, &dir).unwrap();
+    assert!(written.contains(&dir.join("PARCELLE.tsv")));
+    let parcels = std::fs::read_to_string(dir.join("PARCELLE.tsv")).unwrap();
+    assert!(parcels.starts_with("ID\tCOAR\tIDU\tINDP\tSUPF\tTEX\tWKT\r\n"));
+    assert!(parcels.contains("\tPOLYGON (("));
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn lot_to_geoparquet() {
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let batches = lot.to_record_batches().unwrap();
+    let parcels = &batches["PARCELLE_id"];
+    assert_eq!(parcels.num_rows(), 404);
+    let schema = parcels.schema();
+    let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(
+        names,
+        ["ID", "LOT", "SHEET", "COAR", "IDU", "INDP", "SUPF", "TEX", "geometry"]
+    );
+    assert_eq!(parcels.column(8).null_count(), 0);
+
+    // A department is written as a stream of lots.
+    let dir = std::env::temp_dir().join("edigeo-parquet");
+    let mut writer = arrow::GeoParquetWriter::new(&dir).unwrap();
+    writer.write_lot(&lot).unwrap();
+    writer.write_lot(&lot).unwrap();
+    // The files hold the projection of the first lot.
+    let mut other = lot.clone();
+    other.geo.reference.code = Some("RGF93CC46".to_string());
+    assert!(matches!(
+        writer.write_lot(&other),
+        Err(edigeo::error::EdigeoError::IncompatibleLots(_))
+    ));
+    let written = writer.finish().unwrap();
+    assert!(written.contains(&dir.join("PARCELLE.parquet")));
+
+    let file = std::fs::File::open(dir.join("PARCELLE.parquet")).unwrap();
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+    let geo = builder
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|metadata| metadata.iter().find(|kv| kv.key == "geo"))
+        .and_then(|kv| kv.value.clone())
+        .unwrap();
+    let geo: serde_json::Value = serde_json::from_str(&geo).unwrap();
+    let column = &geo["columns"]["geometry"];
+    assert_eq!(column["encoding"], "WKB");
+    assert_eq!(column["crs"]["id"]["code"], 2154);
+    assert!(column["geometry_types"]
+        .as_array()
+        .unwrap()
+        .contains(&"Polygon".into()));
+    assert_eq!(column["bbox"].as_array().unwrap().len(), 4);
+
+    let rows: usize = builder
+        .build()
+        .unwrap()
+        .map(|b| b.unwrap().num_rows())
+        .sum();
+    assert_eq!(rows, 808);
+}
+
+#[test]
+fn render_svg_sheet() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+
+    let renderer = svg::SvgRenderer::default();
+    // The GEN extent of the sample lot misses its features.
+    let viewport = renderer.viewport(&lot, &lot.features()).unwrap();
+    assert!(viewport.min.x > 960000.0 && viewport.max.y < 6570000.0);
+
+    let svg = renderer.render(&lot);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"1200\""));
+    assert!(svg.contains("<g id=\"Parcels\">") && svg.contains("<g id=\"Labels\">"));
+    assert!(!svg.contains("<g id=\"Topology\">"));
+    assert_eq!(
+        svg.matches("fill=\"#ffffff\" stroke=\"#000000\" stroke-width=\"0.8\"")
+            .count(),
+        404
+    );
+    assert_eq!(
+        svg.matches("url(#hard)").count() + svg.matches("url(#light)").count(),
+        81
+    );
+    assert!(svg.matches("<text ").count() > 400);
+
+    let renderer = svg::SvgRenderer {
+        layers: vec![svg::SvgLayer::Topology],
+        ..renderer
+    };
+    let path = std::env::temp_dir().join("edigeo-topology.svg");
+    renderer.write_lot(&lot, &path).unwrap();
+    let svg = std::fs::read_to_string(path).unwrap();
+    let arcs: usize = lot.subsets.values().map(|vec| vec.arcs.len()).sum();
+    assert_eq!(svg.matches("<path ").count(), arcs);
+    assert!(!svg.contains("<g id=\"Parcels\">"));
+    let ids: Vec<_> = svg.split(" id=\"").skip(1).collect();
+    let unique: std::collections::HashSet<_> = ids.iter().map(|id| id.split('"').next()).collect();
+    assert_eq!(unique.len(), ids.len());
+}
+
+#[test]
+fn lot_to_vector_tiles() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+
+    let tileset = mvt::MvtTiler::new(14, 16).tile_lots(&[lot]).unwrap();
+    let bounds = tileset.bounds.unwrap();
+    assert!(bounds.min.x > 6.4 && bounds.max.x < 6.5);
+    assert!(bounds.min.y > 46.0 && bounds.max.y < 46.2);
+    assert_eq!(tileset.layers["PARCELLE"]["SUPF"], "Number");
+    assert!(tileset.tiles.keys().any(|tile| tile.z == 14));
+    assert!(tileset.tiles.keys().all(|tile| (14..=16).contains(&tile.z)));
+    // Every tile starts with a layer, field 3 of the protobuf `Tile`.
+    assert!(tileset.tiles.values().all(|data| data[0] == 0x1a));
+
+    let dir = std::env::temp_dir().join("edigeo-tiles");
+    let written = tileset.write_dir(&dir).unwrap();
+    assert_eq!(written, tileset.tiles.len());
+    let tile = tileset.tiles.keys().next().unwrap();
+    assert!(dir
+        .join(format!("{}/{}/{}.pbf", tile.z, tile.x, tile.y))
+        .exists());
+    let tilejson: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("metadata.json")).unwrap()).unwrap();
+    assert_eq!(tilejson["minzoom"], 14);
+    assert!(tilejson["vector_layers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|layer| layer["id"] == "BATIMENT"));
+}
+
+#[cfg(feature = "mbtiles")]
+#[test]
+fn lot_to_mbtiles() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+    let tileset = mvt::MvtTiler::new(13, 15).tile_lots(&[lot]).unwrap();
+
+    let path = std::env::temp_dir().join("edigeo.mbtiles");
+    let _ = std::fs::remove_file(&path);
+    tileset.write_mbtiles(&path).unwrap();
+    // Writing again replaces the tiles.
+    tileset.write_mbtiles(&path).unwrap();
+
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    let count: usize = connection
+        .query_row("SELECT count(*) FROM tiles", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, tileset.tiles.len());
+    let format: String = connection
+        .query_row(
+            "SELECT value FROM metadata WHERE name = 'format'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(format, "pbf");
+
+    let tile = tileset.tiles.keys().next().unwrap();
+    let data: Vec<u8> = connection
+        .query_row(
+            "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+            (tile.z, tile.x, (1u32 << tile.z) - 1 - tile.y),
+            |row| row.get(0),
+        )
+        .unwrap();
+    // Gzip magic number.
+    assert_eq!(data[..2], [0x1f, 0x8b]);
+}
+
+#[cfg(feature = "pci")]
+#[test]
+fn lot_to_inspire_gml() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+
+    let mut gml = Vec::new();
+    inspire::InspireWriter::default()
+        .write(&lot, &mut gml)
+        .unwrap();
+    let gml = String::from_utf8(gml).unwrap();
+    assert!(gml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<wfs:FeatureCollection"));
+    assert_eq!(gml.matches("<cp:CadastralParcel ").count(), 404);
+    assert_eq!(gml.matches("<cp:CadastralZoning ").count(), 3);
+    assert!(gml.matches("<cp:CadastralBoundary ").count() > 404);
+    assert!(gml.contains(concat!(
+        r#"<cp:CadastralParcel gml:id="FR.PCI.CadastralParcel.740240000A0033">"#,
+        r#"<cp:areaValue uom="m2">37054</cp:areaValue>"#
+    )));
+    assert!(gml
+        .contains("<cp:nationalCadastralReference>740240000A0033</cp:nationalCadastralReference>"));
+    assert!(gml.contains(r#"srsName="http://www.opengis.net/def/crs/EPSG/0/2154""#));
+    assert!(gml.contains(r##"<cp:zoning xlink:href="#FR.PCI.CadastralZoning.740240000A01"/>"##));
+    assert_eq!(
+        gml.matches("<wfs:member>").count(),
+        gml.matches("</wfs:member>").count()
+    );
+
+    // Without a sheet name the department must be given.
+    let mut lot = lot;
+    lot.sheet = None;
+    assert!(matches!(
+        inspire::InspireWriter::default().write(&lot, &mut Vec::new()),
+        Err(edigeo::error::EdigeoError::InvalidIdentifier(_))
+    ));
+    let writer = inspire::InspireWriter {
+        departement: Some("74".to_string()),
+        boundaries: false,
+        ..inspire::InspireWriter::default()
+    };
+    let mut gml = Vec::new();
+    writer.write(&lot, &mut gml).unwrap();
+    assert!(!String::from_utf8(gml)
+        .unwrap()
+        .contains("CadastralBoundary"));
+}
+
+#[test]
+fn write_dxf() {
+    let bundle = EdigeoReader::new("data/edigeo-740240000A01.tar.bz2").read_bundle();
+    let lot = Lot::from_bundle(&bundle).unwrap();
+
+    let path = std::env::temp_dir().join("edigeo-740240000A01.dxf");
+    dxf::DxfWriter::default().write_lot(&lot, &path).unwrap();
+    let dxf = std::fs::read_to_string(path).unwrap();
+    assert!(dxf.starts_with("  0\nSECTION\n  2\nHEADER\n  9\n$ACADVER\n  1\nAC1009\n"));
+    assert!(dxf.ends_with("  0\nENDSEC\n  0\nEOF\n"));
+    assert!(dxf.contains("  0\nLAYER\n  2\nBATIMENT\n 70\n0\n 62\n1\n"));
+    assert_eq!(
+        dxf.matches("  0\nINSERT\n  8\nBORNE\n  2\nBORNE\n").count(),
+        113
+    );
+    assert_eq!(dxf.matches("  0\nTEXT\n  8\nPARCELLE_TEXT\n").count(), 404);
+    assert_eq!(dxf.matches("  0\nPOLYLINE\n  8\nBATIMENT\n").count(), 81);
+    assert!(dxf.contains("M\\U+00F4le"));
+
+    let writer = dxf::DxfWriter {
+        labels: false,
+        ..dxf::DxfWriter::default()
+    };
+    let mut out = Vec::new();
+    writer.write(&lot, &mut out).unwrap();
+    assert!(!String::from_utf8(out).unwrap().contains("TEXT"));
+}